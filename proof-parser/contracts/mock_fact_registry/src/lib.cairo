@@ -0,0 +1,43 @@
+#[starknet::interface]
+pub trait IMockFactRegistry<TContractState> {
+    fn verify_and_register_fact(ref self: TContractState, proof: Span<felt252>);
+    fn is_valid(self: @TContractState, fact: felt252) -> bool;
+}
+
+/// Registers the Poseidon hash of any proof as a fact without verifying it, to exercise
+/// the registration flow against a devnet.
+#[starknet::contract]
+mod MockFactRegistry {
+    use core::poseidon::poseidon_hash_span;
+    use starknet::storage::{Map, StorageMapReadAccess, StorageMapWriteAccess};
+
+    #[storage]
+    struct Storage {
+        facts: Map<felt252, bool>,
+    }
+
+    #[event]
+    #[derive(Drop, starknet::Event)]
+    enum Event {
+        FactRegistered: FactRegistered,
+    }
+
+    #[derive(Drop, starknet::Event)]
+    struct FactRegistered {
+        #[key]
+        fact: felt252,
+    }
+
+    #[abi(embed_v0)]
+    impl MockFactRegistryImpl of super::IMockFactRegistry<ContractState> {
+        fn verify_and_register_fact(ref self: ContractState, proof: Span<felt252>) {
+            let fact = poseidon_hash_span(proof);
+            self.facts.write(fact, true);
+            self.emit(FactRegistered { fact });
+        }
+
+        fn is_valid(self: @ContractState, fact: felt252) -> bool {
+            self.facts.read(fact)
+        }
+    }
+}
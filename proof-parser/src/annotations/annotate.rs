@@ -0,0 +1,155 @@
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    channel::replay_interaction,
+    error::Result,
+    stark_proof::{StarkProof, StarkWitness},
+};
+
+const AIR: &str = "/cpu air/STARK";
+
+/// Stone-style annotation lines for `proof`, as `--generate_annotations` would print them.
+///
+/// The interaction elements are replayed from the commitments with Integrity's Poseidon
+/// channel, so they match Stone's only for proofs generated with that channel. Row and column
+/// details are omitted, and all authentication nodes are annotated as hashes.
+pub fn annotate(proof: &StarkProof) -> Result<Vec<String>> {
+    let commitment = &proof.unsent_commitment;
+    let witness = StarkWitness::from(proof.witness.clone());
+
+    let (interaction_elements, _) = replay_interaction(&proof.public_input, commitment)?;
+
+    let mut lines = Lines::default();
+    lines.sent(
+        "Original/Commit on Trace: Commitment",
+        "Hash",
+        &[commitment.traces.original],
+    );
+    for (i, element) in interaction_elements.iter().enumerate() {
+        lines.0.push(format!(
+            "V->P: {AIR}/Interaction: Interaction element #{i}: Field Element({element:#x})"
+        ));
+    }
+    lines.sent(
+        "Interaction/Commit on Trace: Commitment",
+        "Hash",
+        &[commitment.traces.interaction],
+    );
+    lines.sent(
+        "Out Of Domain Sampling/Commit on Trace: Commitment",
+        "Hash",
+        &[commitment.composition],
+    );
+    lines.sent_list(
+        "Out Of Domain Sampling/OODS values",
+        &commitment.oods_values,
+    );
+    for (i, layer) in commitment.fri.inner_layers.iter().enumerate() {
+        lines.sent(
+            &format!("FRI/Commitment/Layer {}: Commitment", i + 1),
+            "Hash",
+            &[*layer],
+        );
+    }
+    lines.sent_list(
+        "FRI/Commitment/Last Layer: Coefficients",
+        &commitment.fri.last_layer_coefficients,
+    );
+    lines.sent_nonce(commitment.proof_of_work_nonce);
+
+    for (trace, leaves, authentications) in [
+        (
+            0,
+            &witness.original_leaves,
+            &witness.original_authentications,
+        ),
+        (
+            1,
+            &witness.interaction_leaves,
+            &witness.interaction_authentications,
+        ),
+        (
+            2,
+            &witness.composition_leaves,
+            &witness.composition_authentications,
+        ),
+    ] {
+        let path = format!("FRI/Decommitment/Layer 0/Virtual Oracle/Trace {trace}");
+        lines.sent(&path, "Field Element", leaves);
+        lines.sent(&path, "Hash", authentications);
+    }
+    for (i, layer) in witness.fri_witness.layers.iter().enumerate() {
+        let path = format!("FRI/Decommitment/Layer {}", i + 1);
+        lines.sent(&path, "Field Element", &layer.leaves);
+        lines.sent(&path, "Hash", &layer.table_witness);
+    }
+
+    Ok(lines.0)
+}
+
+// Annotation lines with the byte offsets of the prover's messages.
+#[derive(Default)]
+struct Lines(Vec<String>, usize);
+
+impl Lines {
+    fn push(&mut self, path: &str, n_bytes: usize, value: String) {
+        let begin = self.1;
+        self.1 += n_bytes;
+        self.0
+            .push(format!("P->V[{begin}:{}]: {AIR}/{path}: {value}", self.1));
+    }
+
+    fn sent(&mut self, path: &str, kind: &str, values: &[Felt]) {
+        for value in values {
+            self.push(path, 32, format!("{kind}({value:#x})"));
+        }
+    }
+
+    fn sent_list(&mut self, path: &str, values: &[Felt]) {
+        let list = values
+            .iter()
+            .map(|v| format!("{v:#x}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        self.push(path, 32 * values.len(), format!("Field Elements({list})"));
+    }
+
+    fn sent_nonce(&mut self, nonce: Felt) {
+        self.push(
+            "FRI/Proof of Work: POW Nonce",
+            8,
+            format!("Data({nonce:#x})"),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::{proof_from_annotations, ProofJSON};
+
+    #[test]
+    fn test_annotate_round_trip() {
+        let proof = StarkProof::sample();
+        let mut value: serde_json::Value =
+            serde_json::from_str(&proof.to_annotated_stone_json().unwrap()).unwrap();
+        // The sample has fewer OODS values than the recursive layout's mask.
+        value["public_input"]["dynamic_params"] = serde_json::json!({ "mask_len": [2] });
+        let annotated = ProofJSON::from_value(value).unwrap();
+        let annotations = annotated.parsed_annotations().unwrap();
+
+        let (interaction_elements, _) =
+            replay_interaction(&proof.public_input, &proof.unsent_commitment).unwrap();
+        assert_eq!(
+            annotations.interaction_elements,
+            interaction_elements
+                .iter()
+                .map(Felt::to_biguint)
+                .collect::<Vec<_>>()
+        );
+
+        let reparsed = proof_from_annotations(annotated).unwrap();
+        assert_eq!(reparsed.unsent_commitment, proof.unsent_commitment);
+        assert_eq!(reparsed.witness, proof.witness);
+    }
+}
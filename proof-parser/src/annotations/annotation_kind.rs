@@ -1,6 +1,7 @@
 use num_bigint::BigUint;
 
-use super::extract::{extract_annotations, extract_z_and_alpha};
+use super::extract::{extract_z_and_alpha, AnnotationIndex, FRI_LAYERS_COMMITMENTS};
+use crate::error::Result;
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct ZAlpha {
@@ -9,7 +10,7 @@ pub struct ZAlpha {
 }
 
 impl ZAlpha {
-    pub fn extract(annotations: &[&str]) -> anyhow::Result<Self> {
+    pub fn extract(annotations: &[&str]) -> Result<Self> {
         extract_z_and_alpha(annotations)
     }
 }
@@ -33,16 +34,13 @@ pub enum Annotation {
 }
 
 impl Annotation {
-    pub fn extract(&self, annotations: &[&str]) -> anyhow::Result<Vec<BigUint>> {
+    pub fn extract(&self, index: &AnnotationIndex) -> Vec<BigUint> {
         let PrefixAndKind { prefix, kinds } = self.prefix_and_kinds();
-        Ok(kinds
+        kinds
             .to_strs()
             .iter()
-            .map(|k| extract_annotations(annotations, &prefix, k))
-            .collect::<anyhow::Result<Vec<_>>>()?
-            .into_iter()
-            .flatten()
-            .collect())
+            .flat_map(|k| index.get(&prefix, k))
+            .collect()
     }
 
     pub fn prefix_and_kinds(&self) -> PrefixAndKind {
@@ -64,7 +62,7 @@ impl Annotation {
                 kinds: AnnotationKinds::FieldElements,
             },
             Annotation::FriLayersCommitments => PrefixAndKind {
-                prefix: "STARK/FRI/Commitment/Layer [0-9]+".to_string(),
+                prefix: FRI_LAYERS_COMMITMENTS.to_string(),
                 kinds: AnnotationKinds::Hash,
             },
             Annotation::FriLastLayerCoefficients => PrefixAndKind {
@@ -1,7 +1,9 @@
+use std::collections::HashMap;
+
 use num_bigint::BigUint;
-use regex::Regex;
 
 use super::annotation_kind::ZAlpha;
+use crate::error::{Error, Result};
 
 pub trait FromStrHex: Sized {
     fn from_str_hex(val: &str) -> Option<Self>;
@@ -17,57 +19,160 @@ impl FromStrHex for BigUint {
     }
 }
 
-pub fn extract_z_and_alpha(annotations: &[&str]) -> anyhow::Result<ZAlpha> {
-    let re = Regex::new(
-        r"V->P: /cpu air/STARK/Interaction: Interaction element #\d+: Field Element\(0x([0-9a-f]+)\)",
-    ).unwrap();
+const INTERACTION_ELEMENT: &str = "V->P: /cpu air/STARK/Interaction: Interaction element #";
+const FRI_LAYER_COMMITMENT: &str = "STARK/FRI/Commitment/Layer ";
+
+/// Path under which all FRI layer commitments are indexed, see `AnnotationIndex::new`.
+pub const FRI_LAYERS_COMMITMENTS: &str = "STARK/FRI/Commitment/Layer [0-9]+";
+
+/// Prover annotations grouped by `/cpu air/` path and value kind, built in a single pass
+/// over the lines. Values keep the order of the lines they came from.
+#[derive(Debug, Default)]
+pub struct AnnotationIndex<'a> {
+    values: HashMap<(&'a str, &'a str), Vec<&'a str>>,
+    interaction_elements: Vec<&'a str>,
+}
 
-    let mut interaction_elements = Vec::new();
+impl<'a> AnnotationIndex<'a> {
+    pub fn new(annotations: &[&'a str]) -> Self {
+        let mut index = Self::default();
 
-    for annotation in annotations {
-        for cap in re.captures_iter(annotation) {
-            match BigUint::from_str_hex(&cap[1]) {
-                Some(value) => interaction_elements.push(value),
-                None => anyhow::bail!("Unable to parse"),
+        for line in annotations {
+            if let Some(rest) = line.strip_prefix(INTERACTION_ELEMENT) {
+                if let Some((_, value)) = split_value(rest) {
+                    index.interaction_elements.push(value);
+                }
+            } else if let Some((path, kind, value)) = parse_sent(line) {
+                // The FRI layer commitments are extracted together, in line order.
+                let path = match path.strip_prefix(FRI_LAYER_COMMITMENT) {
+                    Some(layer) if layer.bytes().all(|b| b.is_ascii_digit()) => {
+                        FRI_LAYERS_COMMITMENTS
+                    }
+                    _ => path,
+                };
+                index.values.entry((path, kind)).or_default().push(value);
             }
         }
+
+        index
     }
 
-    // Make sure the number of interaction_elements is as expected
-    if ![3, 6].contains(&interaction_elements.len()) {
-        anyhow::bail!(
-            "Unexpected number of interaction elements: {}",
-            interaction_elements.len()
-        );
+    /// Values of `kind` annotated under `path`. `Field Elements` lists are flattened.
+    pub fn get(&self, path: &str, kind: &str) -> Vec<BigUint> {
+        let values = self.values.get(&(path, kind)).map(Vec::as_slice);
+        let values = values.unwrap_or_default().iter();
+
+        if kind == "Field Elements" {
+            values
+                .flat_map(|v| v.split(','))
+                .filter_map(BigUint::from_str_hex)
+                .collect()
+        } else {
+            values.filter_map(|&v| BigUint::from_str_hex(v)).collect()
+        }
     }
 
-    let z_alpha = ZAlpha {
-        z: interaction_elements[0].clone(),
-        alpha: interaction_elements[1].clone(),
-    };
+    pub fn interaction_elements(&self) -> Result<Vec<BigUint>> {
+        let interaction_elements = self
+            .interaction_elements
+            .iter()
+            .map(|v| {
+                BigUint::from_str_hex(v).ok_or(Error::Annotations(format!("Unable to parse {v}")))
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-    Ok(z_alpha)
+        // Make sure the number of interaction_elements is as expected
+        if ![3, 6].contains(&interaction_elements.len()) {
+            return Err(Error::Annotations(format!(
+                "Unexpected number of interaction elements: {}",
+                interaction_elements.len()
+            )));
+        }
+
+        Ok(interaction_elements)
+    }
+
+    pub fn z_and_alpha(&self) -> Result<ZAlpha> {
+        let interaction_elements = self.interaction_elements()?;
+        Ok(ZAlpha {
+            z: interaction_elements[0].clone(),
+            alpha: interaction_elements[1].clone(),
+        })
+    }
 }
 
-pub fn extract_annotations(
-    annotations: &[&str],
-    prefix: &str,
-    kind: &str,
-) -> anyhow::Result<Vec<BigUint>> {
-    let pattern = format!(r"P->V\[(\d+):(\d+)\]: /cpu air/{prefix}: .*{kind}\((.+)\)");
-    let re = Regex::new(&pattern).unwrap();
-    let mut res = Vec::new();
-
-    for line in annotations {
-        if let Some(cap) = re.captures(line) {
-            let str_value = &cap[3];
-            if kind == "Field Elements" {
-                res.extend(str_value.split(',').filter_map(BigUint::from_str_hex));
-            } else if let Some(val) = BigUint::from_str_hex(str_value) {
-                res.push(val);
-            }
-        }
+/// Splits `P->V[a:b]: /cpu air/<path>: ... <kind>(<value>)` into its path, kind and value.
+fn parse_sent(line: &str) -> Option<(&str, &str, &str)> {
+    let rest = line.strip_prefix("P->V[")?;
+    let (range, rest) = rest.split_once("]: /cpu air/")?;
+    let (begin, end) = range.split_once(':')?;
+    if !is_number(begin) || !is_number(end) {
+        return None;
     }
 
-    Ok(res)
+    let (path, rest) = rest.split_once(": ")?;
+    let (kind, value) = split_value(rest)?;
+    Some((path, kind, value))
+}
+
+/// Splits `... <kind>(<value>)` into its kind and value.
+fn split_value(rest: &str) -> Option<(&str, &str)> {
+    let close = rest.rfind(')')?;
+    let open = rest[..close].rfind('(')?;
+    let value = &rest[open + 1..close];
+    let head = &rest[..open];
+
+    let kind = ["Field Elements", "Field Element", "Hash", "Data"]
+        .into_iter()
+        .find(|kind| head.ends_with(kind))?;
+    (!value.is_empty()).then_some((kind, value))
+}
+
+fn is_number(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+pub fn extract_z_and_alpha(annotations: &[&str]) -> Result<ZAlpha> {
+    AnnotationIndex::new(annotations).z_and_alpha()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotation_index() {
+        let annotations = [
+            "V->P: /cpu air/STARK/Interaction: Interaction element #0: Field Element(0x1)",
+            "V->P: /cpu air/STARK/Interaction: Interaction element #1: Field Element(0x2)",
+            "V->P: /cpu air/STARK/Interaction: Interaction element #2: Field Element(0x3)",
+            "P->V[0:32]: /cpu air/STARK/FRI/Commitment/Layer 1: Commitment: Hash(0xa)",
+            "P->V[32:64]: /cpu air/STARK/FRI/Commitment/Layer 2: Commitment: Hash(0xb)",
+            "P->V[64:96]: /cpu air/STARK/FRI/Commitment/Last Layer: Coefficients: Field Elements(0x4,0x5)",
+            "P->V[96:128]: /cpu air/STARK/FRI/Decommitment/Layer 1: Row 3: Field Element(0x6)",
+            "P->V[128:160]: /cpu air/STARK/FRI/Decommitment/Layer 1: For node 7: Hash(0x7)",
+            "P->V[160:192]: /cpu air/STARK/FRI/Decommitment/Layer 10: For node 7: Hash(0x8)",
+            "not an annotation(0x9)",
+        ];
+        let index = AnnotationIndex::new(&annotations);
+        let values = |path, kind| index.get(path, kind);
+        let felts = |v: &[u32]| v.iter().map(|&v| BigUint::from(v)).collect::<Vec<_>>();
+
+        let ZAlpha { z, alpha } = index.z_and_alpha().unwrap();
+        assert_eq!((z, alpha), (BigUint::from(1u32), BigUint::from(2u32)));
+        assert_eq!(values(FRI_LAYERS_COMMITMENTS, "Hash"), felts(&[0xa, 0xb]));
+        assert_eq!(
+            values("STARK/FRI/Commitment/Last Layer", "Field Elements"),
+            felts(&[4, 5])
+        );
+        assert_eq!(
+            values("STARK/FRI/Decommitment/Layer 1", "Field Element"),
+            felts(&[6])
+        );
+        assert_eq!(
+            values("STARK/FRI/Decommitment/Layer 1", "Hash"),
+            felts(&[7])
+        );
+        assert!(values("STARK/FRI/Decommitment/Layer 1", "Data").is_empty());
+    }
 }
@@ -1,87 +1,102 @@
 use num_bigint::BigUint;
 
-use self::annotation_kind::{Annotation, ZAlpha};
+pub use self::annotate::annotate;
+use self::{
+    annotation_kind::{Annotation, ZAlpha},
+    extract::AnnotationIndex,
+};
+use crate::error::{Error, Result};
 
+pub mod annotate;
 pub mod annotation_kind;
 pub mod extract;
 
+/// The proof data recorded in Stone prover annotations (`--generate_annotations`), in the
+/// order the verifier reads it. Values are kept as big integers, as they appear in the annotations.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Annotations {
+    /// The memory interaction elements, see `interaction_elements`.
     pub z: BigUint,
     pub alpha: BigUint,
+    /// All interaction elements drawn by the verifier: 3, or 6 for layouts with a second
+    /// interaction phase.
+    pub interaction_elements: Vec<BigUint>,
+    /// Commitments to the original, interaction and composition traces.
     pub original_commitment_hash: BigUint,
     pub interaction_commitment_hash: BigUint,
     pub composition_commitment_hash: BigUint,
+    /// Trace and composition values at the out of domain point.
     pub oods_values: Vec<BigUint>,
     pub fri_layers_commitments: Vec<BigUint>,
     pub fri_last_layer_coefficients: Vec<BigUint>,
     pub proof_of_work_nonce: BigUint,
+    /// Decommitments of the queried trace rows, with their Merkle authentication paths.
     pub original_leaves: Vec<BigUint>,
     pub original_authentications: Vec<BigUint>,
     pub interaction_leaves: Vec<BigUint>,
     pub interaction_authentications: Vec<BigUint>,
     pub composition_leaves: Vec<BigUint>,
     pub composition_authentications: Vec<BigUint>,
+    /// Decommitments of the inner FRI layers, starting at layer 1.
     pub fri_witnesses: Vec<FriWitness>,
 }
 
 impl Annotations {
-    pub fn new(annotations: &[&str], n_fri_layers: usize) -> anyhow::Result<Annotations> {
-        let ZAlpha { z, alpha } = ZAlpha::extract(annotations)?;
+    /// Parses annotation lines of a proof with `n_fri_layers` FRI layers, i.e. the length of
+    /// its `fri_step_list`.
+    pub fn new(annotations: &[&str], n_fri_layers: usize) -> Result<Annotations> {
+        let index = AnnotationIndex::new(annotations);
+        let ZAlpha { z, alpha } = index.z_and_alpha()?;
         Ok(Annotations {
             z,
             alpha,
+            interaction_elements: index.interaction_elements()?,
             original_commitment_hash: Annotation::OriginalCommitmentHash
-                .extract(annotations)?
+                .extract(&index)
                 .first()
-                .ok_or(anyhow::anyhow!("No OriginalCommitmentHash in annotations!"))?
+                .ok_or(missing("OriginalCommitmentHash"))?
                 .clone(),
             interaction_commitment_hash: Annotation::InteractionCommitmentHash
-                .extract(annotations)?
+                .extract(&index)
                 .first()
-                .ok_or(anyhow::anyhow!(
-                    "No InteractionCommitmentHash in annotations!"
-                ))?
+                .ok_or(missing("InteractionCommitmentHash"))?
                 .clone(),
             composition_commitment_hash: Annotation::CompositionCommitmentHash
-                .extract(annotations)?
+                .extract(&index)
                 .first()
-                .ok_or(anyhow::anyhow!(
-                    "No CompositionCommitmentHash in annotations!"
-                ))?
+                .ok_or(missing("CompositionCommitmentHash"))?
                 .clone(),
-            oods_values: Annotation::OodsValues.extract(annotations)?,
-            fri_layers_commitments: Annotation::FriLayersCommitments.extract(annotations)?,
-            fri_last_layer_coefficients: Annotation::FriLastLayerCoefficients
-                .extract(annotations)?,
+            oods_values: Annotation::OodsValues.extract(&index),
+            fri_layers_commitments: Annotation::FriLayersCommitments.extract(&index),
+            fri_last_layer_coefficients: Annotation::FriLastLayerCoefficients.extract(&index),
             proof_of_work_nonce: Annotation::ProofOfWorkNonce
-                .extract(annotations)?
+                .extract(&index)
                 .first()
-                .ok_or(anyhow::anyhow!("No ProofOfWorkNonce in annotations!"))?
+                .ok_or(missing("ProofOfWorkNonce"))?
                 .clone(),
-            original_leaves: Annotation::OriginalWitnessLeaves.extract(annotations)?,
-            original_authentications: Annotation::OriginalWitnessAuthentications
-                .extract(annotations)?,
-            interaction_leaves: Annotation::InteractionWitnessLeaves.extract(annotations)?,
+            original_leaves: Annotation::OriginalWitnessLeaves.extract(&index),
+            original_authentications: Annotation::OriginalWitnessAuthentications.extract(&index),
+            interaction_leaves: Annotation::InteractionWitnessLeaves.extract(&index),
             interaction_authentications: Annotation::InteractionWitnessAuthentications
-                .extract(annotations)?,
-            composition_leaves: Annotation::CompositionWitnessLeaves.extract(annotations)?,
+                .extract(&index),
+            composition_leaves: Annotation::CompositionWitnessLeaves.extract(&index),
             composition_authentications: Annotation::CompositionWitnessAuthentications
-                .extract(annotations)?,
+                .extract(&index),
             fri_witnesses: (1..n_fri_layers)
-                .map(|i| {
-                    Ok(FriWitness {
-                        layer: i,
-                        leaves: Annotation::FriWitnessesLeaves(i).extract(annotations)?,
-                        authentications: Annotation::FriWitnessesAuthentications(i)
-                            .extract(annotations)?,
-                    })
+                .map(|i| FriWitness {
+                    layer: i,
+                    leaves: Annotation::FriWitnessesLeaves(i).extract(&index),
+                    authentications: Annotation::FriWitnessesAuthentications(i).extract(&index),
                 })
-                .collect::<anyhow::Result<Vec<_>>>()?,
+                .collect(),
         })
     }
 }
 
+fn missing(annotation: &str) -> Error {
+    Error::Annotations(format!("No {annotation} in annotations!"))
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct FriWitness {
     pub layer: usize,
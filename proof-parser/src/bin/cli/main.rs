@@ -0,0 +1,221 @@
+use std::{
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use cairo_proof_parser::{
+    diff::diff,
+    explain::{explain_proof_json, inspect},
+    output::{extract_proof_output, ExtractOutputResult},
+    parse_file,
+    program::{extract_program, CairoVersion, ExtractProgramResult},
+    read_proof_file, to_felts, CalldataFormat, ProofJSON, StarkProof,
+};
+use clap::{Parser, Subcommand, ValueEnum};
+use starknet_types_core::felt::Felt;
+
+mod register;
+mod validate_hex;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Cli {
+    /// Proof JSON to read, stdin if omitted.
+    #[clap(short, long, global = true, value_parser)]
+    input: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the proof calldata in the given format.
+    Convert {
+        #[clap(long, value_enum, default_value_t = Format::Decimal)]
+        to: Format,
+    },
+    /// Print the proof as verifier felts.
+    Display,
+    /// Print a human-readable summary of the proof.
+    Explain,
+    /// Print the differences between two proofs, each a proof JSON or felt calldata.
+    Diff { left: PathBuf, right: PathBuf },
+    /// Print statistics of the parsed proof: config, felts per section and calldata size.
+    Inspect {
+        #[clap(long)]
+        json: bool,
+    },
+    /// Print the program output and its Poseidon hash.
+    Output {
+        #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Print the Poseidon hash of the proven program.
+    Program {
+        #[clap(long, value_enum, default_value_t = Version::Cairo0)]
+        cairo_version: Version,
+    },
+    /// Send the proof to a verifier contract and print the expected facts.
+    Register(Box<register::Args>),
+    /// Check that `proof_hex` agrees with the annotations.
+    ValidateHex,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    /// Space-separated decimals.
+    Decimal,
+    /// `[0x1, 0x2, ...]`.
+    Hex,
+    /// A JSON array of hex strings.
+    Json,
+    /// A `scarb cairo-run --arguments-file` file.
+    ScarbArgs,
+}
+
+impl From<Format> for CalldataFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Decimal => CalldataFormat::Decimal,
+            Format::Hex => CalldataFormat::Hex,
+            Format::Json => CalldataFormat::Json,
+            Format::ScarbArgs => CalldataFormat::ScarbArgs,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum OutputFormat {
+    Text,
+    /// `{ "output": [...], "output_hash": "0x..." }` with hex felts.
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Version {
+    Cairo0,
+    Cairo1,
+}
+
+impl From<Version> for CairoVersion {
+    fn from(version: Version) -> Self {
+        match version {
+            Version::Cairo0 => CairoVersion::Cairo0,
+            Version::Cairo1 => CairoVersion::Cairo1,
+        }
+    }
+}
+
+fn read_input(path: Option<&PathBuf>) -> cairo_proof_parser::Result<String> {
+    match path {
+        Some(path) => read_proof_file(path),
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        }
+    }
+}
+
+// The proof JSON at `path` or stdin, read the way `parse_file` does: gzip is decompressed and
+// a referenced `proof_file` resolves next to the JSON, or in the working directory for stdin.
+// Every subcommand reads its proof through here.
+fn read_proof_json(path: Option<&PathBuf>) -> anyhow::Result<ProofJSON> {
+    let dir = path
+        .and_then(|path| path.parent())
+        .unwrap_or(Path::new("."));
+    Ok(read_input(path)?
+        .parse::<ProofJSON>()?
+        .load_proof_file(dir)?)
+}
+
+// A proof JSON, or calldata as felts separated by whitespace, commas or brackets.
+fn read_proof(path: &PathBuf) -> anyhow::Result<StarkProof> {
+    let input = read_proof_file(path)?;
+    if input.trim_start().starts_with('{') {
+        return Ok(parse_file(path)?);
+    }
+
+    let calldata = input
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | '[' | ']' | '"'))
+        .filter(|felt| !felt.is_empty())
+        .map(|felt| {
+            felt.parse::<Felt>()
+                .map_err(|_| anyhow::anyhow!("invalid felt {felt}"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(StarkProof::from_calldata(&calldata)?)
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let proof_json = || read_proof_json(cli.input.as_ref());
+    let proof = || anyhow::Ok(StarkProof::try_from(proof_json()?)?);
+
+    match cli.command {
+        Command::Convert { to } => {
+            println!("{}", proof()?.to_calldata_string(to.into())?);
+        }
+        Command::Display => {
+            let proof = proof()?;
+            println!("{:?}", to_felts(&proof)?);
+        }
+        Command::Explain => println!("{}", explain_proof_json(proof_json()?)?),
+        Command::Diff { left, right } => {
+            let differences = diff(&read_proof(&left)?, &read_proof(&right)?)?;
+            for difference in &differences {
+                println!("{difference}");
+            }
+            if !differences.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Command::Inspect { json } => {
+            let inspection = inspect(&proof()?)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&inspection)?);
+            } else {
+                println!("{inspection}");
+            }
+        }
+        Command::Output { format } => {
+            let ExtractOutputResult {
+                program_output,
+                program_output_hash,
+            } = extract_proof_output(&proof()?)?;
+
+            match format {
+                OutputFormat::Text => {
+                    let program_output_display: Vec<String> = program_output
+                        .iter()
+                        .map(std::string::ToString::to_string)
+                        .collect();
+
+                    println!("{program_output_display:?}");
+                    println!("{program_output_hash}");
+                }
+                OutputFormat::Json => {
+                    let output = serde_json::json!({
+                        "output": program_output
+                            .iter()
+                            .map(|felt| format!("{felt:#x}"))
+                            .collect::<Vec<_>>(),
+                        "output_hash": format!("{program_output_hash:#x}"),
+                    });
+                    println!("{output}");
+                }
+            }
+        }
+        Command::Program { cairo_version } => {
+            let ExtractProgramResult { program_hash, .. } =
+                extract_program(&proof()?, cairo_version.into())?;
+            println!("{program_hash}");
+        }
+        Command::Register(args) => register::run(&proof()?, *args).await?,
+        Command::ValidateHex => validate_hex::run(proof_json()?)?,
+    }
+
+    Ok(())
+}
@@ -0,0 +1,240 @@
+use cairo_proof_parser::{
+    fact::{compute_fact, CairoVersion, FactHash},
+    registry::{
+        register_proof, simulate_proof_registration, Confirmation, Fee, RegistrationOptions,
+        Simulation, WaitPolicy,
+    },
+    StarkProof,
+};
+use clap::{ArgGroup, Args as ClapArgs, ValueEnum};
+use starknet::accounts::{ExecutionEncoding, SingleOwnerAccount};
+use starknet::core::types::{BlockId, BlockTag, Felt};
+use starknet::core::utils::get_selector_from_name;
+use starknet::providers::jsonrpc::HttpTransport;
+use starknet::providers::JsonRpcClient;
+use starknet::providers::Provider;
+use starknet::signers::{LocalWallet, SigningKey};
+use std::time::Duration;
+use url::Url;
+
+// Read when `--keystore` is given without `--password`.
+const PASSWORD_VAR: &str = "STARKNET_KEYSTORE_PASSWORD";
+
+#[derive(ClapArgs, Debug)]
+#[clap(group(ArgGroup::new("signer").required(true).args(["key", "keystore"])))]
+pub struct Args {
+    /// The StarkNet address of the signer.
+    #[clap(short, long, value_parser = parse_felt)]
+    address: Felt,
+
+    /// The private key of the signer in hexadecimal.
+    #[clap(short, long, value_parser = parse_felt)]
+    key: Option<Felt>,
+
+    /// An encrypted keystore JSON holding the signer's key, as created by starkli.
+    #[clap(long, value_parser)]
+    keystore: Option<std::path::PathBuf>,
+
+    /// The keystore password, read from STARKNET_KEYSTORE_PASSWORD if omitted.
+    #[clap(long, requires = "keystore")]
+    password: Option<String>,
+
+    /// The StarkNet address of the contract.
+    #[clap(short, long, value_parser = parse_felt)]
+    to: Felt,
+
+    /// The selector name for the contract function.
+    #[clap(short, long, value_parser = parse_selector)]
+    selector: Felt,
+
+    /// The URL of the StarkNet JSON-RPC endpoint.
+    #[clap(short, long, value_parser)]
+    url: Url,
+
+    /// Estimate the fee by simulating the transaction instead of using a fixed max fee.
+    #[clap(long, conflicts_with = "max_fee")]
+    estimate_fee: bool,
+
+    /// The max fee in wei of the v1 transaction.
+    #[clap(long, value_parser = parse_felt)]
+    max_fee: Option<Felt>,
+
+    /// Send a v3 transaction paying in STRK, with estimated gas bounds unless given.
+    #[clap(long, conflicts_with = "max_fee")]
+    strk: bool,
+
+    /// The max L1 gas of the v3 transaction.
+    #[clap(long, requires = "strk")]
+    gas: Option<u64>,
+
+    /// The max L1 gas price in fri of the v3 transaction.
+    #[clap(long, requires = "strk")]
+    gas_price: Option<u128>,
+
+    /// The status to wait for after sending.
+    #[clap(long, value_enum, default_value_t = WaitFor::L2)]
+    wait_for: WaitFor,
+
+    /// Seconds to wait for the status before failing.
+    #[clap(long, default_value_t = 60)]
+    wait_timeout: u64,
+
+    /// Seconds between status polls.
+    #[clap(long, default_value_t = 1)]
+    poll_interval: u64,
+
+    /// Proofs with more calldata felts are verified in steps through Integrity's
+    /// verify_proof_initial, verify_proof_step and verify_proof_final_and_register_fact.
+    #[clap(long, default_value_t = 4000)]
+    max_calldata: usize,
+
+    /// Simulate the registration and report its fee and revert reason without sending it.
+    #[clap(long)]
+    dry_run: bool,
+}
+
+// A decimal or `0x` prefixed hexadecimal felt.
+fn parse_felt(s: &str) -> Result<Felt, String> {
+    s.parse()
+        .map_err(|_| format!("{s} is not a decimal or hex felt"))
+}
+
+fn parse_selector(s: &str) -> Result<Felt, String> {
+    get_selector_from_name(s).map_err(|e| format!("invalid selector {s}: {e}"))
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum WaitFor {
+    L1,
+    L2,
+    None,
+}
+
+impl Args {
+    fn signing_key(&self) -> anyhow::Result<SigningKey> {
+        match (&self.key, &self.keystore) {
+            (Some(key), _) => Ok(SigningKey::from_secret_scalar(*key)),
+            (None, Some(keystore)) => {
+                let password = match &self.password {
+                    Some(password) => password.clone(),
+                    None => std::env::var(PASSWORD_VAR).map_err(|_| {
+                        anyhow::anyhow!("--keystore needs --password or {PASSWORD_VAR}")
+                    })?,
+                };
+                SigningKey::from_keystore(keystore, &password)
+                    .map_err(|e| anyhow::anyhow!("Failed to decrypt keystore: {e}"))
+            }
+            (None, None) => unreachable!("clap requires a signer"),
+        }
+    }
+
+    fn options(&self) -> RegistrationOptions {
+        RegistrationOptions {
+            fee: self.fee(),
+            wait: WaitPolicy {
+                confirmation: match self.wait_for {
+                    WaitFor::L1 => Confirmation::AcceptedOnL1,
+                    WaitFor::L2 => Confirmation::AcceptedOnL2,
+                    WaitFor::None => Confirmation::None,
+                },
+                timeout: Duration::from_secs(self.wait_timeout),
+                poll_interval: Duration::from_secs(self.poll_interval),
+            },
+            max_calldata: self.max_calldata,
+            ..RegistrationOptions::default()
+        }
+    }
+
+    fn fee(&self) -> Fee {
+        if self.strk {
+            return Fee::Strk {
+                gas: self.gas,
+                gas_price: self.gas_price,
+            };
+        }
+        match self.max_fee {
+            Some(max_fee) => Fee::Eth {
+                max_fee: Some(max_fee),
+            },
+            None if self.estimate_fee => Fee::Eth { max_fee: None },
+            None => Fee::default(),
+        }
+    }
+}
+
+// Prints the library's progress messages, keeping stdout for the results.
+struct ProgressLogger;
+
+impl log::Log for ProgressLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Info && metadata.target().starts_with("cairo_proof_parser")
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{}", record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+pub async fn run(proof: &StarkProof, args: Args) -> anyhow::Result<()> {
+    if log::set_logger(&ProgressLogger).is_ok() {
+        log::set_max_level(log::LevelFilter::Info);
+    }
+
+    let key = args.signing_key()?;
+
+    // Setup StarkNet provider and wallet
+    let provider = JsonRpcClient::new(HttpTransport::new(args.url.clone()));
+    let signer = LocalWallet::from(key);
+
+    // Fetch chain ID from the provider
+    let chain_id = provider.chain_id().await?;
+
+    let mut account = SingleOwnerAccount::new(
+        provider,
+        signer,
+        args.address,
+        chain_id,
+        ExecutionEncoding::New,
+    );
+    account.set_block_id(BlockId::Tag(BlockTag::Pending));
+
+    let expected_fact = compute_fact(proof, CairoVersion::Cairo0, FactHash::Poseidon)?;
+    let l1_fact = compute_fact(proof, CairoVersion::Cairo0, FactHash::Keccak)?;
+
+    let (to, selector) = (args.to, args.selector);
+
+    if args.dry_run {
+        let Simulation {
+            overall_fee,
+            revert_reason,
+            unsimulated_calls,
+        } = simulate_proof_registration(&account, proof, to, selector, args.options()).await?;
+        println!("estimated fee: {overall_fee}");
+        if let Some(reason) = revert_reason {
+            anyhow::bail!("Registration would revert: {reason}");
+        }
+        if unsimulated_calls > 0 {
+            println!(
+                "verify_proof_initial would succeed, {unsimulated_calls} split verification calls were not simulated."
+            );
+        } else {
+            println!("Registration would succeed.");
+        }
+        return Ok(());
+    }
+
+    for tx in register_proof(&account, proof, to, selector, args.options()).await? {
+        println!("tx: {tx:#x}");
+    }
+    println!(
+        "expected_fact: {}",
+        Felt::from_bytes_be(&expected_fact.fact)
+    );
+    println!("l1_fact: {}", prefix_hex::encode(l1_fact.fact));
+
+    Ok(())
+}
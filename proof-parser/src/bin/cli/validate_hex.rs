@@ -0,0 +1,13 @@
+use cairo_proof_parser::{json_parser::proof_from_hex, ParseOptions, ProofJSON};
+
+pub fn run(proof_json: ProofJSON) -> anyhow::Result<()> {
+    let options = ParseOptions {
+        verify_consistency: true,
+        ..ParseOptions::default()
+    };
+    proof_from_hex(proof_json, &options)?;
+
+    println!("`hex_proof` is consistent with annotations.");
+
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use serde_felt::{from_felts_strict, to_felts};
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    error::{Error, Result},
+    hashes::{HashFunction, HashSettings},
+    stark_proof::{PublicMemoryCell, StarkProof},
+};
+
+const MAGIC: &[u8; 4] = b"CPPB";
+const VERSION: u8 = 2;
+
+// Layout: magic, version, the hash settings, the felt serialization, then the cells of the
+// continuous pages, which the felt serialization leaves out. Lengths and addresses are
+// little-endian `u32`s, and each felt is a length byte followed by its big-endian bytes
+// without leading zeros.
+impl StarkProof {
+    /// A compact binary encoding of the parsed proof, for caching it between runs. The
+    /// source digest is not kept.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let felts = to_felts(self)?;
+        let hashes = &self.config.hashes;
+
+        let mut bytes = Vec::with_capacity(10 + 33 * felts.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        for hash in [hashes.channel, hashes.commitment, hashes.pow] {
+            bytes.push(hash.index());
+        }
+        bytes.push(hashes.verifier_friendly_channel_updates.into());
+
+        bytes.extend_from_slice(&(felts.len() as u32).to_le_bytes());
+        for felt in &felts {
+            push_felt(&mut bytes, felt);
+        }
+
+        let pages = &self.public_input.continuous_pages;
+        bytes.extend_from_slice(&(pages.len() as u32).to_le_bytes());
+        for page in pages {
+            bytes.extend_from_slice(&(page.len() as u32).to_le_bytes());
+            for cell in page {
+                bytes.extend_from_slice(&cell.address.to_le_bytes());
+                push_felt(&mut bytes, &cell.value);
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Decodes a proof encoded with `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = ByteReader(bytes);
+        if reader.take(4)? != MAGIC {
+            return Err(Error::InvalidValue("not a binary proof".to_string()));
+        }
+        let version = reader.take(1)?[0];
+        if version != VERSION {
+            return Err(Error::InvalidValue(format!(
+                "binary proof version {version}, expected {VERSION}"
+            )));
+        }
+
+        let mut hash = || {
+            let index = reader.take(1)?[0];
+            HashFunction::ALL
+                .get(index as usize)
+                .copied()
+                .ok_or_else(|| Error::InvalidValue(format!("hash function {index}")))
+        };
+        let (channel, commitment, pow) = (hash()?, hash()?, hash()?);
+        let hashes = HashSettings {
+            channel,
+            commitment,
+            pow,
+            verifier_friendly_channel_updates: reader.take(1)?[0] != 0,
+        };
+
+        let len = reader.u32()? as usize;
+        let felts = (0..len)
+            .map(|_| reader.felt())
+            .collect::<Result<Vec<_>>>()?;
+        let n_pages = reader.u32()?;
+        let continuous_pages = (0..n_pages)
+            .map(|_| {
+                let len = reader.u32()?;
+                (0..len)
+                    .map(|_| {
+                        Ok(PublicMemoryCell {
+                            address: reader.u32()?,
+                            value: reader.felt()?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if !reader.0.is_empty() {
+            return Err(Error::InvalidValue(format!(
+                "{} trailing bytes after the binary proof",
+                reader.0.len()
+            )));
+        }
+
+        let mut proof: StarkProof = from_felts_strict(&felts)?;
+        proof.config.hashes = hashes;
+        proof.public_input.continuous_pages = continuous_pages;
+        Ok(proof)
+    }
+}
+
+fn push_felt(bytes: &mut Vec<u8>, felt: &Felt) {
+    let be = felt.to_bytes_be();
+    let start = be.iter().position(|b| *b != 0).unwrap_or(32);
+    bytes.push((32 - start) as u8);
+    bytes.extend_from_slice(&be[start..]);
+}
+
+struct ByteReader<'a>(&'a [u8]);
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.0.len() < n {
+            return Err(Error::InvalidValue("truncated binary proof".to_string()));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn felt(&mut self) -> Result<Felt> {
+        let n = self.take(1)?[0] as usize;
+        Ok(Felt::from_bytes_be_slice(self.take(n)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_roundtrip() {
+        let mut proof = StarkProof::sample();
+        proof.config.hashes.commitment = HashFunction::Blake256Masked248Lsb;
+
+        let bytes = proof.to_bytes().unwrap();
+        assert!(bytes.len() < 32 * proof.to_felts().unwrap().len());
+        assert_eq!(StarkProof::from_bytes(&bytes).unwrap(), proof);
+
+        let err = StarkProof::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert!(matches!(err, Error::InvalidValue(_)), "{err}");
+        let mut other_version = bytes.clone();
+        other_version[4] += 1;
+        assert!(StarkProof::from_bytes(&other_version).is_err());
+    }
+
+    #[test]
+    fn test_binary_roundtrip_continuous_pages() {
+        let mut proof = StarkProof::sample();
+        let cell = |address: u32| PublicMemoryCell {
+            address,
+            value: Felt::from(address) * Felt::TWO.pow(200u64),
+        };
+        let public_input = &mut proof.public_input;
+        public_input.continuous_pages = vec![(30..33).map(cell).collect(), vec![], vec![cell(50)]];
+        public_input.compute_page_headers(Felt::from(5u64), Felt::from(7u64));
+
+        let decoded = StarkProof::from_bytes(&proof.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.public_input.continuous_pages.len(), 3);
+        assert_eq!(decoded, proof);
+    }
+}
@@ -0,0 +1,85 @@
+use starknet_types_core::felt::Felt;
+
+use crate::error::{Error, Result};
+
+/// The output of one task run by the Starkware bootloader.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskOutput {
+    pub program_hash: Felt,
+    pub output: Vec<Felt>,
+}
+
+// Each task output starts with its size, counting this header, and its program hash.
+const TASK_HEADER_LEN: usize = 2;
+
+/// Splits a bootloader program output, `n_tasks` followed by `[size, program_hash, ..output]`
+/// for each task, into the task outputs. Fails if the output does not follow this layout.
+pub fn parse_bootloader_output(output: &[Felt]) -> Result<Vec<TaskOutput>> {
+    let (n_tasks, mut rest) = output
+        .split_first()
+        .ok_or_else(|| Error::InvalidValue("empty bootloader output".to_string()))?;
+    let n_tasks = felt_to_usize(*n_tasks, "number of tasks")?;
+
+    let mut tasks = Vec::with_capacity(n_tasks.min(rest.len()));
+    for i in 0..n_tasks {
+        let size = rest
+            .first()
+            .ok_or_else(|| Error::InvalidValue(format!("task {i} is missing")))?;
+        let size = felt_to_usize(*size, "task output size")?;
+        if size < TASK_HEADER_LEN || size > rest.len() {
+            return Err(Error::InvalidValue(format!(
+                "task {i} output size {size} does not fit the {} remaining felts",
+                rest.len()
+            )));
+        }
+
+        let (task, next) = rest.split_at(size);
+        tasks.push(TaskOutput {
+            program_hash: task[1],
+            output: task[TASK_HEADER_LEN..].to_vec(),
+        });
+        rest = next;
+    }
+
+    if !rest.is_empty() {
+        return Err(Error::InvalidValue(format!(
+            "{} felts left after {n_tasks} tasks",
+            rest.len()
+        )));
+    }
+    Ok(tasks)
+}
+
+fn felt_to_usize(felt: Felt, what: &str) -> Result<usize> {
+    felt.try_into()
+        .map_err(|_| Error::InvalidValue(format!("{what} {felt} is out of range")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bootloader_output() {
+        let felts = |values: &[u64]| values.iter().copied().map(Felt::from).collect::<Vec<_>>();
+
+        let tasks = parse_bootloader_output(&felts(&[2, 4, 100, 1, 2, 2, 200])).unwrap();
+        assert_eq!(
+            tasks,
+            [
+                TaskOutput {
+                    program_hash: Felt::from(100),
+                    output: felts(&[1, 2]),
+                },
+                TaskOutput {
+                    program_hash: Felt::from(200),
+                    output: vec![],
+                },
+            ]
+        );
+
+        assert!(parse_bootloader_output(&felts(&[1, 5, 100, 1])).is_err());
+        assert!(parse_bootloader_output(&felts(&[1, 2, 100, 7])).is_err());
+        assert!(parse_bootloader_output(&felts(&[1, 1])).is_err());
+    }
+}
@@ -1,8 +1,14 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+};
 
-use crate::json_parser::MemorySegmentAddress;
+use crate::{
+    error::Error, json_parser::MemorySegmentAddress, layout::Layout, stark_proof::SegmentInfo,
+    StarkProof,
+};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Builtin {
     Program,
     Execution,
@@ -17,19 +23,31 @@ pub enum Builtin {
 }
 
 impl Builtin {
-    pub fn from_str(s: &str) -> Option<Self> {
-        match s {
-            "program" => Some(Builtin::Program),
-            "execution" => Some(Builtin::Execution),
-            "output" => Some(Builtin::Output),
-            "pedersen" => Some(Builtin::Pedersen),
-            "range_check" => Some(Builtin::RangeCheck),
-            "ecdsa" => Some(Builtin::Ecdsa),
-            "bitwise" => Some(Builtin::Bitwise),
-            "ec_op" => Some(Builtin::EcOp),
-            "keccak" => Some(Builtin::Keccak),
-            "poseidon" => Some(Builtin::Poseidon),
-            _ => None,
+    pub fn name(&self) -> &'static str {
+        match self {
+            Builtin::Program => "program",
+            Builtin::Execution => "execution",
+            Builtin::Output => "output",
+            Builtin::Pedersen => "pedersen",
+            Builtin::RangeCheck => "range_check",
+            Builtin::Ecdsa => "ecdsa",
+            Builtin::Bitwise => "bitwise",
+            Builtin::EcOp => "ec_op",
+            Builtin::Keccak => "keccak",
+            Builtin::Poseidon => "poseidon",
+        }
+    }
+    /// Memory cells of one builtin instance, `None` for the program and execution segments.
+    pub fn cells_per_instance(&self) -> Option<u32> {
+        match self {
+            Builtin::Program | Builtin::Execution => None,
+            Builtin::Output | Builtin::RangeCheck => Some(1),
+            Builtin::Pedersen => Some(3),
+            Builtin::Ecdsa => Some(2),
+            Builtin::Bitwise => Some(5),
+            Builtin::EcOp => Some(7),
+            Builtin::Keccak => Some(16),
+            Builtin::Poseidon => Some(6),
         }
     }
     pub fn ordered() -> Vec<Self> {
@@ -52,7 +70,7 @@ impl Builtin {
         let mut segments = memory_segments
             .into_iter()
             .filter_map(|(k, v)| {
-                let builtin = Builtin::from_str(&k)?;
+                let builtin = k.parse::<Builtin>().ok()?;
                 Some((builtin, v))
             })
             .collect::<Vec<_>>();
@@ -60,3 +78,127 @@ impl Builtin {
         segments.into_iter().map(|(_, segment)| segment).collect()
     }
 }
+
+impl FromStr for Builtin {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "program" => Ok(Builtin::Program),
+            "execution" => Ok(Builtin::Execution),
+            "output" => Ok(Builtin::Output),
+            "pedersen" => Ok(Builtin::Pedersen),
+            "range_check" => Ok(Builtin::RangeCheck),
+            "ecdsa" => Ok(Builtin::Ecdsa),
+            "bitwise" => Ok(Builtin::Bitwise),
+            "ec_op" => Ok(Builtin::EcOp),
+            "keccak" => Ok(Builtin::Keccak),
+            "poseidon" => Ok(Builtin::Poseidon),
+            _ => Err(Error::InvalidValue(format!("unknown builtin {s}"))),
+        }
+    }
+}
+
+impl StarkProof {
+    /// The memory segment of `builtin`, `None` if the proof's layout has no such builtin.
+    pub fn segment(&self, builtin: Builtin) -> Option<&SegmentInfo> {
+        let public_input = &self.public_input;
+        let index = match builtin {
+            Builtin::Program => 0,
+            Builtin::Execution => 1,
+            builtin => {
+                let layout = Layout::from_felt(public_input.layout).ok()?;
+                2 + layout
+                    .builtins(&public_input.dynamic_params)
+                    .iter()
+                    .position(|b| *b == builtin)?
+            }
+        };
+        public_input.segments.get(index)
+    }
+
+    pub fn program_segment(&self) -> Option<&SegmentInfo> {
+        self.segment(Builtin::Program)
+    }
+
+    pub fn execution_segment(&self) -> Option<&SegmentInfo> {
+        self.segment(Builtin::Execution)
+    }
+
+    pub fn output_segment(&self) -> Option<&SegmentInfo> {
+        self.segment(Builtin::Output)
+    }
+}
+
+/// Instances used by the proven program of each builtin of the proof's layout.
+pub fn builtin_usage(proof: &StarkProof) -> BTreeMap<Builtin, u64> {
+    layout_builtins(proof)
+        .into_iter()
+        .filter_map(|builtin| {
+            let segment = proof.segment(builtin)?;
+            let cells = segment.stop_ptr.saturating_sub(segment.begin_addr);
+            Some((builtin, u64::from(cells / builtin.cells_per_instance()?)))
+        })
+        .collect()
+}
+
+/// Instances the trace has room for, `2^log_n_steps / ratio`, of each builtin with a ratio.
+/// Empty for the dynamic layout, whose ratios are not known here.
+pub fn builtin_capacity(proof: &StarkProof) -> BTreeMap<Builtin, u64> {
+    let Ok(layout) = Layout::from_felt(proof.public_input.layout) else {
+        return BTreeMap::new();
+    };
+    let n_steps = 1u64 << proof.public_input.log_n_steps;
+    layout_builtins(proof)
+        .into_iter()
+        .filter_map(|builtin| {
+            let ratio = layout.builtin_ratio(builtin)?;
+            Some((builtin, n_steps / u64::from(ratio)))
+        })
+        .collect()
+}
+
+fn layout_builtins(proof: &StarkProof) -> Vec<Builtin> {
+    Layout::from_felt(proof.public_input.layout)
+        .map(|layout| layout.builtins(&proof.public_input.dynamic_params))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment() {
+        let proof = StarkProof::sample();
+
+        assert_eq!(proof.program_segment().unwrap().begin_addr, 1);
+        assert_eq!(proof.execution_segment().unwrap().begin_addr, 10);
+        assert_eq!(proof.output_segment().unwrap().begin_addr, 20);
+        // The recursive layout has a bitwise builtin, but the sample has no segment for it.
+        assert_eq!(proof.segment(Builtin::Bitwise), None);
+        assert_eq!(proof.segment(Builtin::Ecdsa), None);
+    }
+
+    #[test]
+    fn test_builtin_usage() {
+        let mut proof = StarkProof::sample();
+        proof.public_input.segments.push(SegmentInfo {
+            begin_addr: 30,
+            stop_ptr: 39,
+        });
+
+        assert_eq!(
+            builtin_usage(&proof),
+            BTreeMap::from([(Builtin::Output, 2), (Builtin::Pedersen, 3)])
+        );
+        assert_eq!(
+            builtin_capacity(&proof),
+            BTreeMap::from([
+                (Builtin::Pedersen, 128),
+                (Builtin::RangeCheck, 2048),
+                (Builtin::Bitwise, 2048),
+            ])
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use starknet_crypto::{pedersen_hash, poseidon_hash, poseidon_hash_many};
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    builtins::Builtin,
+    error::Result,
+    layout::Layout,
+    stark_proof::{CairoPublicInput, StarkConfig, StarkProof, StarkUnsentCommitment},
+    transcript::TranscriptLog,
+};
+
+/// Poseidon Fiat-Shamir channel of Integrity's verifier.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Channel {
+    pub digest: Felt,
+    counter: Felt,
+    pub log: TranscriptLog,
+}
+
+impl Channel {
+    pub fn new(digest: Felt) -> Self {
+        Channel {
+            digest,
+            counter: Felt::ZERO,
+            log: TranscriptLog::default(),
+        }
+    }
+
+    pub fn random_felt_to_prover(&mut self) -> Felt {
+        let value = poseidon_hash(self.digest, self.counter);
+        self.counter += Felt::ONE;
+        self.log.squeeze(value);
+        value
+    }
+
+    pub fn random_felts_to_prover(&mut self, n: usize) -> Vec<Felt> {
+        (0..n).map(|_| self.random_felt_to_prover()).collect()
+    }
+
+    pub fn read_felt_from_prover(&mut self, value: Felt) {
+        self.digest = poseidon_hash(self.digest + Felt::ONE, value);
+        self.counter = Felt::ZERO;
+        self.log.absorb(&[value], self.digest);
+    }
+
+    pub fn read_felts_from_prover(&mut self, values: &[Felt]) {
+        self.digest = poseidon_hash_many([self.digest + Felt::ONE].iter().chain(values));
+        self.counter = Felt::ZERO;
+        self.log.absorb(values, self.digest);
+    }
+
+    /// Sorted, deduplicated query indices below `upper_bound`, four per random felt.
+    pub fn generate_queries(&mut self, n_samples: usize, upper_bound: u64) -> Vec<u64> {
+        let mut queries = vec![];
+        while queries.len() < n_samples {
+            let bytes = self.random_felt_to_prover().to_bytes_be();
+            queries.extend(
+                bytes
+                    .chunks_exact(8)
+                    .map(|chunk| u64::from_be_bytes(chunk.try_into().unwrap()) % upper_bound)
+                    .take(n_samples - queries.len()),
+            );
+        }
+        queries.sort_unstable();
+        queries.dedup();
+        queries
+    }
+}
+
+impl CairoPublicInput<Felt> {
+    /// The public input hash seeding the channel, as computed by Integrity for Stone v5 proofs.
+    pub fn hash(&self) -> Felt {
+        let main_page_hash = self
+            .main_page
+            .iter()
+            .flat_map(|cell| [Felt::from(cell.address), cell.value])
+            .chain([Felt::from(2 * self.main_page.len())])
+            .fold(Felt::ZERO, |hash, felt| pedersen_hash(&hash, &felt));
+
+        let data = [
+            Felt::from(self.log_n_steps),
+            Felt::from(self.range_check_min),
+            Felt::from(self.range_check_max),
+            self.layout,
+        ]
+        .into_iter()
+        .chain(self.dynamic_params.values().copied())
+        .chain(
+            self.segments
+                .iter()
+                .flat_map(|s| [Felt::from(s.begin_addr), Felt::from(s.stop_ptr)]),
+        )
+        .chain([
+            Felt::from(self.padding_addr),
+            self.padding_value,
+            Felt::from(1 + self.n_continuous_pages),
+            Felt::from(self.main_page.len()),
+            main_page_hash,
+        ])
+        .chain(self.continuous_page_headers.iter().copied())
+        .collect::<Vec<_>>();
+
+        poseidon_hash_many(&data)
+    }
+}
+
+// Layouts with a diluted pool draw three more elements for it.
+fn n_interaction_elements(public_input: &CairoPublicInput<Felt>) -> Result<usize> {
+    let layout = Layout::from_felt(public_input.layout)?;
+    let builtins = layout.builtins(&public_input.dynamic_params);
+    let diluted = builtins
+        .iter()
+        .any(|b| matches!(b, Builtin::Bitwise | Builtin::Keccak));
+    Ok(if diluted { 6 } else { 3 })
+}
+
+/// The channel after the original trace commitment, with the interaction elements drawn.
+pub(crate) fn replay_interaction(
+    public_input: &CairoPublicInput<Felt>,
+    commitment: &StarkUnsentCommitment,
+) -> Result<(Vec<Felt>, Channel)> {
+    let mut channel = Channel::new(public_input.hash());
+
+    channel.read_felt_from_prover(commitment.traces.original);
+    let elements = channel.random_felts_to_prover(n_interaction_elements(public_input)?);
+    Ok((elements, channel))
+}
+
+/// Replays the verifier's channel over the commitments, up to the proof of work.
+pub(crate) fn replay_until_pow(
+    public_input: &CairoPublicInput<Felt>,
+    commitment: &StarkUnsentCommitment,
+) -> Result<Channel> {
+    let (_, mut channel) = replay_interaction(public_input, commitment)?;
+    channel.read_felt_from_prover(commitment.traces.interaction);
+
+    // Composition alpha, then the OODS point and alpha.
+    channel.random_felt_to_prover();
+    channel.read_felt_from_prover(commitment.composition);
+    channel.random_felt_to_prover();
+    channel.read_felts_from_prover(&commitment.oods_values);
+    channel.random_felt_to_prover();
+
+    for layer in &commitment.fri.inner_layers {
+        channel.random_felt_to_prover();
+        channel.read_felt_from_prover(*layer);
+    }
+    channel.random_felt_to_prover();
+    channel.read_felts_from_prover(&commitment.fri.last_layer_coefficients);
+
+    Ok(channel)
+}
+
+/// Replays the whole channel and draws the query indices into the evaluation domain.
+pub(crate) fn replay_queries(
+    config: &StarkConfig,
+    public_input: &CairoPublicInput<Felt>,
+    commitment: &StarkUnsentCommitment,
+) -> Result<(Vec<u64>, Channel)> {
+    let mut channel = replay_until_pow(public_input, commitment)?;
+    if config.proof_of_work.n_bits != 0 {
+        channel.read_felt_from_prover(commitment.proof_of_work_nonce);
+    }
+
+    let upper_bound = 1u64 << config.fri.log_input_size;
+    let queries = channel.generate_queries(config.n_queries as usize, upper_bound);
+    Ok((queries, channel))
+}
+
+impl StarkProof {
+    /// The sorted, deduplicated query indices the verifier derives from the commitments.
+    pub fn query_indices(&self) -> Result<Vec<u64>> {
+        self.config.hashes.check_channel()?;
+        Ok(replay_queries(&self.config, &self.public_input, &self.unsent_commitment)?.0)
+    }
+
+    /// Every channel interaction of the verifier, up to drawing the queries.
+    pub fn transcript(&self) -> Result<TranscriptLog> {
+        self.config.hashes.check_channel()?;
+        Ok(
+            replay_queries(&self.config, &self.public_input, &self.unsent_commitment)?
+                .1
+                .log,
+        )
+    }
+}
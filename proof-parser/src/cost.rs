@@ -0,0 +1,86 @@
+use crate::{error::Result, stark_proof::StarkProof};
+
+// Rough guesses of the Cairo steps Integrity's verifier spends per calldata felt it
+// deserializes and per Merkle node it hashes while decommitting queries.
+const STEPS_PER_FELT: u64 = 30;
+const STEPS_PER_HASH: u64 = 60;
+
+/// Resource prices for `StarkProof::estimate_verification_cost`. The defaults are only a
+/// starting point: use the network's current prices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasPrices {
+    /// Gas charged per calldata felt.
+    pub gas_per_felt: u64,
+    /// Cairo steps covered by one unit of gas.
+    pub steps_per_gas: u64,
+    /// Price of one unit of gas, in the fee token's smallest unit.
+    pub gas_price: u128,
+}
+
+impl Default for GasPrices {
+    fn default() -> Self {
+        GasPrices {
+            gas_per_felt: 1,
+            steps_per_gas: 400,
+            gas_price: 100_000_000_000,
+        }
+    }
+}
+
+/// An estimate of what registering a proof costs, see `StarkProof::estimate_verification_cost`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerificationCost {
+    pub calldata_felts: usize,
+    pub steps: u64,
+    pub gas: u64,
+    pub fee: u128,
+}
+
+impl StarkProof {
+    /// Estimates the cost of verifying the proof with Integrity in a single call. The step
+    /// count is an order-of-magnitude estimate, meant for forecasting, not for fee limits.
+    pub fn estimate_verification_cost(&self, prices: &GasPrices) -> Result<VerificationCost> {
+        let calldata_felts = self.stats()?.total;
+
+        let config = &self.config;
+        let table_heights = [
+            config.traces.original.vector.height,
+            config.traces.interaction.vector.height,
+            config.composition.vector.height,
+        ]
+        .into_iter()
+        .chain(config.fri.inner_layers.iter().map(|l| l.vector.height));
+        let hashes = u64::from(config.n_queries) * table_heights.map(u64::from).sum::<u64>();
+        let steps = STEPS_PER_FELT * calldata_felts as u64 + STEPS_PER_HASH * hashes;
+
+        let gas = prices.gas_per_felt * calldata_felts as u64
+            + steps.div_ceil(prices.steps_per_gas.max(1));
+        Ok(VerificationCost {
+            calldata_felts,
+            steps,
+            gas,
+            fee: u128::from(gas) * prices.gas_price,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_verification_cost() {
+        let proof = StarkProof::sample();
+        let prices = GasPrices::default();
+        let cost = proof.estimate_verification_cost(&prices).unwrap();
+
+        assert_eq!(cost.calldata_felts, proof.to_felts().unwrap().len());
+        assert!(cost.steps > STEPS_PER_FELT * cost.calldata_felts as u64);
+        assert_eq!(cost.fee, u128::from(cost.gas) * prices.gas_price);
+
+        let mut more_queries = proof.clone();
+        more_queries.config.n_queries *= 2;
+        let more = more_queries.estimate_verification_cost(&prices).unwrap();
+        assert!(more.steps > cost.steps);
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt::Display;
+
+use serde_json::Value;
+
+use crate::{error::Result, StarkProof};
+
+// Longer arrays of plain values are summarized instead of diffed element by element.
+const MAX_LISTED_ELEMENTS: usize = 16;
+
+/// A value that differs between two proofs, at a path like `public_input.segments[2].stop_ptr`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    pub path: String,
+    pub left: String,
+    pub right: String,
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} != {}", self.path, self.left, self.right)
+    }
+}
+
+/// Structural differences between two proofs, in field order.
+pub fn diff(left: &StarkProof, right: &StarkProof) -> Result<Vec<Difference>> {
+    let mut differences = vec![];
+    diff_values(
+        "",
+        &serde_json::to_value(left)?,
+        &serde_json::to_value(right)?,
+        &mut differences,
+    );
+    Ok(differences)
+}
+
+fn diff_values(path: &str, left: &Value, right: &Value, out: &mut Vec<Difference>) {
+    let difference = |left: String, right: String| Difference {
+        path: path.to_string(),
+        left,
+        right,
+    };
+
+    match (left, right) {
+        _ if left == right => {}
+        (Value::Object(l), Value::Object(r)) => {
+            for (key, l_value) in l {
+                let key_path = key_path(path, key);
+                match r.get(key) {
+                    Some(r_value) => diff_values(&key_path, l_value, r_value, out),
+                    None => out.push(Difference {
+                        path: key_path,
+                        left: l_value.to_string(),
+                        right: "missing".to_string(),
+                    }),
+                }
+            }
+            for (key, r_value) in r.iter().filter(|(key, _)| !l.contains_key(*key)) {
+                out.push(Difference {
+                    path: key_path(path, key),
+                    left: "missing".to_string(),
+                    right: r_value.to_string(),
+                });
+            }
+        }
+        (Value::Array(l), Value::Array(r))
+            if is_long_scalar_array(l) || is_long_scalar_array(r) =>
+        {
+            let differing = l.iter().zip(r).filter(|(l, r)| l != r).count();
+            let first = l.iter().zip(r).position(|(l, r)| l != r);
+            let summary = match first {
+                Some(first) => format!("{differing} differ from index {first}"),
+                None => "common prefix equal".to_string(),
+            };
+            out.push(difference(
+                format!("{} elements", l.len()),
+                format!("{} elements, {summary}", r.len()),
+            ));
+        }
+        (Value::Array(l), Value::Array(r)) => {
+            for (i, (l_value, r_value)) in l.iter().zip(r).enumerate() {
+                diff_values(&format!("{path}[{i}]"), l_value, r_value, out);
+            }
+            if l.len() != r.len() {
+                out.push(difference(
+                    format!("{} elements", l.len()),
+                    format!("{} elements", r.len()),
+                ));
+            }
+        }
+        _ => out.push(difference(left.to_string(), right.to_string())),
+    }
+}
+
+fn key_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn is_long_scalar_array(values: &[Value]) -> bool {
+    values.len() > MAX_LISTED_ELEMENTS
+        && values
+            .iter()
+            .all(|value| !value.is_array() && !value.is_object())
+}
+
+#[cfg(test)]
+mod tests {
+    use starknet_types_core::felt::Felt;
+
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let left = StarkProof::sample();
+        assert_eq!(diff(&left, &left).unwrap(), []);
+
+        let mut right = left.clone();
+        right.config.n_queries = 3;
+        right.public_input.segments[2].stop_ptr = 23;
+        right.unsent_commitment.oods_values[1] = Felt::ZERO;
+
+        let differences = diff(&left, &right).unwrap();
+        assert_eq!(
+            differences
+                .iter()
+                .map(|d| d.path.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "config.n_queries",
+                "public_input.segments[2].stop_ptr",
+                "unsent_commitment.oods_values[1]"
+            ]
+        );
+        assert_eq!(differences[0].to_string(), "config.n_queries: 2 != 3");
+
+        let mut left = left;
+        left.unsent_commitment.oods_values = (0..20u64).map(Felt::from).collect();
+        let mut right = left.clone();
+        right.unsent_commitment.oods_values[5] = Felt::ZERO;
+        right.unsent_commitment.oods_values.push(Felt::ONE);
+        assert_eq!(
+            diff(&left, &right).unwrap(),
+            [Difference {
+                path: "unsent_commitment.oods_values".to_string(),
+                left: "20 elements".to_string(),
+                right: "21 elements, 1 differ from index 5".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_missing_keys() {
+        let left = serde_json::json!({ "a": 1, "nested": { "b": 2 } });
+        let right = serde_json::json!({ "c": 3, "nested": { "d": 4 } });
+
+        let mut differences = vec![];
+        diff_values("", &left, &right, &mut differences);
+        assert_eq!(
+            differences
+                .iter()
+                .map(|d| (d.path.as_str(), d.left.as_str(), d.right.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                ("a", "1", "missing"),
+                ("nested.b", "2", "missing"),
+                ("nested.d", "missing", "4"),
+                ("c", "missing", "3"),
+            ]
+        );
+    }
+}
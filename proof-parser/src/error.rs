@@ -0,0 +1,93 @@
+use std::fmt::{self, Display};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    InvalidLayout(String),
+    InvalidParameter(String),
+    InvalidValue(String),
+    HexDecode(String),
+    Annotations(String),
+    SegmentMissing(String),
+    LengthMismatch {
+        section: String,
+        expected: usize,
+        got: usize,
+        /// What most likely makes the lengths differ, when it can be told.
+        probable_cause: Option<String>,
+    },
+    Serialization(serde_felt::Error),
+    Verification(String),
+}
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(formatter, "reading proof failed: {e}"),
+            Error::Json(e) => write!(formatter, "invalid proof json: {e}"),
+            Error::InvalidLayout(msg) => write!(formatter, "invalid layout: {msg}"),
+            Error::InvalidParameter(msg) => write!(formatter, "invalid parameter: {msg}"),
+            Error::InvalidValue(msg) => write!(formatter, "invalid value: {msg}"),
+            Error::HexDecode(msg) => write!(formatter, "invalid proof payload: {msg}"),
+            Error::Annotations(msg) => write!(formatter, "invalid annotations: {msg}"),
+            Error::SegmentMissing(segment) => write!(formatter, "{segment} segment not found"),
+            Error::LengthMismatch {
+                section,
+                expected,
+                got,
+                probable_cause,
+            } => {
+                write!(formatter, "{section}: expected {expected} felts, got {got}")?;
+                match probable_cause {
+                    Some(cause) => write!(formatter, " ({cause})"),
+                    None => Ok(()),
+                }
+            }
+            Error::Serialization(e) => write!(formatter, "felt serialization failed: {e}"),
+            Error::Verification(msg) => write!(formatter, "verification failed: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::Serialization(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<serde_felt::Error> for Error {
+    fn from(e: serde_felt::Error) -> Self {
+        match e {
+            serde_felt::Error::LengthMismatch {
+                field,
+                expected,
+                got,
+            } => Error::LengthMismatch {
+                section: field,
+                expected,
+                got,
+                probable_cause: None,
+            },
+            e => Error::Serialization(e),
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::{
+    builtins::Builtin,
+    error::Result,
+    json_parser::{proof_from_hex, ProofJSON},
+    layout::Layout,
+    options::ParseOptions,
+    proof_structure::ProofStructure,
+    sections::to_felts_sections,
+    StarkProof,
+};
+
+/// A human-readable summary of a proof, for triaging proof files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    pub layout: Layout,
+    pub n_steps: u32,
+    /// Builtin name, begin address and stop pointer, in verifier order.
+    pub segments: Vec<(String, u32, u32)>,
+    pub fri_step_list: Vec<u32>,
+    pub last_layer_degree_bound: u32,
+    pub n_queries: u32,
+    pub log_n_cosets: u32,
+    pub proof_of_work_bits: u32,
+    pub structure: ProofStructure,
+    pub proof_len: usize,
+    pub calldata_len: usize,
+}
+
+impl Explanation {
+    /// The usual conjectured security estimate: `n_queries * log_n_cosets + proof_of_work_bits`.
+    pub fn security_bits(&self) -> u32 {
+        self.n_queries * self.log_n_cosets + self.proof_of_work_bits
+    }
+}
+
+pub fn explain(input: &str) -> Result<Explanation> {
+    explain_proof_json(input.parse()?)
+}
+
+/// Like [`explain`], for a proof JSON already read, such as one whose `proof_file` is loaded.
+pub fn explain_proof_json(proof_json: ProofJSON) -> Result<Explanation> {
+    let fri = proof_json.proof_parameters.stark.fri.clone();
+    let log_n_cosets = proof_json.proof_parameters.stark.log_n_cosets;
+    let public_input = &proof_json.public_input;
+    let (layout, n_steps) = (public_input.layout, public_input.n_steps);
+
+    let mut segments = public_input
+        .memory_segments
+        .iter()
+        .map(|(name, s)| (name.clone(), s.begin_addr, s.stop_ptr))
+        .collect::<Vec<_>>();
+    segments.sort_by_key(|(name, _, _)| {
+        name.parse::<Builtin>()
+            .ok()
+            .and_then(|b| Builtin::ordered().iter().position(|o| *o == b))
+    });
+
+    let proof_felts = proof_json.proof_felts()?.0;
+    let proof_len = proof_felts.len();
+    let structure = proof_json.sized_proof_structure(&proof_felts)?;
+
+    let calldata_len = proof_from_hex(proof_json, &ParseOptions::default())?
+        .to_felts()?
+        .len();
+
+    Ok(Explanation {
+        layout,
+        n_steps,
+        segments,
+        fri_step_list: fri.fri_step_list,
+        last_layer_degree_bound: fri.last_layer_degree_bound,
+        n_queries: fri.n_queries,
+        log_n_cosets,
+        proof_of_work_bits: fri.proof_of_work_bits,
+        structure,
+        proof_len,
+        calldata_len,
+    })
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let structure = &self.structure;
+
+        writeln!(f, "layout: {}", self.layout)?;
+        writeln!(f, "n_steps: {}", self.n_steps)?;
+        writeln!(f, "segments:")?;
+        for (name, begin, stop) in &self.segments {
+            writeln!(
+                f,
+                "  {name}: [{begin}, {stop}) ({} cells)",
+                stop.saturating_sub(*begin)
+            )?;
+        }
+        writeln!(f, "fri:")?;
+        writeln!(f, "  step list: {:?}", self.fri_step_list)?;
+        writeln!(f, "  inner layers: {}", structure.layer_count)?;
+        writeln!(
+            f,
+            "  last layer degree bound: {}",
+            self.last_layer_degree_bound
+        )?;
+        writeln!(f, "queries: {}", self.n_queries)?;
+        writeln!(f, "log_n_cosets: {}", self.log_n_cosets)?;
+        writeln!(f, "proof of work bits: {}", self.proof_of_work_bits)?;
+        writeln!(f, "witness:")?;
+        writeln!(f, "  original leaves: {}", structure.first_layer_queries)?;
+        writeln!(
+            f,
+            "  interaction leaves: {}",
+            structure.composition_decommitment
+        )?;
+        writeln!(f, "  composition leaves: {}", structure.composition_leaves)?;
+        writeln!(f, "  authentications: {}", structure.authentications)?;
+        for (i, (leaves, witness)) in structure
+            .layer
+            .iter()
+            .zip(structure.witness.iter())
+            .enumerate()
+        {
+            writeln!(
+                f,
+                "  fri layer {}: {leaves} leaves, {witness} table witness",
+                i + 1
+            )?;
+        }
+        writeln!(f, "estimated security: {} bits", self.security_bits())?;
+        writeln!(f, "proof hex: {} felts", self.proof_len)?;
+        write!(f, "calldata: {} felts", self.calldata_len)
+    }
+}
+
+/// Statistics of a parsed proof, see `inspect`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Inspection {
+    pub layout: Layout,
+    pub n_steps: u64,
+    pub fri_step_sizes: Vec<u32>,
+    pub log_last_layer_degree_bound: u32,
+    pub n_queries: u32,
+    pub log_n_cosets: u32,
+    pub proof_of_work_bits: u32,
+    /// Felts of each proof section, in calldata order.
+    pub sections: Vec<(String, usize)>,
+    pub calldata_len: usize,
+    pub security_bits: u32,
+}
+
+pub fn inspect(proof: &StarkProof) -> Result<Inspection> {
+    let config = &proof.config;
+    let sections = to_felts_sections(proof)?
+        .into_iter()
+        .map(|(section, felts)| (section.to_string(), felts.len()))
+        .collect::<Vec<_>>();
+
+    Ok(Inspection {
+        layout: Layout::from_felt(proof.public_input.layout)?,
+        n_steps: 1 << proof.public_input.log_n_steps,
+        fri_step_sizes: config.fri.fri_step_sizes.clone(),
+        log_last_layer_degree_bound: config.fri.log_last_layer_degree_bound,
+        n_queries: config.n_queries,
+        log_n_cosets: config.log_n_cosets,
+        proof_of_work_bits: config.proof_of_work.n_bits,
+        calldata_len: sections.iter().map(|(_, len)| len).sum(),
+        sections,
+        security_bits: config.n_queries * config.log_n_cosets + config.proof_of_work.n_bits,
+    })
+}
+
+impl Display for Inspection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "layout: {}", self.layout)?;
+        writeln!(f, "n_steps: {}", self.n_steps)?;
+        writeln!(f, "fri:")?;
+        writeln!(f, "  step sizes: {:?}", self.fri_step_sizes)?;
+        writeln!(
+            f,
+            "  log last layer degree bound: {}",
+            self.log_last_layer_degree_bound
+        )?;
+        writeln!(f, "queries: {}", self.n_queries)?;
+        writeln!(f, "log_n_cosets: {}", self.log_n_cosets)?;
+        writeln!(f, "proof of work bits: {}", self.proof_of_work_bits)?;
+        writeln!(f, "sections:")?;
+        for (section, len) in &self.sections {
+            writeln!(f, "  {section}: {len} felts")?;
+        }
+        writeln!(f, "calldata: {} felts", self.calldata_len)?;
+        write!(f, "estimated security: {} bits", self.security_bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::tests::{dummy_proof_hex, proof_json_str};
+
+    #[test]
+    fn test_explain() {
+        let hex = dummy_proof_hex();
+        let len = (hex.len() - 2) / 64;
+
+        let explanation = explain(&proof_json_str(&format!(r#""proof_hex": "{hex}""#))).unwrap();
+
+        assert_eq!(explanation.proof_len, len);
+        assert_eq!(explanation.security_bits(), 2 * 3 + 30);
+        assert_eq!(
+            explanation
+                .segments
+                .iter()
+                .map(|s| s.0.as_str())
+                .collect::<Vec<_>>(),
+            ["program", "execution", "output"]
+        );
+        assert!(explanation.calldata_len > len);
+        assert!(explanation.to_string().contains("layout: recursive"));
+    }
+
+    #[test]
+    fn test_inspect() {
+        let proof = StarkProof::sample();
+        let inspection = inspect(&proof).unwrap();
+
+        assert_eq!(inspection.layout, Layout::Recursive);
+        assert_eq!(inspection.n_steps, 1 << 14);
+        assert_eq!(inspection.security_bits, 2 * 3 + 30);
+        assert_eq!(inspection.calldata_len, proof.to_felts().unwrap().len());
+        assert_eq!(inspection.sections[0].0, "config");
+
+        let json = serde_json::to_value(&inspection).unwrap();
+        assert_eq!(json["layout"], "recursive");
+        assert!(inspection.to_string().contains("calldata:"));
+    }
+}
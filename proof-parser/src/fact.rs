@@ -0,0 +1,152 @@
+use sha3::{Digest, Keccak256};
+use starknet_crypto::{pedersen_hash, poseidon_hash_many};
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    error::{Error, Result},
+    program::{extract_program, ExtractProgramResult},
+    StarkProof,
+};
+
+pub use crate::program::CairoVersion;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FactHash {
+    /// Starknet fact registries: Poseidon program hash, output hash and fact.
+    Poseidon,
+    /// Ethereum fact registries: Pedersen hash chain program hash, keccak output hash and fact.
+    Keccak,
+}
+
+/// The hashes a fact registry sees for a proof, `output_hash` and `fact` as big-endian words.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FactInfo {
+    pub program_hash: Felt,
+    pub output_hash: [u8; 32],
+    pub fact: [u8; 32],
+}
+
+/// Computes the fact a verifier registers for `proof`, see [`extract_program`].
+pub fn compute_fact(proof: &StarkProof, version: CairoVersion, hash: FactHash) -> Result<FactInfo> {
+    let ExtractProgramResult { program, .. } = extract_program(proof, version)?;
+    let output_segment = proof
+        .output_segment()
+        .ok_or_else(|| Error::SegmentMissing("output".to_string()))?;
+    let output = proof
+        .public_input
+        .memory_range(output_segment.begin_addr, output_segment.stop_ptr)?;
+
+    Ok(match hash {
+        FactHash::Poseidon => {
+            let program_hash = poseidon_hash_many(&program);
+            FactInfo {
+                program_hash,
+                output_hash: poseidon_hash_many(&output).to_bytes_be(),
+                fact: poseidon_fact(program_hash, &output).to_bytes_be(),
+            }
+        }
+        FactHash::Keccak => {
+            let program_hash = hash_chain(&program);
+            FactInfo {
+                program_hash,
+                output_hash: keccak_words(&output),
+                fact: keccak_fact(program_hash, &output),
+            }
+        }
+    })
+}
+
+// `compute_program_hash_chain` of cairo-lang: the Pedersen hash chain of `[len, ..data]`.
+fn hash_chain(data: &[Felt]) -> Felt {
+    let len = Felt::from(data.len());
+    let mut chain = std::iter::once(&len).chain(data).rev();
+    let last = *chain.next().unwrap_or(&len);
+    chain.fold(last, |acc, felt| pedersen_hash(felt, &acc))
+}
+
+fn keccak_words(felts: &[Felt]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    for felt in felts {
+        hasher.update(felt.to_bytes_be());
+    }
+    hasher.finalize().into()
+}
+
+/// The fact registered on Starknet: `poseidon(program_hash, poseidon(output))`.
+pub fn poseidon_fact(program_hash: Felt, output: &[Felt]) -> Felt {
+    poseidon_hash_many(&[program_hash, poseidon_hash_many(output)])
+}
+
+/// The fact of Ethereum fact registries, following the GPS statement convention:
+/// `keccak(program_hash, keccak(output))` over 32-byte big-endian words.
+pub fn keccak_fact(program_hash: Felt, output: &[Felt]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(program_hash.to_bytes_be());
+    hasher.update(keccak_words(output));
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::program::tests::proof_with_program;
+
+    #[test]
+    fn test_keccak_fact() {
+        let program_hash = Felt::from(7);
+        let output = [Felt::ONE, Felt::TWO];
+
+        let mut words = [0u8; 64];
+        words[31] = 1;
+        words[63] = 2;
+        let output_hash = Keccak256::digest(words);
+        let mut statement = [0u8; 64];
+        statement[31] = 7;
+        statement[32..].copy_from_slice(&output_hash);
+
+        assert_eq!(
+            keccak_fact(program_hash, &output),
+            <[u8; 32]>::from(Keccak256::digest(statement))
+        );
+        assert_eq!(
+            poseidon_fact(program_hash, &output),
+            poseidon_hash_many(&[program_hash, poseidon_hash_many(&output)])
+        );
+    }
+
+    #[test]
+    fn test_compute_fact() {
+        let program = [
+            0x40780017fff7fff_u64,
+            1,
+            0x1104800180018000,
+            4,
+            0x10780017fff7fff,
+            0,
+            9,
+        ]
+        .map(Felt::from);
+        let output = [Felt::ONE, Felt::TWO];
+        let mut proof = proof_with_program(&program, &output);
+
+        let info = compute_fact(&proof, CairoVersion::Cairo0, FactHash::Poseidon).unwrap();
+        assert_eq!(info.program_hash, poseidon_hash_many(&program));
+        assert_eq!(
+            info.fact,
+            poseidon_fact(info.program_hash, &output).to_bytes_be()
+        );
+
+        let info = compute_fact(&proof, CairoVersion::Cairo1, FactHash::Keccak).unwrap();
+        let chain = program
+            .iter()
+            .rev()
+            .skip(1)
+            .fold(program[6], |acc, felt| pedersen_hash(felt, &acc));
+        assert_eq!(info.program_hash, pedersen_hash(&Felt::from(7), &chain));
+        assert_eq!(info.fact, keccak_fact(info.program_hash, &output));
+
+        proof.public_input.main_page[0].value = Felt::ZERO;
+        assert!(compute_fact(&proof, CairoVersion::Cairo0, FactHash::Poseidon).is_err());
+        assert!(compute_fact(&proof, CairoVersion::Cairo1, FactHash::Poseidon).is_ok());
+    }
+}
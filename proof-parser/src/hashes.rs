@@ -0,0 +1,220 @@
+use std::{fmt::Display, str::FromStr};
+
+use blake2::Blake2s256;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use starknet_crypto::{poseidon_hash, poseidon_hash_many};
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    error::{Error, Result},
+    proof_params::Stone6Parameters,
+};
+
+/// A hash function as named in Stone's prover parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashFunction {
+    Keccak256,
+    Blake256,
+    Keccak256Masked160Lsb,
+    Blake256Masked160Lsb,
+    Blake256Masked248Lsb,
+    Poseidon3,
+    Pedersen,
+}
+
+impl HashFunction {
+    pub const ALL: [HashFunction; 7] = [
+        HashFunction::Keccak256,
+        HashFunction::Blake256,
+        HashFunction::Keccak256Masked160Lsb,
+        HashFunction::Blake256Masked160Lsb,
+        HashFunction::Blake256Masked248Lsb,
+        HashFunction::Poseidon3,
+        HashFunction::Pedersen,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashFunction::Keccak256 => "keccak256",
+            HashFunction::Blake256 => "blake256",
+            HashFunction::Keccak256Masked160Lsb => "keccak256_masked160_lsb",
+            HashFunction::Blake256Masked160Lsb => "blake256_masked160_lsb",
+            HashFunction::Blake256Masked248Lsb => "blake256_masked248_lsb",
+            HashFunction::Poseidon3 => "poseidon3",
+            HashFunction::Pedersen => "pedersen",
+        }
+    }
+
+    // Position in `ALL`, a compact tag for the binary encoding and the digest.
+    pub(crate) fn index(&self) -> u8 {
+        Self::ALL.iter().position(|h| h == self).unwrap() as u8
+    }
+
+    fn unsupported(&self, purpose: &str) -> Error {
+        Error::Verification(format!("{self} is not supported as the {purpose} hash"))
+    }
+
+    // Masked byte hashes keep the low bits of the big-endian digest.
+    fn masked_digest(&self, felts: &[Felt], purpose: &str) -> Result<Felt> {
+        let bytes = |felts: &[Felt]| {
+            felts
+                .iter()
+                .flat_map(|f| f.to_bytes_be())
+                .collect::<Vec<_>>()
+        };
+        match self {
+            HashFunction::Keccak256Masked160Lsb => Ok(Felt::from_bytes_be_slice(
+                &Keccak256::digest(bytes(felts))[12..],
+            )),
+            HashFunction::Blake256Masked160Lsb => Ok(Felt::from_bytes_be_slice(
+                &Blake2s256::digest(bytes(felts))[12..],
+            )),
+            HashFunction::Blake256Masked248Lsb => Ok(Felt::from_bytes_be_slice(
+                &Blake2s256::digest(bytes(felts))[1..],
+            )),
+            _ => Err(self.unsupported(purpose)),
+        }
+    }
+
+    /// Hash of a committed row. Byte hashes read the row in Montgomery form, as Stone
+    /// stores it.
+    pub(crate) fn hash_row(&self, row: &[Felt]) -> Result<Felt> {
+        match self {
+            HashFunction::Poseidon3 => Ok(poseidon_hash_many(row)),
+            _ => self.masked_digest(&serde_felt::felts_to_montgomery(row), "commitment"),
+        }
+    }
+
+    /// Hash of two sibling Merkle nodes.
+    pub(crate) fn hash_nodes(&self, left: Felt, right: Felt) -> Result<Felt> {
+        match self {
+            HashFunction::Poseidon3 => Ok(poseidon_hash(left, right)),
+            _ => self.masked_digest(&[left, right], "commitment"),
+        }
+    }
+
+    /// Plain 32-byte digest, for the proof of work.
+    pub(crate) fn digest(&self, data: &[&[u8]]) -> Result<[u8; 32]> {
+        fn digest<D: Digest>(data: &[&[u8]]) -> [u8; 32] {
+            let mut hasher = D::new();
+            for chunk in data {
+                hasher.update(chunk);
+            }
+            hasher
+                .finalize()
+                .as_slice()
+                .try_into()
+                .expect("32-byte digest")
+        }
+
+        match self {
+            HashFunction::Keccak256 => Ok(digest::<Keccak256>(data)),
+            HashFunction::Blake256 => Ok(digest::<Blake2s256>(data)),
+            _ => Err(self.unsupported("proof of work")),
+        }
+    }
+}
+
+impl Display for HashFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for HashFunction {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        HashFunction::ALL
+            .into_iter()
+            .find(|hash| hash.name() == name)
+            .ok_or_else(|| Error::InvalidParameter(format!("unknown hash function {name}")))
+    }
+}
+
+/// The hashes a proof was generated with. Proofs without Stone v6 parameters get the
+/// defaults Integrity verifies: a Poseidon channel, `keccak256_masked160_lsb` commitments
+/// and a Keccak proof of work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct HashSettings {
+    pub channel: HashFunction,
+    pub commitment: HashFunction,
+    pub pow: HashFunction,
+    pub verifier_friendly_channel_updates: bool,
+}
+
+impl Default for HashSettings {
+    fn default() -> Self {
+        HashSettings {
+            channel: HashFunction::Poseidon3,
+            commitment: HashFunction::Keccak256Masked160Lsb,
+            pow: HashFunction::Keccak256,
+            verifier_friendly_channel_updates: true,
+        }
+    }
+}
+
+impl HashSettings {
+    pub(crate) fn from_stone6(params: &Stone6Parameters) -> Result<Self> {
+        Ok(HashSettings {
+            channel: params.channel_hash.parse()?,
+            commitment: params.commitment_hash.parse()?,
+            pow: params.pow_hash.parse()?,
+            verifier_friendly_channel_updates: params.verifier_friendly_channel_updates,
+        })
+    }
+
+    /// Fails unless the channel is the Poseidon channel with verifier friendly updates,
+    /// the only one this crate replays.
+    pub(crate) fn check_channel(&self) -> Result<()> {
+        if self.channel != HashFunction::Poseidon3 {
+            return Err(self.channel.unsupported("channel"));
+        }
+        if !self.verifier_friendly_channel_updates {
+            return Err(Error::Verification(
+                "channels without verifier friendly updates are not supported".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_settings_from_stone6() {
+        let params = Stone6Parameters {
+            channel_hash: "poseidon3".to_string(),
+            commitment_hash: "blake256_masked248_lsb".to_string(),
+            pow_hash: "blake256".to_string(),
+            statement: None,
+            use_extension_field: false,
+            verifier_friendly_channel_updates: true,
+            verifier_friendly_commitment_hashes: false,
+        };
+        let settings = HashSettings::from_stone6(&params).unwrap();
+        assert_eq!(settings.commitment, HashFunction::Blake256Masked248Lsb);
+        assert_eq!(settings.pow, HashFunction::Blake256);
+        settings.check_channel().unwrap();
+
+        let keccak_channel = Stone6Parameters {
+            channel_hash: "keccak256".to_string(),
+            ..params.clone()
+        };
+        let err = HashSettings::from_stone6(&keccak_channel)
+            .unwrap()
+            .check_channel()
+            .unwrap_err();
+        assert!(matches!(err, Error::Verification(_)), "{err}");
+
+        let unknown = Stone6Parameters {
+            pow_hash: "sha256".to_string(),
+            ..params
+        };
+        assert!(HashSettings::from_stone6(&unknown).is_err());
+    }
+}
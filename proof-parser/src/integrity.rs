@@ -0,0 +1,178 @@
+use serde_felt::to_felts;
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    error::{Error, Result},
+    stark_proof::StarkProof,
+};
+
+/// The verifier configuration Integrity's fact registry takes before the proof.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifierSettings {
+    pub hasher: String,
+    pub stone_version: String,
+    pub memory_verification: String,
+}
+
+impl Default for VerifierSettings {
+    fn default() -> Self {
+        VerifierSettings {
+            hasher: "keccak_160_lsb".to_string(),
+            stone_version: "stone5".to_string(),
+            memory_verification: "strict".to_string(),
+        }
+    }
+}
+
+/// Calldata of Integrity's split verification, for proofs too big for a single
+/// `verify_proof_full_and_register_fact` transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntegrityCalls {
+    pub job_id: Felt,
+    /// `verify_proof_initial`: the job id, the verifier config and the proof without
+    /// its FRI layer witnesses.
+    pub initial: Vec<Felt>,
+    /// The witness of each FRI layer, one `verify_proof_step` call per layer.
+    pub steps: Vec<Vec<Felt>>,
+    /// The last layer coefficients for `verify_proof_final_and_register_fact`.
+    pub last_layer: Vec<Felt>,
+}
+
+impl IntegrityCalls {
+    /// Calldata of the `i`-th `verify_proof_step`, given the FRI verification state
+    /// (constant, then variable) emitted by the previous call.
+    pub fn step_calldata(&self, i: usize, state: &[Felt]) -> Option<Vec<Felt>> {
+        let witness = self.steps.get(i)?;
+        Some(self.with_state(state, witness))
+    }
+
+    /// Calldata of `verify_proof_final_and_register_fact`, given the FRI verification
+    /// state emitted by the last step.
+    pub fn final_calldata(&self, state: &[Felt]) -> Vec<Felt> {
+        self.with_state(state, &self.last_layer)
+    }
+
+    fn with_state(&self, state: &[Felt], payload: &[Felt]) -> Vec<Felt> {
+        [self.job_id]
+            .iter()
+            .chain(state)
+            .chain(payload)
+            .copied()
+            .collect()
+    }
+}
+
+/// The proof serialized as the four structs Integrity's `StarkProofWithSerde`
+/// deserializes, each with its own inner length prefixes. Concatenated in field order they
+/// are the flat serialization.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntegrityProofGroups {
+    pub config: Vec<Felt>,
+    pub public_input: Vec<Felt>,
+    pub unsent_commitment: Vec<Felt>,
+    pub witness: Vec<Felt>,
+}
+
+impl IntegrityProofGroups {
+    pub fn concat(&self) -> Vec<Felt> {
+        [
+            &self.config[..],
+            &self.public_input,
+            &self.unsent_commitment,
+            &self.witness,
+        ]
+        .concat()
+    }
+}
+
+impl StarkProof {
+    /// Serializes the proof grouped by the structs of Integrity's proof layout.
+    pub fn to_integrity_groups(&self) -> Result<IntegrityProofGroups> {
+        Ok(IntegrityProofGroups {
+            config: to_felts(&self.config)?,
+            public_input: to_felts(&self.public_input)?,
+            unsent_commitment: to_felts(&self.unsent_commitment)?,
+            witness: to_felts(&self.witness)?,
+        })
+    }
+
+    /// Splits the proof into the payloads of Integrity's `verify_proof_initial`,
+    /// `verify_proof_step` and `verify_proof_final_and_register_fact` calls.
+    pub fn to_integrity_calls(
+        &self,
+        job_id: Felt,
+        settings: &VerifierSettings,
+    ) -> Result<IntegrityCalls> {
+        let short_string = |s: &str| {
+            serde_felt::short_string_to_felt(s)
+                .map_err(|e| Error::InvalidParameter(format!("Invalid setting {s}: {e}")))
+        };
+
+        let mut initial_proof = self.clone();
+        let layers = std::mem::take(&mut initial_proof.witness.fri_witness.layers);
+
+        let mut initial = vec![
+            job_id,
+            self.public_input.layout,
+            short_string(&settings.hasher)?,
+            short_string(&settings.stone_version)?,
+            short_string(&settings.memory_verification)?,
+        ];
+        initial.extend(initial_proof.to_felts()?);
+
+        Ok(IntegrityCalls {
+            job_id,
+            initial,
+            steps: layers
+                .iter()
+                .map(to_felts)
+                .collect::<std::result::Result<_, _>>()?,
+            last_layer: to_felts(&self.unsent_commitment.fri.last_layer_coefficients)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integrity_calls() {
+        let proof = StarkProof::sample();
+        let job_id = Felt::from(7u64);
+        let calls = proof
+            .to_integrity_calls(job_id, &VerifierSettings::default())
+            .unwrap();
+
+        assert_eq!(calls.initial[..2], [job_id, proof.public_input.layout]);
+        assert_eq!(
+            calls.initial[2],
+            Felt::from_bytes_be_slice(b"keccak_160_lsb")
+        );
+        assert_eq!(calls.steps.len(), proof.witness.fri_witness.layers.len());
+
+        let last_layer = &proof.unsent_commitment.fri.last_layer_coefficients;
+        let state = [Felt::from(1u64), Felt::from(2u64)];
+        let final_calldata = calls.final_calldata(&state);
+        assert_eq!(final_calldata[..3], [job_id, state[0], state[1]]);
+        assert_eq!(final_calldata[3], Felt::from(last_layer.len()));
+        assert_eq!(final_calldata[4..], last_layer[..]);
+
+        assert!(calls.step_calldata(calls.steps.len(), &state).is_none());
+    }
+
+    #[test]
+    fn test_integrity_groups() {
+        let proof = StarkProof::sample();
+        let groups = proof.to_integrity_groups().unwrap();
+
+        assert_eq!(groups.concat(), proof.to_felts().unwrap());
+        // The witness vectors carry Integrity's doubled length prefix.
+        let n = Felt::from(proof.witness.original_leaves.len());
+        assert_eq!(groups.witness[..2], [n, n]);
+        assert_eq!(
+            groups.unsent_commitment[3],
+            Felt::from(proof.unsent_commitment.oods_values.len())
+        );
+    }
+}
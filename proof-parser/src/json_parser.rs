@@ -1,89 +1,190 @@
 use std::{
     collections::{BTreeMap, HashMap},
     convert::TryFrom,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
     vec,
 };
 
-use anyhow::{anyhow, Context};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use num_bigint::BigUint;
-use serde::Deserialize;
-use serde_felt::from_felts_with_lengths;
+use serde::{Deserialize, Serialize};
+use serde_felt::{DeserializeFelt, FeltReader};
 use starknet_types_core::felt::Felt;
 
 use crate::{
-    annotations::Annotations,
+    annotations::{
+        annotate,
+        annotation_kind::{Annotation, ZAlpha},
+        extract::AnnotationIndex,
+        Annotations,
+    },
     builtins::Builtin,
-    layout::Layout,
-    proof_params::{ProofParameters, ProverConfig},
+    channel::replay_queries,
+    error::{Error, Result},
+    hashes::HashSettings,
+    layout::{Layout, LayoutConstants},
+    options::ParseOptions,
+    proof_params::{Fri, ProofParameters, ProverConfig, Stark},
     proof_structure::ProofStructure,
+    public_memory::PublicMemory,
     stark_proof::{
-        CairoPublicInput, FriConfig, FriLayerWitness, FriUnsentCommitment, FriWitness,
-        ProofOfWorkConfig, PublicMemoryCell, SegmentInfo, StarkConfig, StarkProof,
-        StarkUnsentCommitment, StarkWitness, TableCommitmentConfig, TracesConfig,
+        CairoPublicInput, FriConfig, FriUnsentCommitment, ProofOfWorkConfig, PublicMemoryCell,
+        SegmentInfo, StarkConfig, StarkProof, StarkUnsentCommitment, StarkWitness,
+        StoneFriLayerWitness, StoneFriWitness, TableCommitmentConfig, TracesConfig,
         TracesUnsentCommitment, VectorCommitmentConfig,
     },
-    utils::log2_if_power_of_2,
+    utils::{log2_if_power_of_2, read_file},
 };
 
-#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ProofJSON {
-    proof_parameters: ProofParameters,
+    pub(crate) proof_parameters: ProofParameters,
     #[serde(default)]
     annotations: Vec<String>,
-    public_input: PublicInput,
+    pub(crate) public_input: PublicInput,
+    #[serde(default)]
     proof_hex: String,
-    prover_config: ProverConfig,
+    // Some transports re-encode the proof bytes as base64.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    proof_b64: Option<String>,
+    // Some pipelines write the proof body to a separate binary file, relative to the JSON.
+    #[serde(default, alias = "proof_path", skip_serializing_if = "Option::is_none")]
+    proof_file: Option<PathBuf>,
+    #[serde(skip)]
+    proof_file_bytes: Option<Vec<u8>>,
+    #[serde(default)]
+    pub(crate) prover_config: ProverConfig,
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct MemorySegmentAddress {
-    begin_addr: u32,
-    stop_ptr: u32,
+    pub(crate) begin_addr: u32,
+    pub(crate) stop_ptr: u32,
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PublicMemoryElement {
-    address: u32,
-    page: u32,
+    pub(crate) address: u32,
+    pub(crate) page: u32,
     value: String,
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PublicInput {
+    #[serde(skip_serializing_if = "Option::is_none")]
     dynamic_params: Option<BTreeMap<String, BigUint>>,
     pub layout: Layout,
-    memory_segments: HashMap<String, MemorySegmentAddress>,
+    pub(crate) memory_segments: HashMap<String, MemorySegmentAddress>,
     pub n_steps: u32,
-    public_memory: Vec<PublicMemoryElement>,
+    pub(crate) public_memory: Vec<PublicMemoryElement>,
     rc_min: u32,
     rc_max: u32,
 }
 
+/// Stone release a proof JSON was written by, told apart by the `proof_parameters` keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofVersion {
+    Stone5,
+    /// Adds the channel and commitment hash settings to `proof_parameters`.
+    Stone6,
+}
+
+impl ProofVersion {
+    const STONE6_KEYS: [&'static str; 4] = [
+        "channel_hash",
+        "commitment_hash",
+        "pow_hash",
+        "use_extension_field",
+    ];
+
+    pub fn detect(value: &serde_json::Value) -> Self {
+        let params = &value["proof_parameters"];
+        if Self::STONE6_KEYS
+            .iter()
+            .any(|key| params.get(key).is_some())
+        {
+            ProofVersion::Stone6
+        } else {
+            ProofVersion::Stone5
+        }
+    }
+}
+
 pub fn bigint_to_fe(bigint: &BigUint) -> Felt {
     Felt::from_hex(&bigint.to_str_radix(16)).unwrap()
 }
 
 pub fn bigints_to_fe(bigint: &[BigUint]) -> Vec<Felt> {
-    bigint.iter().map(bigint_to_fe).collect()
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        bigint.par_iter().map(bigint_to_fe).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        bigint.iter().map(bigint_to_fe).collect()
+    }
+}
+
+impl FromStr for ProofJSON {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        Self::from_value(serde_json::from_str(input)?)
+    }
 }
 
 impl ProofJSON {
     const COMPONENT_HEIGHT: u32 = 16;
-    pub fn stark_config(&self) -> anyhow::Result<StarkConfig> {
+
+    /// Deserializes a proof JSON of any supported Stone version.
+    pub fn from_value(value: serde_json::Value) -> Result<Self> {
+        match ProofVersion::detect(&value) {
+            ProofVersion::Stone5 => Ok(serde_json::from_value(value)?),
+            ProofVersion::Stone6 => Self::from_stone6(value),
+        }
+    }
+
+    fn from_stone6(value: serde_json::Value) -> Result<Self> {
+        if value["proof_parameters"]
+            .get("n_verifier_friendly_commitment_layers")
+            .is_none()
+        {
+            return Err(Error::InvalidParameter(
+                "n_verifier_friendly_commitment_layers is required by Stone v6 proofs".to_string(),
+            ));
+        }
+
+        let proof_json: ProofJSON = serde_json::from_value(value)?;
+        match &proof_json.proof_parameters.stone6 {
+            None => Err(Error::InvalidParameter(
+                "incomplete Stone v6 proof parameters".to_string(),
+            )),
+            Some(params) if params.use_extension_field => Err(Error::InvalidParameter(
+                "extension field proofs are not supported".to_string(),
+            )),
+            Some(_) => Ok(proof_json),
+        }
+    }
+
+    pub fn version(&self) -> ProofVersion {
+        match self.proof_parameters.stone6 {
+            Some(_) => ProofVersion::Stone6,
+            None => ProofVersion::Stone5,
+        }
+    }
+
+    pub fn prover_config(&self) -> &ProverConfig {
+        &self.prover_config
+    }
+
+    pub fn stark_config(&self) -> Result<StarkConfig> {
         let stark = &self.proof_parameters.stark;
         let n_verifier_friendly_commitment_layers =
             self.proof_parameters.n_verifier_friendly_commitment_layers;
 
-        let consts = match self
-            .public_input
-            .layout
-            .get_dynamics_or_consts(&self.public_input.dynamic_params)
-        {
-            Some(c) => c,
-            None => anyhow::bail!(
-                "There were some constant overrides in the dynamic params but couldn't be parsed!"
-            ),
-        };
+        let consts = self.layout_consts()?;
 
         let log_eval_domain_size = self.log_eval_damain_size()?;
         let traces = TracesConfig {
@@ -121,8 +222,9 @@ impl ProofJSON {
         let layer_log_sizes = self.layer_log_sizes()?;
 
         let fri_step_list = fri.fri_step_list;
-        let log_last_layer_degree_bound = log2_if_power_of_2(fri.last_layer_degree_bound)
-            .ok_or(anyhow::anyhow!("Invalid last layer degree bound"))?;
+        let log_last_layer_degree_bound = log2_if_power_of_2(fri.last_layer_degree_bound).ok_or(
+            Error::InvalidParameter("last layer degree bound is not a power of 2".to_string()),
+        )?;
         let fri = FriConfig {
             log_input_size: layer_log_sizes[0],
             n_layers: fri_step_list.len() as u32,
@@ -150,21 +252,135 @@ impl ProofJSON {
             n_queries,
             log_n_cosets: stark.log_n_cosets,
             n_verifier_friendly_commitment_layers,
+            hashes: match &self.proof_parameters.stone6 {
+                Some(params) => HashSettings::from_stone6(params)?,
+                None => HashSettings::default(),
+            },
         })
     }
 
-    fn log_trace_domain_size(&self) -> anyhow::Result<u32> {
-        let consts = self.public_input.layout.get_consts();
+    /// The layout constants, with the mask length taken from the OODS values in the
+    /// annotations when there are any. The tabulated mask length is then only checked.
+    pub(crate) fn layout_consts(&self) -> Result<LayoutConstants> {
+        let layout = self.public_input.layout;
+        let n_oods = self.annotated_oods_len();
+        if let (Some(table), Some(n_oods)) = (layout.get_consts(), n_oods) {
+            let expected = table.mask_len + table.constraint_degree as usize;
+            let overridden =
+                self.public_input.dynamic_params.as_ref().is_some_and(|p| {
+                    p.contains_key("mask_len") || p.contains_key("constraint_degree")
+                });
+            if n_oods != expected && !overridden {
+                return Err(Error::InvalidLayout(format!(
+                    "{n_oods} OODS values in the annotations, layout {layout} has {expected}"
+                )));
+            }
+        }
+
+        match layout.get_dynamics_or_consts(&self.public_input.dynamic_params, n_oods) {
+            Some(c) => Ok(c),
+            None if self.public_input.dynamic_params.is_none() => n_oods
+                .and_then(|n_oods| {
+                    let leaves = [
+                        Annotation::OriginalWitnessLeaves,
+                        Annotation::InteractionWitnessLeaves,
+                        Annotation::CompositionWitnessLeaves,
+                    ]
+                    .map(|annotation| self.annotated_len(annotation));
+                    layout.consts_from_annotations(n_oods, leaves)
+                })
+                .ok_or_else(|| {
+                    Error::InvalidLayout(format!(
+                        "no constants for layout {layout}, pass them in the dynamic params"
+                    ))
+                }),
+            None => Err(Error::InvalidLayout(
+                "There were some constant overrides in the dynamic params but couldn't be parsed!"
+                    .to_string(),
+            )),
+        }
+    }
+
+    // Number of OODS values in the annotations, `None` without annotations.
+    fn annotated_oods_len(&self) -> Option<usize> {
+        let n_oods = self.annotated_len(Annotation::OodsValues);
+        (n_oods > 0).then_some(n_oods)
+    }
+
+    // Number of values of `annotation` in the annotations.
+    fn annotated_len(&self, annotation: Annotation) -> usize {
+        let path = annotation.prefix_and_kinds().prefix;
+        let lines = self
+            .annotations
+            .iter()
+            .map(String::as_str)
+            .filter(|line| line.contains(&path))
+            .collect::<Vec<_>>();
+        annotation.extract(&AnnotationIndex::new(&lines)).len()
+    }
+
+    /// The structure of the proof body `felts`, see `proof_structure`.
+    pub(crate) fn sized_proof_structure(&self, felts: &[Felt]) -> Result<ProofStructure> {
+        self.proof_structure(&self.stark_config()?, &self.cairo_public_input()?, felts)
+    }
+
+    /// Sizes the witness from the query indices replayed from the commitments. For proofs
+    /// whose commitments cannot be read, or whose channel is not replayed, the witness of a
+    /// proof without duplicated queries is resized to `felts`, see `ProofStructure::resized`.
+    fn proof_structure(
+        &self,
+        config: &StarkConfig,
+        public_input: &CairoPublicInput<Felt>,
+        felts: &[Felt],
+    ) -> Result<ProofStructure> {
+        let consts = self.layout_consts()?;
+        let base = ProofStructure::new(
+            &self.proof_parameters,
+            &self.prover_config,
+            self.public_input.layout,
+            consts,
+            config.fri.log_input_size,
+            None,
+        )?;
+
+        let mut reader = FeltReader::new(felts);
+        let commitment = StarkUnsentCommitment::deserialize_felt(&mut reader, &base);
+        if let (Ok(()), Ok(commitment)) = (config.hashes.check_channel(), commitment) {
+            let (queries, _) = replay_queries(config, public_input, &commitment)?;
+            let exact = ProofStructure::for_queries(
+                &self.proof_parameters,
+                consts,
+                &queries,
+                config.fri.log_input_size,
+            );
+            if exact.expected_len() == felts.len() {
+                return Ok(exact);
+            }
+        }
+
+        ProofStructure::new(
+            &self.proof_parameters,
+            &self.prover_config,
+            self.public_input.layout,
+            consts,
+            config.fri.log_input_size,
+            Some(felts.len()),
+        )
+    }
+
+    fn log_trace_domain_size(&self) -> Result<u32> {
+        let consts = self.layout_consts()?;
         let effective_component_height = Self::COMPONENT_HEIGHT * consts.cpu_component_step;
-        log2_if_power_of_2(effective_component_height * self.public_input.n_steps)
-            .ok_or(anyhow::anyhow!("Invalid cpu component step"))
+        log2_if_power_of_2(effective_component_height * self.public_input.n_steps).ok_or(
+            Error::InvalidParameter("trace domain size is not a power of 2".to_string()),
+        )
     }
 
-    fn log_eval_damain_size(&self) -> anyhow::Result<u32> {
+    pub(crate) fn log_eval_damain_size(&self) -> Result<u32> {
         Ok(self.log_trace_domain_size()? + self.proof_parameters.stark.log_n_cosets)
     }
 
-    fn layer_log_sizes(&self) -> anyhow::Result<Vec<u32>> {
+    fn layer_log_sizes(&self) -> Result<Vec<u32>> {
         let mut layer_log_sizes = vec![self.log_eval_damain_size()?];
         for layer_step in &self.proof_parameters.stark.fri.fri_step_list {
             layer_log_sizes.push(layer_log_sizes.last().unwrap() - layer_step);
@@ -172,25 +388,21 @@ impl ProofJSON {
         Ok(layer_log_sizes)
     }
 
-    pub fn public_input(
-        public_input: PublicInput,
-        // z: BigUint,
-        // alpha: BigUint,
-    ) -> anyhow::Result<CairoPublicInput<Felt>> {
+    /// The public input without continuous page headers, see `cairo_public_input`.
+    pub fn public_input(public_input: PublicInput) -> Result<CairoPublicInput<Felt>> {
         let continuous_page_headers = vec![];
-        // Self::continuous_page_headers(&public_input.public_memory, z, alpha)?; this line does for now anyway
-        let main_page = Self::main_page(&public_input.public_memory)?;
+        let (main_page, continuous_pages) =
+            Self::public_memory(&public_input.public_memory)?.into_pages();
         let dynamic_params = public_input
             .dynamic_params
             .unwrap_or_default()
             .into_iter()
             .map(|e| {
-                Ok((
-                    e.0,
-                    Felt::from_hex(&e.1.to_str_radix(16)).context("Invalid dynamic param")?,
-                ))
+                let value = Felt::from_hex(&e.1.to_str_radix(16))
+                    .map_err(|_| Error::InvalidValue(format!("dynamic param {}", e.0)))?;
+                Ok((e.0, value))
             })
-            .collect::<anyhow::Result<_>>()?;
+            .collect::<Result<_>>()?;
         let memory_segments = Builtin::sort_segments(public_input.memory_segments)
             .into_iter()
             .map(|s| SegmentInfo {
@@ -198,14 +410,16 @@ impl ProofJSON {
                 stop_ptr: s.stop_ptr,
             })
             .collect::<Vec<_>>();
-        let layout = Felt::from_hex(&prefix_hex::encode(public_input.layout.bytes_encode()))?;
+        let layout = Felt::from_hex(&prefix_hex::encode(public_input.layout.bytes_encode()))
+            .map_err(|_| Error::InvalidLayout(public_input.layout.to_string()))?;
         let (padding_addr, padding_value) = match public_input.public_memory.first() {
-            Some(m) => (m.address, Felt::from_hex(&m.value)?),
-            None => anyhow::bail!("Invalid public memory"),
+            Some(m) => (m.address, Self::memory_value(m)?),
+            None => return Err(Error::InvalidValue("empty public memory".to_string())),
         };
         Ok(CairoPublicInput {
-            log_n_steps: log2_if_power_of_2(public_input.n_steps)
-                .ok_or(anyhow::anyhow!("Invalid number of steps"))?,
+            log_n_steps: log2_if_power_of_2(public_input.n_steps).ok_or(
+                Error::InvalidParameter("number of steps is not a power of 2".to_string()),
+            )?,
             range_check_min: public_input.rc_min,
             range_check_max: public_input.rc_max,
             layout,
@@ -218,31 +432,155 @@ impl ProofJSON {
             main_page,
             n_continuous_pages: continuous_page_headers.len(),
             continuous_page_headers,
+            continuous_pages,
         })
     }
 
-    fn main_page(
-        public_memory: &[PublicMemoryElement],
-    ) -> anyhow::Result<Vec<PublicMemoryCell<Felt>>> {
-        public_memory
-            .iter()
-            .filter(|m| m.page == 0)
-            .map(|m| {
-                Ok(PublicMemoryCell {
-                    address: m.address,
-                    value: Felt::from_hex(&m.value).context("Invalid memory value")?,
+    fn public_memory(public_memory: &[PublicMemoryElement]) -> Result<PublicMemory> {
+        PublicMemory::from_cells(
+            public_memory
+                .iter()
+                .map(|m| {
+                    let cell = PublicMemoryCell {
+                        address: m.address,
+                        value: Self::memory_value(m)?,
+                    };
+                    Ok((m.page, cell))
                 })
+                .collect::<Result<Vec<_>>>()?,
+        )
+    }
+
+    fn memory_value(m: &PublicMemoryElement) -> Result<Felt> {
+        Felt::from_hex(&m.value)
+            .map_err(|_| Error::InvalidValue(format!("memory value at address {}", m.address)))
+    }
+
+    /// The public input with the headers of its continuous pages, which need the memory
+    /// interaction elements from the annotations.
+    pub fn cairo_public_input(&self) -> Result<CairoPublicInput<Felt>> {
+        let mut public_input = Self::public_input(self.public_input.clone())?;
+        if !public_input.continuous_pages.is_empty() {
+            let (z, alpha) = self.interaction_elements("continuous page headers")?;
+            public_input.compute_page_headers(z, alpha);
+        }
+        Ok(public_input)
+    }
+
+    // The memory interaction elements `z` and `alpha`, for the named use.
+    fn interaction_elements(&self, purpose: &str) -> Result<(Felt, Felt)> {
+        let annotations = self
+            .annotations
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let ZAlpha { z, alpha } = ZAlpha::extract(&annotations).map_err(|e| match e {
+            Error::Annotations(msg) => {
+                Error::Annotations(format!("{purpose} require the interaction elements: {msg}"))
+            }
+            e => e,
+        })?;
+        Ok((bigint_to_fe(&z), bigint_to_fe(&alpha)))
+    }
+
+    /// The proof data in the `annotations` lines.
+    pub fn parsed_annotations(&self) -> Result<Annotations> {
+        Annotations::new(
+            &self
+                .annotations
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>(),
+            self.proof_parameters.stark.fri.fri_step_list.len(),
+        )
+    }
+
+    /// Supplies the proof body referenced by `proof_file`, or replaces the inline payload.
+    pub fn with_proof_bytes(mut self, bytes: Vec<u8>) -> Self {
+        self.proof_file_bytes = Some(bytes);
+        self
+    }
+
+    /// Reads the `proof_file` the JSON refers to from `dir`. The path must be relative and
+    /// stay under `dir`, so a proof JSON cannot read files elsewhere.
+    pub fn load_proof_file(self, dir: &Path) -> Result<Self> {
+        match &self.proof_file {
+            Some(path) if self.proof_file_bytes.is_none() => {
+                if !path
+                    .components()
+                    .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+                {
+                    return Err(Error::InvalidParameter(format!(
+                        "proof_file {} is not a relative path under the proof JSON's directory",
+                        path.display()
+                    )));
+                }
+                let bytes = read_file(&dir.join(path))?;
+                Ok(self.with_proof_bytes(bytes))
+            }
+            _ => Ok(self),
+        }
+    }
+
+    fn has_proof_payload(&self) -> bool {
+        self.proof_b64.is_some()
+            || !self.proof_hex.trim().is_empty()
+            || self.proof_file.is_some()
+            || self.proof_file_bytes.is_some()
+    }
+
+    fn external_proof_bytes(&self) -> Result<Option<&[u8]>> {
+        match (&self.proof_file_bytes, &self.proof_file) {
+            (Some(bytes), _) => Ok(Some(bytes)),
+            (None, Some(path)) => Err(Error::InvalidParameter(format!(
+                "proof_file {} is not loaded, use parse_file or parse_with_proof_bytes",
+                path.display()
+            ))),
+            (None, None) => Ok(None),
+        }
+    }
+
+    pub(crate) fn proof_bytes(&self) -> Result<Vec<u8>> {
+        if let Some(bytes) = self.external_proof_bytes()? {
+            return Ok(bytes.to_vec());
+        }
+        if let Some(b64) = &self.proof_b64 {
+            return BASE64
+                .decode(b64.trim())
+                .map_err(|_| Error::HexDecode("invalid base64".to_string()));
+        }
+
+        let payload = self.proof_hex.trim();
+        if payload.starts_with("0x") {
+            prefix_hex::decode(payload).map_err(|_| Error::HexDecode("invalid hex".to_string()))
+        } else if payload.len().is_multiple_of(2) && payload.bytes().all(|b| b.is_ascii_hexdigit())
+        {
+            prefix_hex::decode(format!("0x{payload}"))
+                .map_err(|_| Error::HexDecode("invalid hex".to_string()))
+        } else {
+            BASE64.decode(payload).map_err(|_| {
+                Error::HexDecode("proof_hex is neither valid hex nor base64".to_string())
             })
-            .collect::<anyhow::Result<Vec<_>>>()
+        }
     }
 
-    fn _continuous_page_headers(
-        _public_memory: &[PublicMemoryElement],
-        _z: BigUint,
-        _alpha: BigUint,
-    ) -> anyhow::Result<Vec<BigUint>> {
-        //TODO: Do it properly
-        Ok(vec![])
+    /// The proof payload as felts. Hex payloads are decoded word by word, without building the
+    /// intermediate byte buffer.
+    pub(crate) fn proof_felts(&self) -> Result<HexProof> {
+        if let Some(bytes) = self.external_proof_bytes()? {
+            return Ok(HexProof::from(bytes));
+        }
+        let payload = self.proof_hex.trim();
+        if self.proof_b64.is_none() {
+            if let Some(digits) = payload.strip_prefix("0x") {
+                return HexProof::from_hex(digits);
+            }
+            if payload.len().is_multiple_of(2) && payload.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return HexProof::from_hex(payload);
+            }
+        }
+
+        Ok(HexProof::from(self.proof_bytes()?.as_slice()))
     }
 
     fn stark_unsent_commitment(&self, annotations: &Annotations) -> StarkUnsentCommitment {
@@ -269,11 +607,11 @@ impl ProofJSON {
             interaction_authentications: bigints_to_fe(&annotations.interaction_authentications),
             composition_leaves: bigints_to_fe(&annotations.composition_leaves),
             composition_authentications: bigints_to_fe(&annotations.composition_authentications),
-            fri_witness: FriWitness {
+            fri_witness: StoneFriWitness {
                 layers: annotations
                     .fri_witnesses
                     .iter()
-                    .map(|w| FriLayerWitness {
+                    .map(|w| StoneFriLayerWitness {
                         leaves: bigints_to_fe(&w.leaves),
                         table_witness: bigints_to_fe(&w.authentications),
                     })
@@ -284,34 +622,77 @@ impl ProofJSON {
 }
 
 #[derive(Debug)]
-struct HexProof(Vec<Felt>);
-
-impl TryFrom<&str> for HexProof {
-    type Error = anyhow::Error;
-    fn try_from(value: &str) -> anyhow::Result<Self> {
-        let hex: Vec<u8> = prefix_hex::decode(value).map_err(|_| anyhow!("Invalid hex"))?;
-        let mut result = vec![];
-        for chunk in hex.chunks(32) {
-            result.push(Felt::from_bytes_be_slice(chunk));
+pub(crate) struct HexProof(pub(crate) Vec<Felt>);
+
+impl From<&[u8]> for HexProof {
+    fn from(bytes: &[u8]) -> Self {
+        let chunks = bytes.chunks_exact(32);
+        let remainder = chunks.remainder();
+
+        let word = |chunk: &[u8]| Felt::from_bytes_be(chunk.try_into().expect("chunk of 32 bytes"));
+
+        let mut result = Vec::with_capacity(bytes.len().div_ceil(32));
+        // Whole words go through the fixed-size conversion, avoiding the per-chunk length handling.
+        // With the `parallel` feature the Montgomery conversion of the words is spread across threads.
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            let whole = bytes.len() - remainder.len();
+            result.par_extend(bytes[..whole].par_chunks_exact(32).map(word));
+        }
+        #[cfg(not(feature = "parallel"))]
+        result.extend(chunks.map(word));
+        if !remainder.is_empty() {
+            result.push(Felt::from_bytes_be_slice(remainder));
         }
 
+        HexProof(result)
+    }
+}
+
+impl HexProof {
+    /// Decodes unprefixed hex digits, 64 at a time, straight into felts.
+    pub(crate) fn from_hex(digits: &str) -> Result<Self> {
+        let digits = digits.as_bytes();
+        if !digits.len().is_multiple_of(2) {
+            return Err(Error::HexDecode("invalid hex".to_string()));
+        }
+
+        let word = |chunk: &[u8]| -> Result<Felt> {
+            let mut bytes = [0u8; 32];
+            for (byte, pair) in bytes.iter_mut().zip(chunk.chunks_exact(2)) {
+                *byte = (hex_digit(pair[0])? << 4) | hex_digit(pair[1])?;
+            }
+            Ok(Felt::from_bytes_be_slice(&bytes[..chunk.len() / 2]))
+        };
+
+        #[cfg(feature = "parallel")]
+        let result = {
+            use rayon::prelude::*;
+            digits.par_chunks(64).map(word).collect::<Result<_>>()?
+        };
+        #[cfg(not(feature = "parallel"))]
+        let result = digits.chunks(64).map(word).collect::<Result<_>>()?;
+
         Ok(HexProof(result))
     }
 }
 
-pub fn proof_from_annotations(value: ProofJSON) -> anyhow::Result<StarkProof> {
+fn hex_digit(digit: u8) -> Result<u8> {
+    match digit {
+        b'0'..=b'9' => Ok(digit - b'0'),
+        b'a'..=b'f' => Ok(digit - b'a' + 10),
+        b'A'..=b'F' => Ok(digit - b'A' + 10),
+        _ => Err(Error::HexDecode("invalid hex".to_string())),
+    }
+}
+
+pub fn proof_from_annotations(value: ProofJSON) -> Result<StarkProof> {
     let config = value.stark_config()?;
 
-    let annotations = Annotations::new(
-        &value
-            .annotations
-            .iter()
-            .map(String::as_str)
-            .collect::<Vec<_>>(),
-        value.proof_parameters.stark.fri.fri_step_list.len(),
-    )?;
+    let annotations = value.parsed_annotations()?;
 
-    let public_input = ProofJSON::public_input(value.public_input.clone())?;
+    let public_input = value.cairo_public_input()?;
 
     let unsent_commitment = value.stark_unsent_commitment(&annotations);
     let witness = ProofJSON::stark_witness(&annotations);
@@ -321,79 +702,831 @@ pub fn proof_from_annotations(value: ProofJSON) -> anyhow::Result<StarkProof> {
         public_input,
         unsent_commitment,
         witness: witness.into(),
+        source_digest: None,
     })
 }
 
 impl TryFrom<ProofJSON> for StarkProof {
-    type Error = anyhow::Error;
-    fn try_from(value: ProofJSON) -> anyhow::Result<Self> {
-        let config = value.stark_config()?;
-
-        let public_input = ProofJSON::public_input(
-            value.public_input.clone(),
-            // annotations.z.clone(),
-            // annotations.alpha.clone(),
-        )?;
+    type Error = Error;
+    fn try_from(value: ProofJSON) -> Result<Self> {
+        if value.has_proof_payload() {
+            proof_from_hex(value, &ParseOptions::default())
+        } else {
+            proof_from_annotations(value)
+        }
+    }
+}
+
+impl TryFrom<&StarkProof> for ProofJSON {
+    type Error = Error;
+    fn try_from(proof: &StarkProof) -> Result<Self> {
+        let config = &proof.config;
+        let public_input = &proof.public_input;
+        let witness = &proof.witness;
+
+        if public_input.continuous_pages.len() != public_input.n_continuous_pages {
+            return Err(Error::InvalidValue(
+                "continuous pages without their cells cannot be re-emitted as Stone public memory"
+                    .to_string(),
+            ));
+        }
+
+        let layout = Layout::from_felt(public_input.layout)?;
+        let builtins = [Builtin::Program, Builtin::Execution]
+            .into_iter()
+            .chain(layout.builtins(&public_input.dynamic_params))
+            .collect::<Vec<_>>();
+        if public_input.segments.len() > builtins.len() {
+            return Err(Error::InvalidLayout(format!(
+                "{layout} has {} segments, got {}",
+                builtins.len(),
+                public_input.segments.len()
+            )));
+        }
+        let memory_segments = builtins
+            .iter()
+            .zip(&public_input.segments)
+            .map(|(builtin, segment)| {
+                (
+                    builtin.name().to_string(),
+                    MemorySegmentAddress {
+                        begin_addr: segment.begin_addr,
+                        stop_ptr: segment.stop_ptr,
+                    },
+                )
+            })
+            .collect();
+
+        let public_memory = [&public_input.main_page]
+            .into_iter()
+            .chain(&public_input.continuous_pages)
+            .enumerate()
+            .flat_map(|(page, cells)| {
+                cells.iter().map(move |cell| PublicMemoryElement {
+                    address: cell.address,
+                    page: page as u32,
+                    value: format!("{:#x}", cell.value),
+                })
+            })
+            .collect();
+
+        let dynamic_params = (!public_input.dynamic_params.is_empty()).then(|| {
+            public_input
+                .dynamic_params
+                .iter()
+                .map(|(k, v)| (k.clone(), BigUint::from_bytes_be(&v.to_bytes_be())))
+                .collect()
+        });
+
+        let fri_step_list = config.fri.fri_step_sizes.clone();
+        let n_queries = config.n_queries;
+
+        // The prover config is not part of the proof, but the task size is what sizes the
+        // trace authentications, so it is read back from them.
+        let constraint_polynomial_task_size = u32::try_from(witness.original_authentications.len())
+            .map_err(|_| {
+                Error::InvalidValue(format!(
+                    "{} original authentications",
+                    witness.original_authentications.len()
+                ))
+            })?;
+        let prover_config = ProverConfig {
+            constraint_polynomial_task_size,
+            ..ProverConfig::default()
+        };
+
+        let proof_bytes = proof
+            .encode_body()
+            .iter()
+            .flat_map(|felt| felt.to_bytes_be())
+            .collect::<Vec<u8>>();
+
+        Ok(ProofJSON {
+            proof_parameters: ProofParameters {
+                stark: Stark {
+                    fri: Fri {
+                        fri_step_list,
+                        last_layer_degree_bound: 1 << config.fri.log_last_layer_degree_bound,
+                        n_queries,
+                        proof_of_work_bits: config.proof_of_work.n_bits,
+                    },
+                    log_n_cosets: config.log_n_cosets,
+                },
+                n_verifier_friendly_commitment_layers: config.n_verifier_friendly_commitment_layers,
+                stone6: None,
+            },
+            annotations: vec![],
+            public_input: PublicInput {
+                dynamic_params,
+                layout,
+                memory_segments,
+                n_steps: 1 << public_input.log_n_steps,
+                public_memory,
+                rc_min: public_input.range_check_min,
+                rc_max: public_input.range_check_max,
+            },
+            proof_hex: prefix_hex::encode(proof_bytes),
+            proof_b64: None,
+            proof_file: None,
+            proof_file_bytes: None,
+            prover_config,
+        })
+    }
+}
+
+impl StarkProof {
+    /// Re-emits the proof as a Stone proof JSON, with a regenerated `proof_hex`.
+    pub fn to_stone_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&ProofJSON::try_from(self)?)?)
+    }
+
+    /// Same as `to_stone_json`, with the annotations regenerated by `annotations::annotate`.
+    pub fn to_annotated_stone_json(&self) -> Result<String> {
+        let mut proof_json = ProofJSON::try_from(self)?;
+        proof_json.annotations = annotate(self)?;
+        Ok(serde_json::to_string(&proof_json)?)
+    }
+}
+
+pub fn proof_from_hex(value: ProofJSON, options: &ParseOptions) -> Result<StarkProof> {
+    let config = value.stark_config()?;
+
+    let public_input = value.cairo_public_input()?;
+
+    let hex = value.proof_felts()?;
+
+    let proof_structure = value.proof_structure(&config, &public_input, &hex.0)?;
+
+    let mut proof = StarkProof::decode_body(&hex.0, &proof_structure, &config, public_input)?;
+
+    let fri = &mut proof.unsent_commitment.fri;
+    fri.last_layer_coefficients = options
+        .last_layer_coefficients
+        .decode(std::mem::take(&mut fri.last_layer_coefficients));
+
+    if options.verify_consistency {
+        let from_annotations = proof_from_annotations(value.clone())?;
+        let differences = crate::diff::diff(&proof, &from_annotations)
+            .map_err(|e| Error::InvalidValue(e.to_string()))?;
+        if let Some(difference) = differences.first() {
+            return Err(Error::Verification(format!(
+                "proof hex and annotations disagree at {difference}"
+            )));
+        }
+    }
+
+    Ok(proof)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::BTreeSet;
+
+    use serde_felt::montgomery_to_felts;
+
+    use super::*;
+
+    pub(crate) fn proof_json_str(proof_fields: &str) -> String {
+        format!(
+            r#"{{
+                "proof_parameters": {{
+                    "stark": {{
+                        "fri": {{
+                            "fri_step_list": [0, 4],
+                            "last_layer_degree_bound": 4,
+                            "n_queries": 2,
+                            "proof_of_work_bits": 30
+                        }},
+                        "log_n_cosets": 3
+                    }}
+                }},
+                "public_input": {{
+                    "layout": "recursive",
+                    "memory_segments": {{
+                        "program": {{ "begin_addr": 1, "stop_ptr": 5 }},
+                        "execution": {{ "begin_addr": 10, "stop_ptr": 20 }},
+                        "output": {{ "begin_addr": 20, "stop_ptr": 22 }}
+                    }},
+                    "n_steps": 16384,
+                    "public_memory": [
+                        {{ "address": 1, "page": 0, "value": "0x2a" }},
+                        {{ "address": 2, "page": 0, "value": "0x2b" }}
+                    ],
+                    "rc_min": 0,
+                    "rc_max": 100
+                }},
+                "prover_config": {{
+                    "constraint_polynomial_task_size": 256,
+                    "n_out_of_memory_merkle_layers": 1,
+                    "table_prover_n_tasks_per_segment": 1
+                }},
+                {proof_fields}
+            }}"#
+        )
+    }
 
-        let hex = HexProof::try_from(value.proof_hex.as_str())?;
+    fn proof_json(proof_fields: &str) -> ProofJSON {
+        serde_json::from_str(&proof_json_str(proof_fields)).unwrap()
+    }
 
-        let proof_structure = ProofStructure::new(
-            &value.proof_parameters,
-            &value.prover_config,
-            value.public_input.layout,
-            Some(hex.0.len()),
+    // `proof_json_str` with another layout, for tests to adjust before deserializing.
+    fn proof_json_with_layout(layout: &str, proof_hex: &str) -> serde_json::Value {
+        let mut value: serde_json::Value =
+            serde_json::from_str(&proof_json_str(&format!(r#""proof_hex": "{proof_hex}""#)))
+                .unwrap();
+        value["public_input"]["layout"] = layout.into();
+        value
+    }
+
+    // A proof hex of the length expected by `proof_json_str`, with arbitrary felts.
+    pub(crate) fn dummy_proof_hex() -> String {
+        dummy_proof_hex_for(&proof_json(r#""proof_hex": """#))
+    }
+
+    fn dummy_proof_hex_for(proof_json: &ProofJSON) -> String {
+        let len = ProofStructure::new(
+            &proof_json.proof_parameters,
+            &proof_json.prover_config,
+            proof_json.public_input.layout,
+            proof_json.layout_consts().unwrap(),
+            proof_json.log_eval_damain_size().unwrap(),
+            None,
+        )
+        .unwrap()
+        .expected_len();
+
+        let hex = (1..=len).map(|i| format!("{i:064x}")).collect::<String>();
+        format!("0x{hex}")
+    }
+
+    // Section lengths of a Stone proof body with the `proof_json_str` FRI parameters, for an
+    // AIR with the given trace columns and mask length as listed in Stone's layout
+    // definitions. Pinned here rather than derived from `LayoutConstants`. The FRI table
+    // witness is a full path per query through the 2^17 cosets of the first FRI layer.
+    fn stone_section_lens((first, second): (usize, usize), mask_len: usize) -> [usize; 15] {
+        let oods = mask_len + 2;
+        [
+            1,
+            1,
+            1,
+            oods,
+            1,
+            4,
+            1,
+            2 * first,
+            256,
+            2 * second,
+            256,
+            4,
+            256,
+            240,
+            2 * 17,
+        ]
+    }
+
+    // Felt `i` of `section`, so that a misplaced section boundary decodes to the wrong value.
+    fn section_felt(section: usize, i: usize) -> u64 {
+        ((section as u64) << 32) | i as u64
+    }
+
+    fn section_felts(section: usize, len: usize) -> Vec<Felt> {
+        (0..len).map(|i| section_felt(section, i).into()).collect()
+    }
+
+    fn stone_proof_hex(lens: &[usize]) -> String {
+        let hex = lens
+            .iter()
+            .enumerate()
+            .flat_map(|(section, &len)| (0..len).map(move |i| section_felt(section, i)))
+            .map(|felt| format!("{felt:064x}"))
+            .collect::<String>();
+        format!("0x{hex}")
+    }
+
+    fn assert_stone_sections(proof: &StarkProof, lens: &[usize; 15]) {
+        let commitment = &proof.unsent_commitment;
+        assert_eq!(commitment.traces.original, section_felts(0, 1)[0]);
+        assert_eq!(commitment.traces.interaction, section_felts(1, 1)[0]);
+        assert_eq!(commitment.composition, section_felts(2, 1)[0]);
+        assert_eq!(commitment.oods_values, section_felts(3, lens[3]));
+        assert_eq!(commitment.fri.inner_layers, section_felts(4, lens[4]));
+        assert_eq!(
+            commitment.fri.last_layer_coefficients,
+            section_felts(5, lens[5])
+        );
+        assert_eq!(commitment.proof_of_work_nonce, section_felts(6, 1)[0]);
+
+        let witness = &proof.witness;
+        let leaves = |section: usize| montgomery_to_felts(&section_felts(section, lens[section]));
+        assert_eq!(witness.original_leaves, leaves(7));
+        assert_eq!(witness.original_authentications, section_felts(8, lens[8]));
+        assert_eq!(witness.interaction_leaves, leaves(9));
+        assert_eq!(
+            witness.interaction_authentications,
+            section_felts(10, lens[10])
+        );
+        assert_eq!(witness.composition_leaves, leaves(11));
+        assert_eq!(
+            witness.composition_authentications,
+            section_felts(12, lens[12])
         );
+        let fri_layer = &witness.fri_witness.layers[0];
+        assert_eq!(fri_layer.leaves, leaves(13));
+        assert_eq!(fri_layer.table_witness, section_felts(14, lens[14]));
+    }
 
-        assert_eq!(hex.0.len(), proof_structure.expected_len());
-
-        let (unsent_commitment, witness): (StarkUnsentCommitment, StarkWitness) =
-            from_felts_with_lengths(
-                &hex.0,
-                vec![
-                    ("oods_values", vec![proof_structure.oods]),
-                    ("inner_layers", vec![proof_structure.layer_count]),
-                    (
-                        "last_layer_coefficients",
-                        vec![proof_structure.last_layer_degree_bound],
-                    ),
-                    // WITNESS
-                    ("original_leaves", vec![proof_structure.first_layer_queries]),
-                    (
-                        "original_authentications",
-                        vec![proof_structure.authentications],
-                    ),
-                    (
-                        "interaction_leaves",
-                        vec![proof_structure.composition_decommitment],
-                    ),
-                    (
-                        "interaction_authentications",
-                        vec![proof_structure.authentications],
-                    ),
-                    (
-                        "composition_leaves",
-                        vec![proof_structure.composition_leaves],
-                    ),
-                    (
-                        "composition_authentications",
-                        vec![proof_structure.authentications],
-                    ),
-                    ("fri_witness", vec![proof_structure.witness.len()]),
-                    ("leaves", proof_structure.layer),
-                    ("table_witness", proof_structure.witness),
-                ]
+    #[test]
+    fn test_parse_starknet_with_keccak() {
+        let segments = [
+            "program",
+            "execution",
+            "output",
+            "pedersen",
+            "range_check",
+            "ecdsa",
+            "bitwise",
+            "ec_op",
+            "keccak",
+            "poseidon",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            format!(
+                r#""{name}": {{ "begin_addr": {}, "stop_ptr": {} }}"#,
+                i * 100 + 1,
+                i * 100 + 5
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+        let input = |proof_hex: &str| {
+            let mut value = proof_json_with_layout("starknet_with_keccak", proof_hex);
+            value["public_input"]["memory_segments"] =
+                serde_json::from_str(&format!("{{{segments}}}")).unwrap();
+            serde_json::from_value::<ProofJSON>(value).unwrap()
+        };
+
+        // Stone's starknet_with_keccak AIR: 12 + 3 trace columns and 734 mask values.
+        let lens = stone_section_lens((12, 3), 734);
+        let proof = StarkProof::try_from(input(&stone_proof_hex(&lens))).unwrap();
+
+        assert_eq!(proof.config.traces.original.n_columns, 12);
+        assert_eq!(proof.config.traces.interaction.n_columns, 3);
+        assert_eq!(proof.public_input.segments.len(), 10);
+        assert_stone_sections(&proof, &lens);
+
+        let reemitted: ProofJSON = serde_json::from_str(&proof.to_stone_json().unwrap()).unwrap();
+        assert_eq!(StarkProof::try_from(reemitted).unwrap(), proof);
+    }
+
+    #[test]
+    fn test_parse_fixed_layouts() {
+        // Trace columns and mask lengths of Stone's AIRs.
+        for (layout, columns, mask_len) in [
+            (Layout::Dex, (21, 1), 200),
+            (Layout::Plain, (6, 2), 49),
+            (Layout::RecursiveWithPoseidon, (6, 2), 192),
+            (Layout::Small, (23, 2), 201),
+        ] {
+            let segments = [Builtin::Program, Builtin::Execution]
                 .into_iter()
-                .map(|(k, v)| (k.to_string(), v))
-                .collect(),
-            )?;
+                .chain(layout.builtins(&BTreeMap::new()))
+                .enumerate()
+                .map(|(i, builtin)| {
+                    format!(
+                        r#""{}": {{ "begin_addr": {}, "stop_ptr": {} }}"#,
+                        builtin.name(),
+                        i * 100 + 1,
+                        i * 100 + 5
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            let input = |proof_hex: &str| {
+                let mut value = proof_json_with_layout(&layout.to_string(), proof_hex);
+                value["public_input"]["memory_segments"] =
+                    serde_json::from_str(&format!("{{{segments}}}")).unwrap();
+                serde_json::from_value::<ProofJSON>(value).unwrap()
+            };
+
+            let lens = stone_section_lens(columns, mask_len);
+            let proof = StarkProof::try_from(input(&stone_proof_hex(&lens))).unwrap();
+
+            assert_eq!(
+                proof.config.traces.original.n_columns as usize, columns.0,
+                "{layout}"
+            );
+            assert_stone_sections(&proof, &lens);
+            assert_eq!(
+                proof.public_input.segments.len(),
+                2 + layout.builtins(&BTreeMap::new()).len()
+            );
+
+            let reemitted: ProofJSON =
+                serde_json::from_str(&proof.to_stone_json().unwrap()).unwrap();
+            assert_eq!(StarkProof::try_from(reemitted).unwrap(), proof, "{layout}");
+        }
+    }
+
+    #[test]
+    fn test_parse_continuous_pages() {
+        let mut public_input = proof_json(r#""proof_hex": """#).public_input;
+        public_input
+            .public_memory
+            .extend(
+                [(20, 2), (21, 2), (30, 1)].map(|(address, page)| PublicMemoryElement {
+                    address,
+                    page,
+                    value: format!("{address:#x}"),
+                }),
+            );
+
+        let parsed = ProofJSON::public_input(public_input.clone()).unwrap();
+        assert_eq!(parsed.main_page.len(), 2);
+        assert_eq!(
+            parsed
+                .continuous_pages
+                .iter()
+                .map(|page| page.iter().map(|cell| cell.address).collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            [vec![30], vec![20, 21]]
+        );
+
+        let mut proof_json = proof_json(r#""proof_hex": """#);
+        proof_json.public_input = public_input.clone();
+        let err = proof_json.cairo_public_input().unwrap_err();
+        assert!(err.to_string().contains("continuous page headers"));
+
+        public_input.public_memory.retain(|m| m.page != 1);
+        assert!(ProofJSON::public_input(public_input).is_err());
+    }
+
+    // `proof_json_with_layout` with `dynamic_params`, given as the members of a JSON object.
+    fn proof_json_with_params(
+        layout: &str,
+        proof_hex: &str,
+        dynamic_params: Option<&str>,
+    ) -> ProofJSON {
+        let mut value = proof_json_with_layout(layout, proof_hex);
+        if let Some(params) = dynamic_params {
+            value["public_input"]["dynamic_params"] =
+                serde_json::from_str(&format!("{{{params}}}")).unwrap();
+        }
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_parse_dynamic_layout() {
+        let input = |proof_hex: &str, params| proof_json_with_params("dynamic", proof_hex, params);
+        let params = Some(
+            r#""cpu_component_step": [1], "constraint_degree": [2],
+            "num_columns_first": [5], "num_columns_second": [4], "mask_len": [90],
+            "uses_output_builtin": [1], "uses_pedersen_builtin": [0]"#,
+        );
+
+        let proof_hex = dummy_proof_hex_for(&input("", params));
+        let proof = StarkProof::try_from(input(&proof_hex, params)).unwrap();
+
+        assert_eq!(proof.config.traces.original.n_columns, 5);
+        assert_eq!(proof.config.traces.interaction.n_columns, 4);
+        assert_eq!(proof.unsent_commitment.oods_values.len(), 90 + 2);
+
+        let reemitted: ProofJSON = serde_json::from_str(&proof.to_stone_json().unwrap()).unwrap();
+        assert_eq!(StarkProof::try_from(reemitted).unwrap(), proof);
 
-        let proof = StarkProof {
-            config,
-            public_input,
-            unsent_commitment,
-            witness: witness.into(),
+        let err = input(&proof_hex, Some(r#""cpu_component_step": [1]"#))
+            .layout_consts()
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidLayout(_)), "{err}");
+    }
+
+    #[test]
+    fn test_all_cairo_constants_from_dynamic_params() {
+        let input =
+            |proof_hex: &str, params| proof_json_with_params("all_cairo", proof_hex, params);
+        // Placeholders, not Stone's all_cairo constants: this only checks that the dynamic
+        // params are picked up and that their absence is reported.
+        let params = r#""cpu_component_step": [1], "constraint_degree": [2],
+            "num_columns_first": [9], "num_columns_second": [3], "mask_len": [100]"#;
+
+        let proof_hex = dummy_proof_hex_for(&input("", Some(params)));
+        let proof = StarkProof::try_from(input(&proof_hex, Some(params))).unwrap();
+        assert_eq!(
+            Layout::from_felt(proof.public_input.layout).unwrap(),
+            Layout::AllCairo
+        );
+        assert_eq!(proof.config.traces.original.n_columns, 9);
+
+        let err = input(&proof_hex, None).layout_consts().unwrap_err();
+        assert!(err.to_string().contains("all_cairo"), "{err}");
+    }
+
+    #[test]
+    fn test_mask_len_from_annotations() {
+        let input = |layout: &str, params| {
+            let mut proof_json = proof_json_with_params(layout, "0x", params);
+            proof_json.annotations = vec![
+                "P->V[0:96]: /cpu air/STARK/Out Of Domain Sampling/OODS values: Field Elements(0x1,0x2,0x3)".to_string(),
+            ];
+            proof_json
+        };
+
+        // The dynamic params leave the mask length to the annotations.
+        let params = r#""cpu_component_step": [1], "constraint_degree": [2],
+            "num_columns_first": [5], "num_columns_second": [4]"#;
+        let consts = input("dynamic", Some(params)).layout_consts().unwrap();
+        assert_eq!(consts.mask_len, 1);
+
+        let err = input("recursive", None).layout_consts().unwrap_err();
+        assert!(err.to_string().contains("3 OODS values"), "{err}");
+    }
+
+    #[test]
+    fn test_all_cairo_constants_from_annotations() {
+        // Two decommitted rows of 4 + 2 trace columns and 2 composition columns.
+        let leaves = |trace: usize, n: usize| {
+            (0..n).map(move |i| {
+                format!(
+                    "P->V[0:32]: /cpu air/STARK/FRI/Decommitment/Layer 0/Virtual Oracle/Trace {trace}: Row {}, Column {}: Field Element(0x{i:x})",
+                    i % 2,
+                    i / 2
+                )
+            })
+        };
+        let annotations = leaves(0, 8)
+            .chain(leaves(1, 4))
+            .chain(leaves(2, 4))
+            .chain(["P->V[0:96]: /cpu air/STARK/Out Of Domain Sampling/OODS values: Field Elements(0x1,0x2,0x3,0x4,0x5)".to_string()])
+            .collect::<Vec<_>>();
+
+        for layout in ["all_cairo", "all_solidity"] {
+            let mut proof_json = proof_json_with_params(layout, "0x", None);
+            proof_json.annotations = annotations.clone();
+            let consts = proof_json.layout_consts().unwrap();
+            assert_eq!(
+                (consts.num_columns_first, consts.num_columns_second),
+                (4, 2)
+            );
+            assert_eq!(consts.mask_len, 5 - 2);
+        }
+
+        let mut proof_json = proof_json_with_params("dynamic", "0x", None);
+        proof_json.annotations = annotations;
+        assert!(proof_json.layout_consts().is_err());
+    }
+
+    #[test]
+    fn test_parse_stone6() {
+        let input = proof_json_str(&format!(r#""proof_hex": "{}""#, dummy_proof_hex()));
+        assert_eq!(
+            input.parse::<ProofJSON>().unwrap().version(),
+            ProofVersion::Stone5
+        );
+
+        let mut value: serde_json::Value = serde_json::from_str(&input).unwrap();
+        value["proof_parameters"]["n_verifier_friendly_commitment_layers"] = 0.into();
+        value["proof_parameters"]["channel_hash"] = "poseidon3".into();
+        value["proof_parameters"]["commitment_hash"] = "keccak256_masked160_lsb".into();
+        value["proof_parameters"]["pow_hash"] = "keccak256".into();
+        value["proof_parameters"]["statement"] = serde_json::json!({ "page_hash": "pedersen" });
+        value["proof_parameters"]["use_extension_field"] = false.into();
+        value["proof_parameters"]["verifier_friendly_channel_updates"] = true.into();
+        value["proof_parameters"]["verifier_friendly_commitment_hashes"] = false.into();
+        assert_eq!(ProofVersion::detect(&value), ProofVersion::Stone6);
+
+        let proof_json = ProofJSON::from_value(value.clone()).unwrap();
+        assert_eq!(proof_json.version(), ProofVersion::Stone6);
+        assert_eq!(
+            StarkProof::try_from(proof_json).unwrap(),
+            StarkProof::try_from(input.parse::<ProofJSON>().unwrap()).unwrap()
+        );
+
+        // Only the Poseidon channel is replayed, so a Keccak channel proof is sized without
+        // its queries and has no transcript.
+        let mut keccak_channel = value.clone();
+        keccak_channel["proof_parameters"]["channel_hash"] = "keccak256".into();
+        let proof = StarkProof::try_from(ProofJSON::from_value(keccak_channel).unwrap()).unwrap();
+        let err = proof.transcript().unwrap_err();
+        assert!(matches!(err, Error::Verification(_)), "{err}");
+
+        value["proof_parameters"]["use_extension_field"] = true.into();
+        let err = ProofJSON::from_value(value.clone()).unwrap_err();
+        assert!(matches!(err, Error::InvalidParameter(_)), "{err}");
+
+        value["proof_parameters"]
+            .as_object_mut()
+            .unwrap()
+            .remove("channel_hash");
+        let err = ProofJSON::from_value(value).unwrap_err();
+        assert!(matches!(err, Error::InvalidParameter(_)), "{err}");
+    }
+
+    #[test]
+    fn test_parse_sizes_witness_from_queries() {
+        let empty = proof_json(r#""proof_hex": """#);
+        let config = empty.stark_config().unwrap();
+        let public_input = ProofJSON::public_input(empty.public_input.clone()).unwrap();
+        let base = ProofStructure::new(
+            &empty.proof_parameters,
+            &empty.prover_config,
+            empty.public_input.layout,
+            empty.layout_consts().unwrap(),
+            config.fri.log_input_size,
+            None,
+        )
+        .unwrap();
+
+        // The commitment sections do not depend on the queries.
+        let mut lens = stone_section_lens((7, 3), 133);
+        let felts = (0..7)
+            .flat_map(|section| section_felts(section, lens[section]))
+            .collect::<Vec<_>>();
+        let commitment =
+            StarkUnsentCommitment::deserialize_felt(&mut FeltReader::new(&felts), &base).unwrap();
+        let (queries, _) = replay_queries(&config, &public_input, &commitment).unwrap();
+
+        // The witness sections counted from the Merkle paths of the queries, independently of
+        // `ProofStructure`: one authentication node per path node whose sibling is on no path.
+        let authentications = |leaves: &BTreeSet<u64>, height: u32| {
+            let paths = leaves
+                .iter()
+                .flat_map(|leaf| (0..height).map(move |level| ((1 << height) + leaf) >> level))
+                .collect::<BTreeSet<_>>();
+            paths
+                .iter()
+                .filter(|node| !paths.contains(&(*node ^ 1)))
+                .count()
+        };
+        let log_eval_domain_size = config.fri.log_input_size;
+        let rows = queries.iter().copied().collect::<BTreeSet<_>>();
+        let cosets = rows.iter().map(|row| row >> 4).collect::<BTreeSet<_>>();
+        lens[7] = 7 * rows.len();
+        lens[9] = 3 * rows.len();
+        lens[11] = 2 * rows.len();
+        for section in [8, 10, 12] {
+            lens[section] = authentications(&rows, log_eval_domain_size);
+        }
+        // The first FRI layer sends the coset values that are not the queries' own.
+        lens[13] = 16 * cosets.len() - rows.len();
+        lens[14] = authentications(&cosets, log_eval_domain_size - 4);
+
+        let hex = stone_proof_hex(&lens);
+        let proof = StarkProof::try_from(proof_json(&format!(r#""proof_hex": "{hex}""#))).unwrap();
+        assert_eq!(proof.query_indices().unwrap(), queries);
+        assert_stone_sections(&proof, &lens);
+
+        // Without an exact match the sections are still split, as a guess, even though the
+        // proof is far shorter than one without duplicated queries.
+        assert!(proof.to_felts().unwrap().len() < base.expected_len() / 2);
+        let longer = format!("{hex}{:064x}", 1);
+        let guessed =
+            StarkProof::try_from(proof_json(&format!(r#""proof_hex": "{longer}""#))).unwrap();
+        assert_eq!(
+            guessed.to_felts().unwrap().len(),
+            proof.to_felts().unwrap().len() + 1
+        );
+
+        // Verifier-friendly layers only change the hash of the upper Merkle layers, not the
+        // number of authentication nodes.
+        let mut friendly = proof_json(&format!(r#""proof_hex": "{hex}""#));
+        friendly
+            .proof_parameters
+            .n_verifier_friendly_commitment_layers = 9;
+        let friendly = StarkProof::try_from(friendly).unwrap();
+        assert_eq!(friendly.config.n_verifier_friendly_commitment_layers, 9);
+        assert_eq!(friendly.witness, proof.witness);
+    }
+
+    #[test]
+    fn test_parse_without_annotations() {
+        let input = proof_json_str(&format!(r#""proof_hex": "{}""#, dummy_proof_hex()));
+        assert!(!input.contains("annotations"));
+
+        let proof_json = serde_json::from_str::<ProofJSON>(&input).unwrap();
+        let proof = StarkProof::try_from(proof_json.clone()).unwrap();
+        assert_eq!(proof.config.n_queries, 2);
+
+        // The cross-check needs the annotations.
+        let options = ParseOptions {
+            verify_consistency: true,
+            ..ParseOptions::default()
         };
+        let err = proof_from_hex(proof_json, &options).unwrap_err();
+        assert!(matches!(err, Error::Annotations(_)), "{err}");
+    }
+
+    #[test]
+    fn test_parse_without_prover_config() {
+        let input = proof_json_str(&format!(r#""proof_hex": "{}""#, dummy_proof_hex()));
+        let mut value = serde_json::from_str::<serde_json::Value>(&input).unwrap();
+        value.as_object_mut().unwrap().remove("prover_config");
+
+        let proof_json = ProofJSON::from_value(value).unwrap();
+        assert_eq!(proof_json.prover_config(), &ProverConfig::default());
+
+        let proof = StarkProof::try_from(proof_json).unwrap();
+        assert_eq!(proof.config.n_queries, 2);
+    }
+
+    #[test]
+    fn test_parse_without_proof_hex_uses_annotations() {
+        let input = proof_json_str(r#""annotations": []"#);
+        let proof_json = serde_json::from_str::<ProofJSON>(&input).unwrap();
+
+        let err = StarkProof::try_from(proof_json).unwrap_err();
+        assert!(matches!(err, Error::Annotations(_)), "{err}");
+    }
+
+    #[test]
+    fn test_stone_json_roundtrip() {
+        let proof_json = proof_json(&format!(r#""proof_hex": "{}""#, dummy_proof_hex()));
+        let proof = StarkProof::try_from(proof_json.clone()).unwrap();
+
+        let reemitted: ProofJSON = serde_json::from_str(&proof.to_stone_json().unwrap()).unwrap();
+        assert_eq!(reemitted.proof_hex, proof_json.proof_hex);
+        assert_eq!(reemitted.public_input, proof_json.public_input);
+        assert_eq!(StarkProof::try_from(reemitted).unwrap(), proof);
+    }
+
+    #[test]
+    fn test_stone_json_prover_config() {
+        let mut proof = StarkProof::sample();
+        // Steps beyond the first layer's 16 and more table witness than queries used to
+        // underflow the guessed config.
+        proof.config.fri.fri_step_sizes = vec![0, 17];
+        proof.witness.original_authentications.truncate(3);
 
-        Ok(proof)
+        let proof_json = ProofJSON::try_from(&proof).unwrap();
+        assert_eq!(proof_json.prover_config.constraint_polynomial_task_size, 3);
+        assert_eq!(
+            proof_json.prover_config.n_out_of_memory_merkle_layers,
+            ProverConfig::default().n_out_of_memory_merkle_layers
+        );
+    }
+
+    #[test]
+    fn test_length_mismatch() {
+        let proof_json = proof_json(r#""proof_hex": "0xdeadbeef""#);
+
+        let err = StarkProof::try_from(proof_json).unwrap_err();
+        assert!(matches!(err, Error::LengthMismatch { got: 1, .. }), "{err}");
+    }
+
+    #[test]
+    fn test_proof_bytes_encodings() {
+        let expected = vec![0xde, 0xad, 0xbe, 0xef];
+
+        let prefixed = proof_json(r#""proof_hex": "0xdeadbeef""#);
+        let unprefixed = proof_json(r#""proof_hex": "deadbeef""#);
+        let detected = proof_json(r#""proof_hex": "3q2+7w==""#);
+        let explicit = proof_json(r#""proof_b64": "3q2+7w==""#);
+
+        assert_eq!(prefixed.proof_bytes().unwrap(), expected);
+        assert_eq!(unprefixed.proof_bytes().unwrap(), expected);
+        assert_eq!(detected.proof_bytes().unwrap(), expected);
+        assert_eq!(explicit.proof_bytes().unwrap(), expected);
+        assert!(proof_json(r#""proof_hex": "not a proof""#)
+            .proof_bytes()
+            .is_err());
+    }
+
+    #[test]
+    fn test_load_proof_file_stays_in_dir() {
+        let dir = Path::new("proofs");
+        for path in ["/etc/passwd", "../proof.bin", "bodies/../../proof.bin"] {
+            let proof_json = proof_json(&format!(r#""proof_file": "{path}""#));
+            let err = proof_json.load_proof_file(dir).unwrap_err();
+            assert!(matches!(err, Error::InvalidParameter(_)), "{path}: {err}");
+        }
+
+        let err = proof_json(r#""proof_file": "./missing.bin""#)
+            .load_proof_file(dir)
+            .unwrap_err();
+        assert!(matches!(err, Error::Io(_)), "{err}");
+    }
+
+    #[test]
+    fn test_hex_proof_from_bytes() {
+        let bytes = (0..100u8).map(|b| b.wrapping_mul(37)).collect::<Vec<_>>();
+        let expected = bytes
+            .chunks(32)
+            .map(Felt::from_bytes_be_slice)
+            .collect::<Vec<_>>();
+
+        assert_eq!(HexProof::from(bytes.as_slice()).0, expected);
+        assert!(HexProof::from(&[][..]).0.is_empty());
+
+        let digits = prefix_hex::encode(bytes.as_slice());
+        assert_eq!(HexProof::from_hex(&digits[2..]).unwrap().0, expected);
+        assert_eq!(
+            proof_json(&format!(r#""proof_hex": "{digits}""#))
+                .proof_felts()
+                .unwrap()
+                .0,
+            expected
+        );
+        assert!(HexProof::from_hex("0g").is_err());
+        assert!(HexProof::from_hex("abc").is_err());
     }
 }
@@ -0,0 +1,213 @@
+//! Test support for running the fact registration flow against a local Katana devnet.
+//!
+//! Requires the `katana` binary on `PATH` (or `KATANA_BIN`), and the mock fact registry
+//! of `contracts/mock_fact_registry` built with `scarb build`. `MOCK_FACT_REGISTRY_SIERRA`
+//! and `MOCK_FACT_REGISTRY_CASM` point to other artifacts.
+
+use std::{process::Stdio, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Context};
+use starknet::{
+    accounts::{Account, ExecutionEncoding, SingleOwnerAccount},
+    contract::ContractFactory,
+    core::types::{
+        contract::{CompiledClass, SierraClass},
+        BlockId, BlockTag, Felt,
+    },
+    providers::{jsonrpc::HttpTransport, JsonRpcClient, Provider},
+    signers::{LocalWallet, SigningKey},
+};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::{Child, Command},
+    time::{sleep, timeout},
+};
+use url::Url;
+
+use crate::registry::{wait_for_transaction, WaitPolicy};
+
+const KATANA_URL: &str = "http://127.0.0.1:5050";
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+const MOCK_FACT_REGISTRY_ARTIFACTS: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/contracts/mock_fact_registry/target/dev/mock_fact_registry_MockFactRegistry"
+);
+
+pub type KatanaAccount = SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>;
+
+/// A running Katana devnet, killed when dropped.
+pub struct Katana {
+    _process: Child,
+    url: Url,
+    account_address: Felt,
+    private_key: Felt,
+}
+
+impl Katana {
+    /// Starts Katana and waits until its RPC endpoint answers. The first predeployed
+    /// account is read from the startup banner.
+    pub async fn spawn() -> anyhow::Result<Self> {
+        let bin = std::env::var("KATANA_BIN").unwrap_or_else(|_| "katana".to_string());
+        let mut process = Command::new(&bin)
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("failed to start {bin}"))?;
+
+        let stdout = process.stdout.take().context("katana stdout")?;
+        let (account_address, private_key) = timeout(STARTUP_TIMEOUT, read_account(stdout))
+            .await
+            .context("katana did not print a predeployed account")??;
+
+        let katana = Self {
+            _process: process,
+            url: Url::parse(KATANA_URL)?,
+            account_address,
+            private_key,
+        };
+
+        let provider = katana.provider();
+        timeout(STARTUP_TIMEOUT, async {
+            while provider.chain_id().await.is_err() {
+                sleep(Duration::from_millis(100)).await;
+            }
+        })
+        .await
+        .context("katana rpc did not come up")?;
+
+        Ok(katana)
+    }
+
+    pub fn provider(&self) -> JsonRpcClient<HttpTransport> {
+        JsonRpcClient::new(HttpTransport::new(self.url.clone()))
+    }
+
+    pub async fn account(&self) -> anyhow::Result<KatanaAccount> {
+        let provider = self.provider();
+        let chain_id = provider.chain_id().await?;
+        let signer = LocalWallet::from(SigningKey::from_secret_scalar(self.private_key));
+
+        let mut account = SingleOwnerAccount::new(
+            provider,
+            signer,
+            self.account_address,
+            chain_id,
+            ExecutionEncoding::New,
+        );
+        account.set_block_id(BlockId::Tag(BlockTag::Pending));
+
+        Ok(account)
+    }
+
+    /// Declares and deploys the mock fact registry, returning its address.
+    pub async fn deploy_mock_fact_registry(&self) -> anyhow::Result<Felt> {
+        let sierra: SierraClass = read_artifact("MOCK_FACT_REGISTRY_SIERRA", "contract_class")?;
+        let casm: CompiledClass =
+            read_artifact("MOCK_FACT_REGISTRY_CASM", "compiled_contract_class")?;
+        let compiled_class_hash = casm.class_hash()?;
+        let class = sierra.flatten()?;
+        let class_hash = class.class_hash();
+
+        let provider = self.provider();
+        let account = self.account().await?;
+
+        let declaration = account
+            .declare_v2(Arc::new(class), compiled_class_hash)
+            .send()
+            .await?;
+        wait_for_transaction(
+            &provider,
+            declaration.transaction_hash,
+            WaitPolicy::default(),
+        )
+        .await?;
+
+        let factory = ContractFactory::new(class_hash, account);
+        let deployment = factory.deploy_v1(vec![], Felt::ZERO, false);
+        let address = deployment.deployed_address();
+        let tx = deployment.send().await?;
+        wait_for_transaction(&provider, tx.transaction_hash, WaitPolicy::default()).await?;
+
+        Ok(address)
+    }
+}
+
+async fn read_account(stdout: tokio::process::ChildStdout) -> anyhow::Result<(Felt, Felt)> {
+    let mut lines = BufReader::new(stdout).lines();
+    let mut address = None;
+
+    while let Some(line) = lines.next_line().await? {
+        let Some(value) = line.split_whitespace().last() else {
+            continue;
+        };
+        if line.contains("Account address") && address.is_none() {
+            address = Some(Felt::from_hex(value)?);
+        } else if line.contains("Private key") {
+            if let Some(address) = address {
+                return Ok((address, Felt::from_hex(value)?));
+            }
+        }
+    }
+
+    Err(anyhow!("katana exited before printing its accounts"))
+}
+
+// The artifact at `var`, or the scarb build output with the given extension.
+fn read_artifact<T: serde::de::DeserializeOwned>(var: &str, kind: &str) -> anyhow::Result<T> {
+    let path = std::env::var(var)
+        .unwrap_or_else(|_| format!("{MOCK_FACT_REGISTRY_ARTIFACTS}.{kind}.json"));
+    let file = std::fs::File::open(&path).with_context(|| {
+        format!("failed to open {path}, run scarb build in contracts/mock_fact_registry")
+    })?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use starknet::core::{types::FunctionCall, utils::get_selector_from_name};
+    use starknet_crypto::poseidon_hash_many;
+
+    use super::*;
+    use crate::{
+        registry::{register_fact, RegistrationOptions},
+        StarkProof,
+    };
+
+    #[tokio::test]
+    #[ignore = "requires a katana binary and the mock fact registry built with scarb"]
+    async fn test_register_fact_on_katana() {
+        let katana = Katana::spawn().await.unwrap();
+        let registry = katana.deploy_mock_fact_registry().await.unwrap();
+        let account = katana.account().await.unwrap();
+
+        // The mock takes the proof as a `Span<felt252>`, prefixed by its length.
+        let serialized_proof = StarkProof::sample().to_felts().unwrap();
+        let calldata = [Felt::from(serialized_proof.len())]
+            .into_iter()
+            .chain(serialized_proof.iter().copied())
+            .collect();
+        register_fact(
+            &account,
+            calldata,
+            registry,
+            get_selector_from_name("verify_and_register_fact").unwrap(),
+            RegistrationOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        let is_valid = katana
+            .provider()
+            .call(
+                FunctionCall {
+                    contract_address: registry,
+                    entry_point_selector: get_selector_from_name("is_valid").unwrap(),
+                    calldata: vec![poseidon_hash_many(&serialized_proof)],
+                },
+                BlockId::Tag(BlockTag::Pending),
+            )
+            .await
+            .unwrap();
+        assert_eq!(is_valid, [Felt::ONE]);
+    }
+}
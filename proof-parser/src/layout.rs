@@ -1,13 +1,18 @@
 use std::{collections::BTreeMap, convert::TryInto, fmt::Display};
 
 use num_bigint::BigUint;
-use serde::Deserialize;
+use serde::{de::value::StrDeserializer, Deserialize, Serialize};
+use starknet_types_core::felt::Felt;
 
-// For now only the recursive and starknet layouts is supported
-#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+use crate::{builtins::Builtin, error::Error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Layout {
+    AllCairo,
+    AllSolidity,
     Dex,
+    Dynamic,
     Plain,
     Recursive,
     RecursiveWithPoseidon,
@@ -17,59 +22,192 @@ pub enum Layout {
 }
 
 impl Layout {
-    pub(crate) fn get_consts(&self) -> LayoutConstants {
-        match self {
+    /// Constants of the fixed layouts, `Dynamic` takes them from the dynamic params. The
+    /// trace columns and mask lengths of `AllCairo` and `AllSolidity` are not tabulated: they
+    /// are read from the proof's annotations, see `consts_from_annotations`, or passed in
+    /// the dynamic params.
+    pub(crate) fn get_consts(&self) -> Option<LayoutConstants> {
+        Some(match self {
+            Layout::AllCairo | Layout::AllSolidity => return None,
             Layout::Dex => LayoutConstants::dex(),
+            Layout::Dynamic => return None,
             Layout::Plain => LayoutConstants::plain(),
             Layout::Recursive => LayoutConstants::recursive(),
             Layout::RecursiveWithPoseidon => LayoutConstants::recursive_with_poseidon(),
             Layout::Small => LayoutConstants::small(),
             Layout::Starknet => LayoutConstants::starknet(),
             Layout::StarknetWithKeccak => LayoutConstants::starknet_with_keccak(),
-        }
+        })
     }
+    /// The layout's constants, overridden by the dynamic params. `n_oods`, the number of
+    /// OODS values when the proof content tells it, takes precedence over the tabulated
+    /// mask length.
     pub(crate) fn get_dynamics_or_consts(
         &self,
         dynamic_params: &Option<BTreeMap<String, BigUint>>,
+        n_oods: Option<usize>,
     ) -> Option<LayoutConstants> {
-        let consts = self.get_consts();
+        let consts = match (self.get_consts(), n_oods) {
+            (Some(consts), Some(n_oods)) => Some(LayoutConstants {
+                mask_len: n_oods.checked_sub(consts.constraint_degree as usize)?,
+                ..consts
+            }),
+            (consts, _) => consts,
+        };
 
         let Some(dynamic_params) = dynamic_params else {
-            return Some(consts);
+            return consts;
         };
 
+        // Params that are present must be valid, missing ones fall back to the layout's
+        // constants, of which the dynamic layout has none.
+        let param = |name: &str, default: Option<u32>| -> Option<u32> {
+            match dynamic_params.get(name) {
+                Some(value) => value.try_into().ok(),
+                None => default,
+            }
+        };
+
+        let constraint_degree = param("constraint_degree", consts.map(|c| c.constraint_degree))?;
+        let mask_len = match n_oods {
+            Some(n_oods) => n_oods
+                .checked_sub(constraint_degree as usize)
+                .map(|m| m as u32),
+            None => consts.map(|c| c.mask_len as u32),
+        };
         Some(LayoutConstants {
-            cpu_component_step: dynamic_params
-                .get("cpu_component_step")
-                .map(<&BigUint>::try_into)
-                .map(Result::ok)?
-                .unwrap_or(consts.cpu_component_step),
-            constraint_degree: dynamic_params
-                .get("constraint_degree")
-                .map(<&BigUint>::try_into)
-                .map(Result::ok)?
-                .unwrap_or(consts.constraint_degree),
-            num_columns_first: dynamic_params
-                .get("num_columns_first")
-                .map(<&BigUint>::try_into)
-                .map(Result::ok)?
-                .unwrap_or(consts.num_columns_first),
-            num_columns_second: dynamic_params
-                .get("num_columns_second")
-                .map(<&BigUint>::try_into)
-                .map(Result::ok)?
-                .unwrap_or(consts.num_columns_second),
+            cpu_component_step: param("cpu_component_step", consts.map(|c| c.cpu_component_step))?,
+            constraint_degree,
+            num_columns_first: param("num_columns_first", consts.map(|c| c.num_columns_first))?,
+            num_columns_second: param("num_columns_second", consts.map(|c| c.num_columns_second))?,
+            mask_len: param("mask_len", mask_len)? as usize,
+        })
+    }
+
+    /// Constants of `AllCairo` and `AllSolidity` from what a proof's annotations tell:
+    /// `n_oods` OODS values and the leaves decommitted from the original trace, the
+    /// interaction trace and the composition. Like Stone's other CPU layouts, both have one
+    /// step per CPU component and a constraint degree of 2, so the composition leaves give
+    /// the number of decommitted rows.
+    pub(crate) fn consts_from_annotations(
+        &self,
+        n_oods: usize,
+        [original, interaction, composition]: [usize; 3],
+    ) -> Option<LayoutConstants> {
+        if !matches!(self, Layout::AllCairo | Layout::AllSolidity) {
+            return None;
+        }
+        let constraint_degree = 2;
+        let rows = composition / constraint_degree;
+        if rows == 0 || !composition.is_multiple_of(constraint_degree) {
+            return None;
+        }
+        let columns = |leaves: usize| {
+            leaves
+                .is_multiple_of(rows)
+                .then_some((leaves / rows) as u32)
+        };
+
+        Some(LayoutConstants {
+            cpu_component_step: 1,
+            constraint_degree: constraint_degree as u32,
+            num_columns_first: columns(original).filter(|n| *n > 0)?,
+            num_columns_second: columns(interaction)?,
+            mask_len: n_oods.checked_sub(constraint_degree)?,
         })
     }
+
     pub fn bytes_encode(&self) -> Vec<u8> {
         self.to_string().as_bytes().to_vec()
     }
+
+    /// Inverse of the public input encoding, see `bytes_encode`.
+    pub(crate) fn from_felt(felt: Felt) -> crate::error::Result<Self> {
+        let bytes = felt.to_bytes_be();
+        let name = std::str::from_utf8(&bytes)
+            .map_err(|_| Error::InvalidLayout(format!("{felt:#x}")))?
+            .trim_start_matches('\0');
+
+        Layout::deserialize(StrDeserializer::<serde::de::value::Error>::new(name))
+            .map_err(|_| Error::InvalidLayout(name.to_string()))
+    }
+
+    /// Builtins of the layout, in the order of their memory segments. The dynamic
+    /// layout lists its builtins through `uses_<builtin>_builtin` params.
+    pub(crate) fn builtins(&self, dynamic_params: &BTreeMap<String, Felt>) -> Vec<Builtin> {
+        use Builtin::*;
+        match self {
+            Layout::Dynamic => Builtin::ordered()
+                .into_iter()
+                .filter(|b| {
+                    dynamic_params
+                        .get(&format!("uses_{}_builtin", b.name()))
+                        .is_some_and(|uses| *uses != Felt::ZERO)
+                })
+                .collect(),
+            Layout::AllCairo => vec![
+                Output, Pedersen, RangeCheck, Ecdsa, Bitwise, EcOp, Keccak, Poseidon,
+            ],
+            Layout::AllSolidity => vec![Output, Pedersen, RangeCheck, Ecdsa, Bitwise, EcOp],
+            Layout::Dex | Layout::Small => vec![Output, Pedersen, RangeCheck, Ecdsa],
+            Layout::Plain => vec![Output],
+            Layout::Recursive => vec![Output, Pedersen, RangeCheck, Bitwise],
+            Layout::RecursiveWithPoseidon => vec![Output, Pedersen, RangeCheck, Bitwise, Poseidon],
+            Layout::Starknet => vec![Output, Pedersen, RangeCheck, Ecdsa, Bitwise, EcOp, Poseidon],
+            Layout::StarknetWithKeccak => vec![
+                Output, Pedersen, RangeCheck, Ecdsa, Bitwise, EcOp, Keccak, Poseidon,
+            ],
+        }
+    }
+}
+
+impl Layout {
+    /// Steps per instance of `builtin`, `None` for builtins without a ratio and for the
+    /// dynamic layout.
+    pub(crate) fn builtin_ratio(&self, builtin: Builtin) -> Option<u32> {
+        use Builtin::*;
+        Some(match (self, builtin) {
+            (Layout::Dex | Layout::Small, Pedersen) => 8,
+            (Layout::Dex | Layout::Small, RangeCheck) => 8,
+            (Layout::Dex | Layout::Small, Ecdsa) => 512,
+            (Layout::Recursive, Pedersen) => 128,
+            (Layout::Recursive, RangeCheck) => 8,
+            (Layout::Recursive, Bitwise) => 8,
+            (Layout::RecursiveWithPoseidon, Pedersen) => 256,
+            (Layout::RecursiveWithPoseidon, RangeCheck) => 16,
+            (Layout::RecursiveWithPoseidon, Bitwise) => 16,
+            (Layout::RecursiveWithPoseidon, Poseidon) => 64,
+            (Layout::Starknet | Layout::StarknetWithKeccak, Pedersen) => 32,
+            (Layout::Starknet | Layout::StarknetWithKeccak, RangeCheck) => 16,
+            (Layout::Starknet | Layout::StarknetWithKeccak, Ecdsa) => 2048,
+            (Layout::Starknet | Layout::StarknetWithKeccak, Bitwise) => 64,
+            (Layout::Starknet | Layout::StarknetWithKeccak, EcOp) => 1024,
+            (Layout::Starknet | Layout::StarknetWithKeccak, Poseidon) => 32,
+            (Layout::StarknetWithKeccak, Keccak) => 2048,
+            (Layout::AllCairo, Pedersen) => 256,
+            (Layout::AllCairo, RangeCheck) => 8,
+            (Layout::AllCairo, Ecdsa) => 2048,
+            (Layout::AllCairo, Bitwise) => 16,
+            (Layout::AllCairo, EcOp) => 1024,
+            (Layout::AllCairo, Keccak) => 2048,
+            (Layout::AllCairo, Poseidon) => 256,
+            (Layout::AllSolidity, Pedersen) => 8,
+            (Layout::AllSolidity, RangeCheck) => 8,
+            (Layout::AllSolidity, Ecdsa) => 512,
+            (Layout::AllSolidity, Bitwise) => 256,
+            (Layout::AllSolidity, EcOp) => 256,
+            _ => return None,
+        })
+    }
 }
 
 impl Display for Layout {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Layout::AllCairo => write!(f, "all_cairo"),
+            Layout::AllSolidity => write!(f, "all_solidity"),
             Layout::Dex => write!(f, "dex"),
+            Layout::Dynamic => write!(f, "dynamic"),
             Layout::Plain => write!(f, "plain"),
             Layout::Recursive => write!(f, "recursive"),
             Layout::RecursiveWithPoseidon => write!(f, "recursive_with_poseidon"),
@@ -80,13 +218,16 @@ impl Display for Layout {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) struct LayoutConstants {
     pub cpu_component_step: u32,
     pub constraint_degree: u32,
     pub num_columns_first: u32,
     pub num_columns_second: u32,
+    pub mask_len: usize,
 }
 
+// Mask lengths: https://github.com/cartridge-gg/stone-prover/blob/fd78b4db8d6a037aa467b7558ac8930c10e48dc1/src/starkware/air/cpu/board/cpu_air_definition4.inl#L1775-L1776
 impl LayoutConstants {
     pub fn recursive() -> Self {
         LayoutConstants {
@@ -94,6 +235,7 @@ impl LayoutConstants {
             cpu_component_step: 1,
             num_columns_first: 7,
             num_columns_second: 3,
+            mask_len: 133,
         }
     }
     pub fn starknet() -> Self {
@@ -102,6 +244,7 @@ impl LayoutConstants {
             cpu_component_step: 1,
             num_columns_first: 9,
             num_columns_second: 1,
+            mask_len: 271,
         }
     }
     pub fn small() -> Self {
@@ -110,6 +253,7 @@ impl LayoutConstants {
             cpu_component_step: 1,
             num_columns_first: 23,
             num_columns_second: 2,
+            mask_len: 201,
         }
     }
     pub fn recursive_with_poseidon() -> Self {
@@ -118,6 +262,7 @@ impl LayoutConstants {
             cpu_component_step: 1,
             num_columns_first: 6,
             num_columns_second: 2,
+            mask_len: 192,
         }
     }
     pub fn plain() -> Self {
@@ -126,6 +271,7 @@ impl LayoutConstants {
             cpu_component_step: 1,
             num_columns_first: 6,
             num_columns_second: 2,
+            mask_len: 49,
         }
     }
     pub fn starknet_with_keccak() -> Self {
@@ -134,6 +280,7 @@ impl LayoutConstants {
             cpu_component_step: 1,
             num_columns_first: 12,
             num_columns_second: 3,
+            mask_len: 734,
         }
     }
     pub fn dex() -> Self {
@@ -142,21 +289,7 @@ impl LayoutConstants {
             cpu_component_step: 1,
             num_columns_first: 21,
             num_columns_second: 1,
-        }
-    }
-}
-
-impl Layout {
-    // https://github.com/cartridge-gg/stone-prover/blob/fd78b4db8d6a037aa467b7558ac8930c10e48dc1/src/starkware/air/cpu/board/cpu_air_definition4.inl#L1775-L1776
-    pub fn mask_len(&self) -> usize {
-        match self {
-            Layout::Recursive => 133,
-            Layout::Starknet => 271,
-            Layout::Dex => 200,
-            Layout::Plain => 49,
-            Layout::RecursiveWithPoseidon => 192,
-            Layout::Small => 201,
-            Layout::StarknetWithKeccak => 734,
+            mask_len: 200,
         }
     }
 }
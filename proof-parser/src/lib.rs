@@ -1,22 +1,74 @@
-use std::{convert::TryFrom, fmt::Display};
+use std::{convert::TryFrom, fmt::Display, io, path::Path};
 
-mod annotations;
-mod builtins;
+pub mod annotations;
+mod binary;
+pub mod bootloader;
+pub mod builtins;
+mod channel;
+pub mod cost;
+pub mod diff;
+pub mod error;
+pub mod explain;
+pub mod fact;
+mod hashes;
+pub mod integrity;
 pub mod json_parser;
+#[cfg(all(feature = "katana", not(target_arch = "wasm32")))]
+pub mod katana;
 mod layout;
+mod onchain;
+pub mod options;
 pub mod output;
+mod pagination;
+mod pow;
 pub mod program;
+mod proof_hex;
 mod proof_params;
 mod proof_structure;
+pub mod provenance;
+mod public_memory;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod registry;
+pub mod salvage;
+mod sections;
 mod stark_proof;
+mod stream;
+#[cfg(feature = "stwo")]
+pub mod stwo;
+pub mod transcript;
 mod utils;
+mod validation;
+mod verification;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
-pub use crate::{json_parser::ProofJSON, stark_proof::StarkProof};
-pub use serde_felt::{to_felts, from_felts};
+pub use crate::{
+    annotations::Annotations,
+    builtins::Builtin,
+    error::{Error, Result},
+    hashes::{HashFunction, HashSettings},
+    json_parser::ProofJSON,
+    layout::Layout,
+    onchain::CalldataFormat,
+    options::{FeltRepresentation, ParseOptions},
+    pagination::ContinuousPageHeader,
+    proof_params::{CachedLdeConfig, ProverConfig},
+    proof_structure::ProofStructure,
+    public_memory::PublicMemory,
+    sections::{to_felts_sections, ProofStats, Section},
+    stark_proof::{CairoPublicInput, PublicMemoryCell, StarkConfig, StarkProof},
+    validation::{validate_against_air_public_input, ValidationIssue, ValidationReport},
+    verification::Decommitments,
+};
+use provenance::DigestReader;
+pub use serde_felt::{from_felts, to_felts};
+use starknet_crypto::poseidon_hash_many;
+use starknet_types_core::felt::Felt;
+use stream::ProofHexReader;
 
 impl Display for StarkProof {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let serialized = to_felts(self).map_err(|_| std::fmt::Error)?;
+        let serialized = self.to_felts().map_err(|_| std::fmt::Error)?;
         let done = serialized
             .into_iter()
             .map(|f| format!("{f}"))
@@ -29,15 +81,255 @@ impl Display for StarkProof {
     }
 }
 
-pub fn parse(input: &str) -> anyhow::Result<StarkProof> {
-    let proof_json = serde_json::from_str::<ProofJSON>(input)?;
+impl StarkProof {
+    /// Same as `to_felts(self)`. With the `parallel` feature, the top-level
+    /// sections are serialized concurrently.
+    pub fn to_felts(&self) -> Result<Vec<Felt>> {
+        #[cfg(feature = "parallel")]
+        {
+            let ((config, public_input), (unsent_commitment, witness)) = rayon::join(
+                || rayon::join(|| to_felts(&self.config), || to_felts(&self.public_input)),
+                || {
+                    rayon::join(
+                        || to_felts(&self.unsent_commitment),
+                        || to_felts(&self.witness),
+                    )
+                },
+            );
+            Ok([config?, public_input?, unsent_commitment?, witness?].concat())
+        }
+
+        #[cfg(not(feature = "parallel"))]
+        Ok(to_felts(self)?)
+    }
+
+    /// Poseidon hash over the felt serialization of the proof. Proofs that differ only in
+    /// fields the serialization leaves out share it, see `digest`.
+    pub fn commitment(&self) -> Result<Felt> {
+        Ok(poseidon_hash_many(&self.to_felts()?))
+    }
+
+    /// A stable key for the proof, for caching and deduplication. It covers what `Eq` and
+    /// `Hash` compare, so equal proofs have equal digests whatever file they were parsed
+    /// from: the felt serialization `commitment` hashes, then the hash settings and the
+    /// continuous page cells, which the felt serialization leaves out.
+    pub fn digest(&self) -> Result<Felt> {
+        let hashes = &self.config.hashes;
+        let mut felts = self.to_felts()?;
+        felts
+            .extend([hashes.channel, hashes.commitment, hashes.pow].map(|h| Felt::from(h.index())));
+        felts.push(u8::from(hashes.verifier_friendly_channel_updates).into());
+
+        let pages = &self.public_input.continuous_pages;
+        felts.push(pages.len().into());
+        for page in pages {
+            felts.push(page.len().into());
+            felts.extend(
+                page.iter()
+                    .flat_map(|cell| [cell.address.into(), cell.value]),
+            );
+        }
+        Ok(poseidon_hash_many(&felts))
+    }
+}
+
+pub fn parse(input: &str) -> Result<StarkProof> {
+    let proof_json = input.parse::<ProofJSON>()?;
     let stark_proof = StarkProof::try_from(proof_json)?;
 
-    Ok(stark_proof)
+    Ok(stark_proof.with_source_digest(input.as_bytes()))
+}
+
+pub fn parse_with_options(input: &str, options: &ParseOptions) -> Result<StarkProof> {
+    let proof_json = input.parse::<ProofJSON>()?;
+    let stark_proof = json_parser::proof_from_hex(proof_json, options)?;
+
+    Ok(stark_proof.with_source_digest(input.as_bytes()))
 }
 
-pub fn parse_raw(input: &str) -> anyhow::Result<StarkProof> {
-    let proof_json = serde_json::from_str::<ProofJSON>(input)?;
+/// Like `parse`, but reads the proof JSON from any `io::Read`, such as a file or a
+/// decompressor. A hex `proof_hex` is decoded as it is read, so the payload is never held
+/// in memory as text.
+pub fn parse_reader<R: io::Read>(reader: R) -> Result<StarkProof> {
+    let mut reader = ProofHexReader::new(DigestReader::new(reader));
+    let value = serde_json::from_reader(&mut reader);
+    let (reader, proof_bytes) = reader.finish()?;
+
+    let mut proof_json = ProofJSON::from_value(value?)?;
+    if let Some(bytes) = proof_bytes {
+        proof_json = proof_json.with_proof_bytes(bytes);
+    }
     let stark_proof = StarkProof::try_from(proof_json)?;
-    Ok(stark_proof)
+
+    Ok(stark_proof.with_digest(reader.finish()))
+}
+
+/// Reads a proof file as a string, decompressing `.gz` files.
+pub fn read_proof_file(path: impl AsRef<Path>) -> Result<String> {
+    String::from_utf8(utils::read_file(path.as_ref())?)
+        .map_err(|e| Error::Io(io::Error::new(io::ErrorKind::InvalidData, e)))
+}
+
+/// Parses a proof JSON file, which may be gzip compressed. A `proof_file` it refers to
+/// is read relative to the JSON.
+pub fn parse_file(path: impl AsRef<Path>) -> Result<StarkProof> {
+    let path = path.as_ref();
+    let input = read_proof_file(path)?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let proof_json = input.parse::<ProofJSON>()?.load_proof_file(dir)?;
+    let stark_proof = StarkProof::try_from(proof_json)?;
+
+    Ok(stark_proof.with_source_digest(input.as_bytes()))
+}
+
+/// Parses a proof JSON whose body is supplied separately, as raw proof bytes.
+pub fn parse_with_proof_bytes(input: &str, bytes: &[u8]) -> Result<StarkProof> {
+    let proof_json = input.parse::<ProofJSON>()?.with_proof_bytes(bytes.to_vec());
+    let stark_proof = StarkProof::try_from(proof_json)?;
+
+    Ok(stark_proof.with_source_digest(input.as_bytes()))
+}
+
+pub fn parse_raw(input: &str) -> Result<StarkProof> {
+    let proof_json = input.parse::<ProofJSON>()?;
+    let stark_proof = StarkProof::try_from(proof_json)?;
+    Ok(stark_proof.with_source_digest(input.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+    use super::*;
+
+    #[test]
+    fn test_to_felts_matches_serializer() {
+        let proof = StarkProof::sample();
+        assert_eq!(proof.to_felts().unwrap(), to_felts(&proof).unwrap());
+    }
+
+    #[test]
+    fn test_digest_ignores_source() {
+        let proof = StarkProof::sample();
+        let parsed = proof.clone().with_source_digest(b"proof.json");
+        assert_eq!(parsed, proof);
+        assert_eq!(parsed.digest().unwrap(), proof.digest().unwrap());
+
+        let proofs = std::collections::HashSet::from([proof.clone(), parsed]);
+        assert_eq!(proofs.len(), 1);
+
+        let mut other = proof.clone();
+        other.unsent_commitment.proof_of_work_nonce += Felt::ONE;
+        assert_ne!(other, proof);
+        assert_ne!(other.digest().unwrap(), proof.digest().unwrap());
+
+        // Fields left out of the felt serialization change the digest, not the commitment.
+        let mut other_hashes = proof.clone();
+        other_hashes.config.hashes.commitment = HashFunction::Blake256Masked248Lsb;
+        let mut paged = proof.clone();
+        paged.public_input.continuous_pages = vec![vec![PublicMemoryCell {
+            address: 30,
+            value: Felt::from(7u64),
+        }]];
+        for other in [other_hashes, paged] {
+            assert_ne!(other, proof);
+            assert_eq!(other.commitment().unwrap(), proof.commitment().unwrap());
+            assert_ne!(other.digest().unwrap(), proof.digest().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let proof = StarkProof::sample();
+        let json = serde_json::to_string(&proof).unwrap();
+
+        assert_eq!(serde_json::from_str::<StarkProof>(&json).unwrap(), proof);
+    }
+
+    #[test]
+    fn test_json_roundtrip_hashes_and_pages() {
+        let mut proof = StarkProof::sample();
+        proof.config.hashes = HashSettings {
+            channel: HashFunction::Keccak256,
+            commitment: HashFunction::Blake256Masked248Lsb,
+            pow: HashFunction::Blake256,
+            verifier_friendly_channel_updates: false,
+        };
+        let felts = proof.to_felts().unwrap();
+        assert_eq!(felts, StarkProof::sample().to_felts().unwrap());
+
+        let public_input = &mut proof.public_input;
+        public_input.continuous_pages = vec![vec![PublicMemoryCell {
+            address: 30,
+            value: Felt::from(7u64),
+        }]];
+        public_input.n_continuous_pages = 1;
+        public_input.continuous_page_headers = vec![Felt::from(30u64), Felt::ONE, Felt::TWO];
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let decoded = serde_json::from_str::<StarkProof>(&json).unwrap();
+        assert_eq!(decoded.config.hashes, proof.config.hashes);
+        assert_eq!(decoded, proof);
+
+        // The felt serialization keeps only the page headers.
+        let from_felts = StarkProof::from_calldata(&proof.to_felts().unwrap()).unwrap();
+        assert!(from_felts.public_input.continuous_pages.is_empty());
+        assert_eq!(from_felts.config.hashes, HashSettings::default());
+    }
+
+    #[test]
+    fn test_parse_external_proof_bytes() {
+        let hex = json_parser::tests::dummy_proof_hex();
+        let bytes = prefix_hex::decode::<Vec<u8>>(hex.as_str()).unwrap();
+        let inline = parse(&json_parser::tests::proof_json_str(&format!(
+            r#""proof_hex": "{hex}""#
+        )))
+        .unwrap();
+
+        let dir = std::env::temp_dir().join(format!("proof-file-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("proof.bin"), &bytes).unwrap();
+        let input = json_parser::tests::proof_json_str(r#""proof_file": "proof.bin""#);
+        fs::write(dir.join("proof.json"), &input).unwrap();
+
+        let from_file = parse_file(dir.join("proof.json")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(from_file, inline);
+        assert_eq!(parse_with_proof_bytes(&input, &bytes).unwrap(), inline);
+
+        let err = parse(&input).unwrap_err();
+        assert!(matches!(err, Error::InvalidParameter(_)), "{err}");
+    }
+
+    #[test]
+    fn test_parse_reader() {
+        let input = json_parser::tests::proof_json_str(&format!(
+            r#""proof_hex": "{}""#,
+            json_parser::tests::dummy_proof_hex()
+        ));
+
+        let from_reader = parse_reader(input.as_bytes()).unwrap();
+        assert_eq!(from_reader, parse(&input).unwrap());
+        assert_eq!(
+            from_reader.source_digest(),
+            Some(&provenance::SourceDigest::new(input.as_bytes()))
+        );
+
+        let unprefixed = input.replacen(r#""proof_hex": "0x"#, r#""proof_hex": ""#, 1);
+        assert_eq!(parse_reader(unprefixed.as_bytes()).unwrap(), from_reader);
+
+        let hex = json_parser::tests::dummy_proof_hex();
+        let bytes = prefix_hex::decode::<Vec<u8>>(hex.as_str()).unwrap();
+        let base64 = json_parser::tests::proof_json_str(&format!(
+            r#""proof_hex": "{}""#,
+            BASE64.encode(bytes)
+        ));
+        assert_eq!(parse_reader(base64.as_bytes()).unwrap(), from_reader);
+
+        let invalid = json_parser::tests::proof_json_str(&format!(r#""proof_hex": "{hex}zz""#));
+        let err = parse_reader(invalid.as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::HexDecode(_)), "{err}");
+    }
 }
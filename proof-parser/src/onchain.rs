@@ -0,0 +1,276 @@
+use serde_felt::from_felts_strict;
+#[cfg(not(target_arch = "wasm32"))]
+use starknet::{
+    core::types::{InvokeTransaction, Transaction},
+    macros::selector,
+    providers::Provider,
+};
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    error::{Error, Result},
+    stark_proof::StarkProof,
+};
+
+// Every chunk starts with its index and the number of chunks.
+const CHUNK_HEADER_LEN: usize = 2;
+
+// Integrity's verifier configuration: layout, hasher, Stone version and memory verification.
+#[cfg(not(target_arch = "wasm32"))]
+const VERIFIER_CONFIG_LEN: usize = 4;
+
+/// Text encodings of the proof calldata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalldataFormat {
+    /// Space-separated decimals, the `Display` of `StarkProof`.
+    Decimal,
+    /// `[0x1, 0x2, ...]`.
+    Hex,
+    /// A JSON array of hex strings.
+    Json,
+    /// A `scarb cairo-run --arguments-file` file passing the calldata as one `Array<felt252>`.
+    ScarbArgs,
+}
+
+impl StarkProof {
+    /// Decodes a proof from its felt serialization, the inverse of `to_felts`.
+    pub fn from_calldata(calldata: &[Felt]) -> Result<Self> {
+        Ok(from_felts_strict(&calldata.to_vec())?)
+    }
+
+    pub fn to_calldata_string(&self, format: CalldataFormat) -> Result<String> {
+        let felts = self.to_felts()?;
+        let hex = || felts.iter().map(|felt| format!("{felt:#x}"));
+
+        Ok(match format {
+            CalldataFormat::Decimal => felts
+                .iter()
+                .map(|felt| felt.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            CalldataFormat::Hex => format!("[{}]", hex().collect::<Vec<_>>().join(", ")),
+            CalldataFormat::Json => serde_json::to_string(&hex().collect::<Vec<_>>())?,
+            CalldataFormat::ScarbArgs => serde_json::to_string(&[hex().collect::<Vec<_>>()])?,
+        })
+    }
+
+    /// Splits the felt serialization into chunks of at most `max_felts` felts, each
+    /// prefixed with its index and the total number of chunks.
+    pub fn to_calldata_chunks(&self, max_felts: usize) -> Result<Vec<Vec<Felt>>> {
+        if max_felts <= CHUNK_HEADER_LEN {
+            return Err(Error::InvalidParameter(format!(
+                "Chunks of {max_felts} felts leave no room for the proof"
+            )));
+        }
+
+        let felts = self.to_felts()?;
+        let payloads = felts
+            .chunks(max_felts - CHUNK_HEADER_LEN)
+            .collect::<Vec<_>>();
+        let total = Felt::from(payloads.len());
+
+        Ok(payloads
+            .into_iter()
+            .enumerate()
+            .map(|(i, payload)| {
+                [Felt::from(i), total]
+                    .into_iter()
+                    .chain(payload.iter().copied())
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Reassembles a proof from the chunks of `to_calldata_chunks`, in any order.
+    pub fn from_calldata_chunks(chunks: &[Vec<Felt>]) -> Result<Self> {
+        let mut chunks = chunks.iter().collect::<Vec<_>>();
+        chunks.sort_by_key(|chunk| chunk.first().copied());
+
+        let mut calldata = vec![];
+        for (i, chunk) in chunks.iter().enumerate() {
+            match chunk.as_slice() {
+                [index, total, payload @ ..]
+                    if *index == Felt::from(i) && *total == Felt::from(chunks.len()) =>
+                {
+                    calldata.extend_from_slice(payload)
+                }
+                _ => {
+                    return Err(Error::InvalidValue(format!(
+                        "Missing or malformed calldata chunk {i}"
+                    )))
+                }
+            }
+        }
+
+        Self::from_calldata(&calldata)
+    }
+
+    /// Fetches the calldata of a registration transaction and decodes the proof it submitted.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn from_tx<P>(provider: &P, tx_hash: Felt) -> anyhow::Result<Self>
+    where
+        P: Provider + Sync,
+    {
+        let tx = provider.get_transaction_by_hash(tx_hash).await?;
+
+        let calls = match tx {
+            Transaction::Invoke(InvokeTransaction::V0(tx)) => {
+                vec![(tx.entry_point_selector, tx.calldata)]
+            }
+            Transaction::Invoke(InvokeTransaction::V1(tx)) => execute_calls(&tx.calldata)?,
+            Transaction::Invoke(InvokeTransaction::V3(tx)) => execute_calls(&tx.calldata)?,
+            _ => anyhow::bail!("Transaction {tx_hash:#x} is not an invoke transaction"),
+        };
+
+        calls
+            .iter()
+            .find_map(|(selector, calldata)| Self::from_call(*selector, calldata).ok())
+            .ok_or(anyhow::anyhow!(
+                "No call in transaction {tx_hash:#x} carries a proof"
+            ))
+    }
+
+    /// Decodes the proof a call to `selector` submitted, stripping the verifier's framing:
+    /// the configuration of `verify_proof_full_and_register_fact`, and the job id and
+    /// configuration of `verify_proof_initial`, whose proof has no FRI layer witnesses.
+    /// Other selectors are taken to carry the bare proof, as `register_fact` sends it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_call(selector: Felt, calldata: &[Felt]) -> Result<Self> {
+        let proof = if selector == selector!("verify_proof_full_and_register_fact") {
+            calldata.get(VERIFIER_CONFIG_LEN..)
+        } else if selector == selector!("verify_proof_initial") {
+            calldata.get(1 + VERIFIER_CONFIG_LEN..)
+        } else if selector == selector!("verify_proof_step")
+            || selector == selector!("verify_proof_final_and_register_fact")
+        {
+            return Err(Error::InvalidValue(
+                "Split verification steps carry no proof".to_string(),
+            ));
+        } else {
+            Some(calldata)
+        };
+
+        Self::from_calldata(proof.ok_or(Error::InvalidValue(
+            "Truncated verifier calldata".to_string(),
+        ))?)
+    }
+}
+
+// Splits account `__execute__` calldata, `[n_calls, (to, selector, len, calldata...)...]`,
+// into the selector and calldata of each call.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn execute_calls(calldata: &[Felt]) -> Result<Vec<(Felt, Vec<Felt>)>> {
+    let to_usize = |felt: &Felt| -> Result<usize> {
+        felt.to_string()
+            .parse::<usize>()
+            .map_err(|_| Error::InvalidValue("Invalid length in calldata".to_string()))
+    };
+    let truncated = || Error::InvalidValue("Truncated calldata".to_string());
+
+    let (n_calls, mut rest) = calldata
+        .split_first()
+        .ok_or(Error::InvalidValue("Empty calldata".to_string()))?;
+
+    let mut calls = vec![];
+    for _ in 0..to_usize(n_calls)? {
+        let (selector, len) = match rest.get(1..3) {
+            Some([selector, len]) => (*selector, to_usize(len)?),
+            _ => return Err(truncated()),
+        };
+        let end = len.checked_add(3).ok_or_else(truncated)?;
+        let call = rest.get(3..end).ok_or_else(truncated)?;
+        calls.push((selector, call.to_vec()));
+        rest = rest.get(end..).ok_or_else(truncated)?;
+    }
+
+    Ok(calls)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_felt::to_felts;
+
+    use super::*;
+
+    #[test]
+    fn test_calldata_roundtrip() {
+        let proof = StarkProof::sample();
+        let calldata = to_felts(&proof).unwrap();
+
+        let decoded = StarkProof::from_calldata(&calldata).unwrap();
+        assert_eq!(decoded, proof);
+        assert_eq!(decoded.commitment().unwrap(), proof.commitment().unwrap());
+    }
+
+    #[test]
+    fn test_calldata_formats() {
+        let proof = StarkProof::sample();
+        let felts = proof.to_felts().unwrap();
+
+        assert_eq!(
+            proof.to_calldata_string(CalldataFormat::Decimal).unwrap(),
+            proof.to_string()
+        );
+        let hex = proof.to_calldata_string(CalldataFormat::Hex).unwrap();
+        assert!(hex.starts_with(&format!("[{:#x}, ", felts[0])));
+
+        let json: Vec<String> =
+            serde_json::from_str(&proof.to_calldata_string(CalldataFormat::Json).unwrap()).unwrap();
+        assert_eq!(json.len(), felts.len());
+        let args: Vec<Vec<String>> =
+            serde_json::from_str(&proof.to_calldata_string(CalldataFormat::ScarbArgs).unwrap())
+                .unwrap();
+        assert_eq!(args, [json]);
+    }
+
+    #[test]
+    fn test_calldata_chunks() {
+        let proof = StarkProof::sample();
+        let len = proof.to_felts().unwrap().len();
+
+        let mut chunks = proof.to_calldata_chunks(50).unwrap();
+        assert_eq!(chunks.len(), len.div_ceil(48));
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 50));
+        assert_eq!(chunks[1][..2], [Felt::ONE, Felt::from(chunks.len())]);
+
+        chunks.reverse();
+        assert_eq!(StarkProof::from_calldata_chunks(&chunks).unwrap(), proof);
+
+        chunks.pop();
+        assert!(matches!(
+            StarkProof::from_calldata_chunks(&chunks),
+            Err(Error::InvalidValue(_))
+        ));
+        assert!(matches!(
+            proof.to_calldata_chunks(2),
+            Err(Error::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_execute_calls() {
+        let calldata = [2u64, 10, 11, 2, 100, 101, 20, 21, 1, 200]
+            .map(Felt::from)
+            .to_vec();
+
+        assert_eq!(
+            execute_calls(&calldata).unwrap(),
+            vec![
+                (
+                    Felt::from(11u64),
+                    vec![Felt::from(100u64), Felt::from(101u64)]
+                ),
+                (Felt::from(21u64), vec![Felt::from(200u64)])
+            ]
+        );
+        assert!(execute_calls(&calldata[..5]).is_err());
+
+        // A length that would overflow the end index.
+        let overflowing = [1u64, 10, 11]
+            .map(Felt::from)
+            .into_iter()
+            .chain([Felt::from(u64::MAX)]);
+        assert!(execute_calls(&overflowing.collect::<Vec<_>>()).is_err());
+    }
+}
@@ -0,0 +1,49 @@
+use serde_felt::montgomery_to_felts;
+use starknet_types_core::felt::Felt;
+
+/// How the field elements of a proof section are encoded in the proof hex.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FeltRepresentation {
+    #[default]
+    Standard,
+    Montgomery,
+}
+
+impl FeltRepresentation {
+    pub fn decode(self, felts: Vec<Felt>) -> Vec<Felt> {
+        match self {
+            FeltRepresentation::Standard => felts,
+            FeltRepresentation::Montgomery => montgomery_to_felts(&felts),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParseOptions {
+    /// Stone builds differ in whether they emit the FRI last layer in Montgomery form.
+    pub last_layer_coefficients: FeltRepresentation,
+    /// Also builds the proof from the annotations and fails if it differs from the proof
+    /// hex. Doubles parse time, so it is off by default.
+    pub verify_consistency: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_representation() {
+        let montgomery =
+            Felt::from_hex("0x004b372a6c0acf83dd330cdf701e5dc85726b19819d4b33158dcb57a33f704c7")
+                .unwrap();
+
+        assert_eq!(
+            FeltRepresentation::Standard.decode(vec![montgomery]),
+            vec![montgomery]
+        );
+        assert_eq!(
+            FeltRepresentation::Montgomery.decode(vec![montgomery]),
+            vec![serde_felt::montgomery_to_felt(montgomery)]
+        );
+    }
+}
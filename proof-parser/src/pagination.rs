@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use sha3::{Digest, Keccak256};
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    error::{Error, Result},
+    json_parser::PublicInput,
+    stark_proof::{CairoPublicInput, PublicMemoryCell},
+};
+
+/// A continuous page header as verifiers read it, the `hash` being a `u256`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContinuousPageHeader {
+    pub start_address: Felt,
+    pub size: Felt,
+    pub hash_low: Felt,
+    pub hash_high: Felt,
+    pub prod: Felt,
+}
+
+// Felts in a serialized `ContinuousPageHeader`.
+pub(crate) const HEADER_LEN: usize = 5;
+
+impl<B> CairoPublicInput<B> {
+    /// The main page followed by the cells of the continuous pages.
+    pub fn public_memory(&self) -> impl Iterator<Item = &PublicMemoryCell<B>> {
+        self.main_page
+            .iter()
+            .chain(self.continuous_pages.iter().flatten())
+    }
+}
+
+impl CairoPublicInput<Felt> {
+    /// The values at `begin..end`, each of which must be in public memory.
+    pub fn memory_range(&self, begin: u32, end: u32) -> Result<Vec<Felt>> {
+        let memory = self
+            .public_memory()
+            .map(|cell| (cell.address, cell.value))
+            .collect::<HashMap<_, _>>();
+
+        (begin..end)
+            .map(|addr| {
+                memory.get(&addr).copied().ok_or_else(|| {
+                    Error::InvalidValue(format!("address {addr} not in public memory"))
+                })
+            })
+            .collect()
+    }
+
+    /// Decodes `continuous_page_headers`, which must hold `n_continuous_pages` headers.
+    pub fn page_headers(&self) -> Result<Vec<ContinuousPageHeader>> {
+        if self.continuous_page_headers.len() != self.n_continuous_pages * HEADER_LEN {
+            return Err(Error::InvalidValue(format!(
+                "{} continuous pages with {} header felts",
+                self.n_continuous_pages,
+                self.continuous_page_headers.len()
+            )));
+        }
+
+        Ok(self
+            .continuous_page_headers
+            .chunks(HEADER_LEN)
+            .map(|header| ContinuousPageHeader {
+                start_address: header[0],
+                size: header[1],
+                hash_low: header[2],
+                hash_high: header[3],
+                prod: header[4],
+            })
+            .collect())
+    }
+
+    /// Sets the continuous page headers from the cells of `continuous_pages`.
+    pub fn compute_page_headers(&mut self, z: Felt, alpha: Felt) {
+        self.continuous_page_headers = self
+            .continuous_pages
+            .iter()
+            .flat_map(|page| page_header(page, z, alpha))
+            .collect();
+        self.n_continuous_pages = self.continuous_pages.len();
+    }
+}
+
+impl PublicInput {
+    /// Keeps at most `max_page_len` cells in the main page of an `air_public_input.json`
+    /// and moves the rest into new continuous pages of at most `max_page_len` consecutive
+    /// addresses, for verifiers with a page size limit. The pages are part of the public
+    /// input hash that seeds the channel, so this must run before proving: Stone then
+    /// computes the page headers.
+    pub fn paginate(&mut self, max_page_len: usize) -> Result<()> {
+        if max_page_len == 0 {
+            return Err(Error::InvalidParameter(
+                "page size limit must be positive".to_string(),
+            ));
+        }
+
+        let mut next_page = self.public_memory.iter().map(|m| m.page).max().unwrap_or(0);
+        let (mut page_len, mut last_address) = (0, None);
+        for cell in self
+            .public_memory
+            .iter_mut()
+            .filter(|m| m.page == 0)
+            .skip(max_page_len)
+        {
+            let contiguous = last_address.and_then(|a: u32| a.checked_add(1)) == Some(cell.address);
+            if !contiguous || page_len == max_page_len {
+                next_page += 1;
+                page_len = 0;
+            }
+            cell.page = next_page;
+            page_len += 1;
+            last_address = Some(cell.address);
+        }
+
+        Ok(())
+    }
+}
+
+fn page_header(page: &[PublicMemoryCell<Felt>], z: Felt, alpha: Felt) -> [Felt; HEADER_LEN] {
+    let start_address = page.first().map_or(0, |cell| cell.address);
+
+    let mut hasher = Keccak256::new();
+    for cell in page {
+        hasher.update(cell.value.to_bytes_be());
+    }
+    let hash = hasher.finalize();
+
+    let prod = page.iter().fold(Felt::ONE, |prod, cell| {
+        prod * (z - (Felt::from(cell.address) + alpha * cell.value))
+    });
+
+    [
+        Felt::from(start_address),
+        Felt::from(page.len()),
+        Felt::from_bytes_be_slice(&hash[16..]),
+        Felt::from_bytes_be_slice(&hash[..16]),
+        prod,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProofJSON;
+
+    #[test]
+    fn test_paginate() {
+        let public_memory = [1, 2, 3, 4, 5, 9, 10]
+            .map(|address| {
+                format!(r#"{{"address": {address}, "page": 0, "value": "0x{address}00"}}"#)
+            })
+            .join(", ");
+        let mut public_input: PublicInput = serde_json::from_str(&format!(
+            r#"{{
+                "layout": "recursive",
+                "memory_segments": {{"program": {{"begin_addr": 1, "stop_ptr": 5}}}},
+                "n_steps": 16,
+                "public_memory": [{public_memory}],
+                "rc_min": 0,
+                "rc_max": 100
+            }}"#
+        ))
+        .unwrap();
+        let (z, alpha) = (Felt::from(7), Felt::from(3));
+        let hash = |public_input: &PublicInput| {
+            let mut public_input = ProofJSON::public_input(public_input.clone()).unwrap();
+            public_input.compute_page_headers(z, alpha);
+            public_input.hash()
+        };
+        let before = hash(&public_input);
+
+        public_input.paginate(2).unwrap();
+        let pages = public_input
+            .public_memory
+            .iter()
+            .map(|m| (m.address, m.page))
+            .collect::<Vec<_>>();
+        // [3, 4], [5], [9, 10]: split by the size limit and by the address gap.
+        assert_eq!(
+            pages,
+            [(1, 0), (2, 0), (3, 1), (4, 1), (5, 2), (9, 3), (10, 3)]
+        );
+
+        let mut paginated = ProofJSON::public_input(public_input.clone()).unwrap();
+        assert_eq!(paginated.main_page_len, 2);
+        paginated.compute_page_headers(z, alpha);
+        assert_eq!(paginated.n_continuous_pages, 3);
+        let headers = paginated.page_headers().unwrap();
+        assert_eq!(headers[0].start_address, Felt::from(3));
+        assert_eq!(headers[0].size, Felt::from(2));
+        assert_eq!(
+            headers[0].prod,
+            (z - Felt::from(3 + 3 * 0x300)) * (z - Felt::from(4 + 3 * 0x400))
+        );
+        assert_eq!(headers[2].start_address, Felt::from(9));
+        assert_eq!(
+            paginated
+                .public_memory()
+                .map(|cell| cell.address)
+                .collect::<Vec<_>>(),
+            [1, 2, 3, 4, 5, 9, 10]
+        );
+
+        // The pages are hashed into the channel seed: a proven proof can't be paginated.
+        assert_ne!(hash(&public_input), before);
+
+        assert!(public_input.paginate(0).is_err());
+    }
+}
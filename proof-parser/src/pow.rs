@@ -0,0 +1,77 @@
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    channel::replay_until_pow,
+    error::{Error, Result},
+    hashes::HashFunction,
+    stark_proof::StarkProof,
+};
+
+const POW_MAGIC: u64 = 0x0123456789abcded;
+
+/// Checks that `hash(hash(magic || digest || n_bits) || nonce)` starts with `n_bits` zero bits.
+pub(crate) fn check_pow(hash: HashFunction, digest: Felt, n_bits: u32, nonce: u64) -> Result<bool> {
+    let init_hash = hash.digest(&[
+        &POW_MAGIC.to_be_bytes(),
+        &digest.to_bytes_be(),
+        &[n_bits as u8],
+    ])?;
+    let hash = hash.digest(&[&init_hash, &nonce.to_be_bytes()])?;
+
+    let leading_zeros = hash
+        .iter()
+        .position(|byte| *byte != 0)
+        .map(|i| 8 * i as u32 + hash[i].leading_zeros())
+        .unwrap_or(256);
+    Ok(leading_zeros >= n_bits)
+}
+
+impl StarkProof {
+    /// Recomputes the proof of work from the channel state and checks the nonce
+    /// against `proof_of_work.n_bits`. A cheap sanity check before submitting a proof.
+    pub fn verify_pow(&self) -> Result<()> {
+        let n_bits = self.config.proof_of_work.n_bits;
+        if n_bits == 0 {
+            return Ok(());
+        }
+
+        let nonce = self.unsent_commitment.proof_of_work_nonce;
+        let nonce = u64::try_from(nonce)
+            .map_err(|_| Error::InvalidValue(format!("proof of work nonce {nonce:#x}")))?;
+
+        let hashes = &self.config.hashes;
+        hashes.check_channel()?;
+        let channel = replay_until_pow(&self.public_input, &self.unsent_commitment)?;
+        if !check_pow(hashes.pow, channel.digest, n_bits, nonce)? {
+            return Err(Error::Verification(format!(
+                "proof of work nonce {nonce:#x} does not meet {n_bits} bits"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_pow() {
+        let mut proof = StarkProof::sample();
+        proof.config.proof_of_work.n_bits = 8;
+
+        let digest = replay_until_pow(&proof.public_input, &proof.unsent_commitment)
+            .unwrap()
+            .digest;
+        let nonce = (0..)
+            .find(|nonce| check_pow(HashFunction::Keccak256, digest, 8, *nonce).unwrap())
+            .unwrap();
+
+        proof.unsent_commitment.proof_of_work_nonce = Felt::from(nonce);
+        proof.verify_pow().unwrap();
+
+        proof.unsent_commitment.oods_values[0] += Felt::ONE;
+        let err = proof.verify_pow().unwrap_err();
+        assert!(matches!(err, Error::Verification(_)), "{err}");
+    }
+}
@@ -1,65 +1,59 @@
 use starknet_crypto::poseidon_hash_many;
 use starknet_types_core::felt::Felt;
-use std::collections::HashMap;
-use std::convert::TryInto;
 
-use crate::output::OUTPUT_SEGMENT_OFFSET;
-use crate::parse_raw;
+use crate::error::{Error, Result};
+use crate::layout::Layout;
+use crate::StarkProof;
 
-const PROGRAM_SEGMENT_OFFSET: usize = 0;
+// The Cairo 0 entry code: `ap += n_builtins; call rel main; jmp rel 0`.
+const CAIRO0_ENTRY_CODE: [(usize, u64); 4] = [
+    (0, 0x40780017fff7fff),
+    (2, 0x1104800180018000),
+    (4, 0x10780017fff7fff),
+    (5, 0x0),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CairoVersion {
+    /// The program starts with the Cairo 0 entry code, which is checked.
+    Cairo0,
+    Cairo1,
+}
 
 pub struct ExtractProgramResult {
     pub program: Vec<Felt>,
     pub program_hash: Felt,
 }
 
-pub fn extract_program(input: &str) -> anyhow::Result<ExtractProgramResult> {
-    // Parse the input string into a proof structure
-    let proof = parse_raw(input)?;
-
-    // Retrieve the program segment from the proof
-    let program_segment = proof
-        .public_input
-        .segments
-        .get(PROGRAM_SEGMENT_OFFSET)
-        .ok_or_else(|| anyhow::Error::msg("Program segment not found"))?;
-
-    // Retrieve the execution segment from the proof
-    let output_segment = proof
-        .public_input
-        .segments
-        .get(OUTPUT_SEGMENT_OFFSET)
-        .ok_or_else(|| anyhow::Error::msg("Output segment not found"))?;
-
-    // Construct a map for the main page elements
-    let mut main_page_map = HashMap::new();
-    for element in &proof.public_input.main_page {
-        let value_bytes = element.value.to_bytes_be();
-        let padded_value = vec![0u8; 32 - value_bytes.len()]
-            .iter()
-            .chain(value_bytes.iter())
-            .copied()
-            .collect::<Vec<u8>>();
-        let field_element =
-            Felt::from_bytes_be(&padded_value.try_into().expect("Failed to convert to array"));
-
-        main_page_map.insert(element.address, field_element);
-    }
+/// Reads the program from the public memory between the program segment start and
+/// `initial_fp - 2`, where the runner stores the return frame.
+pub fn extract_program(proof: &StarkProof, version: CairoVersion) -> Result<ExtractProgramResult> {
+    let initial_pc = proof
+        .program_segment()
+        .ok_or_else(|| Error::SegmentMissing("program".to_string()))?
+        .begin_addr;
+    let initial_fp = proof
+        .execution_segment()
+        .ok_or_else(|| Error::SegmentMissing("execution".to_string()))?
+        .begin_addr;
 
-    let initial_pc = program_segment.begin_addr;
+    let program_end = initial_fp
+        .checked_sub(2)
+        .filter(|end| *end >= initial_pc)
+        .ok_or_else(|| Error::InvalidValue(format!("initial fp {initial_fp} before program")))?;
+    let program = proof.public_input.memory_range(initial_pc, program_end)?;
 
-    // Extract program bytecode using the address range in the segments
-    let program: Vec<Felt> = (initial_pc
-        ..(proof.public_input.main_page.len() as u32 - output_segment.stop_ptr
-            + output_segment.begin_addr))
-        .map(|addr| {
-            *main_page_map
-                .get(&addr)
-                .expect("Address not found in main page map")
-        })
-        .collect();
+    if version == CairoVersion::Cairo0 {
+        for (index, instruction) in CAIRO0_ENTRY_CODE {
+            if program.get(index) != Some(&Felt::from(instruction)) {
+                return Err(Error::InvalidValue(format!(
+                    "program does not start with the Cairo 0 entry code (felt {index})"
+                )));
+            }
+        }
+    }
 
-    // Calculate the Poseidon hash of the program output
+    // Calculate the Poseidon hash of the program
     let program_hash = poseidon_hash_many(&program);
 
     Ok(ExtractProgramResult {
@@ -67,3 +61,168 @@ pub fn extract_program(input: &str) -> anyhow::Result<ExtractProgramResult> {
         program_hash,
     })
 }
+
+/// A Cairo 0 program as far as it can be recovered from a proof, for diffing against the
+/// compiled artifact. `main_pc` is relative to the program start, like pcs in `program.json`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconstructedProgram {
+    pub data: Vec<Felt>,
+    pub main_pc: u32,
+    pub builtins: Vec<String>,
+}
+
+impl ReconstructedProgram {
+    /// A minimal cairo-vm `program.json`, without hints, identifiers other than `main`
+    /// and debug info.
+    pub fn to_program_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "attributes": [],
+            "builtins": self.builtins,
+            "compiler_version": "0.0.0",
+            "data": self.data.iter().map(|felt| format!("{felt:#x}")).collect::<Vec<_>>(),
+            "debug_info": null,
+            "hints": {},
+            "identifiers": {
+                "__main__.main": {
+                    "decorators": [],
+                    "pc": self.main_pc,
+                    "type": "function"
+                }
+            },
+            "main_scope": "__main__",
+            "prime": "0x800000000000011000000000000000000000000000000000000000000000001",
+            "reference_manager": { "references": [] }
+        })
+    }
+}
+
+/// Rebuilds a Cairo 0 program from the entry code and the builtin pointers the runner
+/// stores at the start of the execution segment.
+pub fn reconstruct_program(proof: &StarkProof) -> Result<ReconstructedProgram> {
+    let ExtractProgramResult { program, .. } = extract_program(proof, CairoVersion::Cairo0)?;
+    let public_input = &proof.public_input;
+
+    // `call rel main` sits at pc 2, its offset at pc 3.
+    let main_pc = (program[3] + Felt::TWO)
+        .try_into()
+        .map_err(|_| Error::InvalidValue(format!("main offset {}", program[3])))?;
+    let n_builtins: u32 = program[1]
+        .try_into()
+        .map_err(|_| Error::InvalidValue(format!("{} builtins", program[1])))?;
+
+    let layout = Layout::from_felt(public_input.layout)?;
+    let layout_builtins = layout.builtins(&public_input.dynamic_params);
+    let initial_ap = proof
+        .execution_segment()
+        .ok_or_else(|| Error::SegmentMissing("execution".to_string()))?
+        .begin_addr;
+
+    let builtins = public_input
+        .memory_range(initial_ap, initial_ap + n_builtins)?
+        .into_iter()
+        .map(|pointer| {
+            layout_builtins
+                .iter()
+                .find(|builtin| {
+                    proof
+                        .segment(**builtin)
+                        .is_some_and(|segment| Felt::from(segment.begin_addr) == pointer)
+                })
+                .map(|builtin| builtin.name().to_string())
+                .ok_or_else(|| {
+                    Error::InvalidValue(format!("{pointer} is not a {layout} builtin base"))
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(ReconstructedProgram {
+        data: program,
+        main_pc,
+        builtins,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::stark_proof::PublicMemoryCell;
+
+    // Places `program` at the sample program segment start and the output after it.
+    pub(crate) fn proof_with_program(program: &[Felt], output: &[Felt]) -> StarkProof {
+        let mut proof = StarkProof::sample();
+        // Program at 1, initial fp at 10, output at [20, 22).
+        proof.public_input.main_page = (1..)
+            .zip(program.iter().copied())
+            .chain((20..).zip(output.iter().copied()))
+            .map(|(address, value)| PublicMemoryCell { address, value })
+            .collect();
+        proof
+    }
+
+    #[test]
+    fn test_extract_program() {
+        let cairo0 = [
+            0x40780017fff7fff_u64,
+            1,
+            0x1104800180018000,
+            4,
+            0x10780017fff7fff,
+            0,
+            9,
+        ]
+        .map(Felt::from);
+        let proof = proof_with_program(&cairo0, &[Felt::ONE, Felt::TWO]);
+        for version in [CairoVersion::Cairo0, CairoVersion::Cairo1] {
+            let result = extract_program(&proof, version).unwrap();
+            assert_eq!(result.program, cairo0);
+            assert_eq!(result.program_hash, poseidon_hash_many(&cairo0));
+        }
+
+        // A Cairo 1 program has no Cairo 0 entry code, and it may contain any value, 9 included.
+        let cairo1 = [9_u64, 0x7ffd8000, 7, 9, 0, 1, 2].map(Felt::from);
+        let proof = proof_with_program(&cairo1, &[Felt::ONE, Felt::TWO]);
+        assert_eq!(
+            extract_program(&proof, CairoVersion::Cairo1)
+                .unwrap()
+                .program,
+            cairo1
+        );
+        assert!(extract_program(&proof, CairoVersion::Cairo0).is_err());
+
+        // Missing program cells are reported, not skipped.
+        let proof = proof_with_program(&cairo1[..5], &[Felt::ONE, Felt::TWO]);
+        assert!(extract_program(&proof, CairoVersion::Cairo1).is_err());
+    }
+
+    #[test]
+    fn test_reconstruct_program() {
+        let cairo0 = [
+            0x40780017fff7fff_u64,
+            1,
+            0x1104800180018000,
+            3,
+            0x10780017fff7fff,
+            0,
+            9,
+        ]
+        .map(Felt::from);
+        let mut proof = proof_with_program(&cairo0, &[Felt::ONE, Felt::TWO]);
+        // The output pointer at initial ap.
+        proof.public_input.main_page.push(PublicMemoryCell {
+            address: 10,
+            value: Felt::from(20),
+        });
+
+        let program = reconstruct_program(&proof).unwrap();
+        assert_eq!(program.main_pc, 5);
+        assert_eq!(program.builtins, ["output"]);
+        assert_eq!(program.data, cairo0);
+
+        let json = program.to_program_json();
+        assert_eq!(json["identifiers"]["__main__.main"]["pc"], 5);
+        assert_eq!(json["data"][0], "0x40780017fff7fff");
+
+        proof.public_input.main_page.last_mut().unwrap().value = Felt::from(21);
+        assert!(reconstruct_program(&proof).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use serde_felt::{
+    felts_to_montgomery, montgomery_to_felts, DeserializeFelt, FeltReader, SerializeFelt,
+};
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    error::Result,
+    proof_structure::ProofStructure,
+    stark_proof::{
+        CairoPublicInput, StarkConfig, StarkProof, StarkUnsentCommitment, StarkWitness,
+        StoneFriLayerWitness, StoneFriWitness,
+    },
+};
+
+// Each FRI layer has its own lengths, so the layers are not derivable.
+impl<'de> DeserializeFelt<'de, ProofStructure> for StoneFriWitness {
+    fn deserialize_felt(
+        reader: &mut FeltReader<'de>,
+        structure: &ProofStructure,
+    ) -> serde_felt::Result<Self> {
+        let layers = structure
+            .layer
+            .iter()
+            .zip(structure.witness.iter())
+            .map(|(&leaves, &table_witness)| {
+                Ok(StoneFriLayerWitness {
+                    leaves: montgomery_to_felts(reader.take(leaves, "leaves")?),
+                    table_witness: reader.take(table_witness, "table_witness")?.to_vec(),
+                })
+            })
+            .collect::<serde_felt::Result<_>>()?;
+
+        Ok(Self { layers })
+    }
+}
+
+impl SerializeFelt for StoneFriWitness {
+    fn serialize_felt(&self, out: &mut Vec<Felt>) {
+        for layer in &self.layers {
+            out.extend(felts_to_montgomery(&layer.leaves));
+            out.extend_from_slice(&layer.table_witness);
+        }
+    }
+}
+
+impl StarkProof {
+    /// Reconstructs a proof from the felts of a Stone proof body (commitments followed
+    /// by the witness, leaves in Montgomery form) laid out according to `structure`.
+    pub fn decode_body(
+        felts: &[Felt],
+        structure: &ProofStructure,
+        config: &StarkConfig,
+        public_input: CairoPublicInput<Felt>,
+    ) -> Result<Self> {
+        let mut reader = FeltReader::new(felts);
+        let unsent_commitment = StarkUnsentCommitment::deserialize_felt(&mut reader, structure)?;
+        let witness = StarkWitness::deserialize_felt(&mut reader, structure)?;
+        reader.finish()?;
+
+        Ok(StarkProof {
+            config: config.clone(),
+            public_input,
+            unsent_commitment,
+            witness: witness.into(),
+            source_digest: None,
+        })
+    }
+
+    /// Inverse of `decode_body`: the proof body in the Stone proof hex layout.
+    pub fn encode_body(&self) -> Vec<Felt> {
+        let mut out = vec![];
+        self.unsent_commitment.serialize_felt(&mut out);
+        StarkWitness::from(self.witness.clone()).serialize_felt(&mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    fn structure() -> ProofStructure {
+        ProofStructure {
+            first_layer_queries: 2,
+            layer_count: 1,
+            composition_decommitment: 1,
+            oods: 3,
+            composition_leaves: 2,
+            last_layer_degree_bound: 2,
+            authentications: 1,
+            layer: vec![2],
+            witness: vec![1],
+        }
+    }
+
+    #[test]
+    fn test_decode_unsent_commitment() {
+        let felts = (1..=10u64).map(Felt::from).collect::<Vec<_>>();
+        let mut reader = FeltReader::new(&felts);
+        let commitment =
+            StarkUnsentCommitment::deserialize_felt(&mut reader, &structure()).unwrap();
+        reader.finish().unwrap();
+
+        assert_eq!(commitment.traces.interaction, Felt::from(2));
+        assert_eq!(commitment.oods_values, [4, 5, 6].map(Felt::from));
+        assert_eq!(commitment.fri.inner_layers, vec![Felt::from(7)]);
+        assert_eq!(commitment.proof_of_work_nonce, Felt::from(10));
+    }
+
+    #[test]
+    fn test_decode_truncated() {
+        let felts = vec![Felt::ONE; 4];
+        let mut reader = FeltReader::new(&felts);
+        let err = StarkUnsentCommitment::deserialize_felt(&mut reader, &structure()).unwrap_err();
+        assert_eq!(
+            Error::from(err).to_string(),
+            "oods_values: expected 3 felts, got 1"
+        );
+    }
+
+    #[test]
+    fn test_decode_body() {
+        let proof = StarkProof::sample();
+        let structure = structure();
+        let len = 10 + 2 + 1 + 1 + 1 + 2 + 1 + 2 + 1;
+        let felts = (1..=len as u64).map(Felt::from).collect::<Vec<_>>();
+
+        let decoded = StarkProof::decode_body(
+            &felts,
+            &structure,
+            &proof.config,
+            proof.public_input.clone(),
+        )
+        .unwrap();
+
+        assert_eq!(decoded.config, proof.config);
+        assert_eq!(
+            decoded.witness.original_authentications,
+            vec![Felt::from(13)]
+        );
+        assert_eq!(
+            decoded.witness.fri_witness.layers[0].table_witness,
+            vec![Felt::from(len as u64)]
+        );
+        assert_eq!(decoded.encode_body(), felts);
+        assert!(StarkProof::decode_body(
+            &felts[1..],
+            &structure,
+            &proof.config,
+            proof.public_input
+        )
+        .is_err());
+    }
+}
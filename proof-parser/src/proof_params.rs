@@ -1,20 +1,40 @@
-use ::serde::Deserialize;
+use ::serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ProofParameters {
     pub stark: Stark,
     #[serde(default)]
     pub n_verifier_friendly_commitment_layers: u32,
+    #[serde(flatten)]
+    pub stone6: Option<Stone6Parameters>,
 }
 
-// https://github.com/cartridge-gg/stone-prover/blob/fd78b4db8d6a037aa467b7558ac8930c10e48dc1/src/starkware/main/verifier_main_helper_impl.cc#L54-L55#[derive(Deserialize, Debug, Clone, PartialEq)]
-#[derive(Deserialize, Debug, Clone, PartialEq)]
+// Hash and channel settings that Stone v6 adds next to `stark`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Stone6Parameters {
+    pub channel_hash: String,
+    pub commitment_hash: String,
+    pub pow_hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statement: Option<Statement>,
+    pub use_extension_field: bool,
+    pub verifier_friendly_channel_updates: bool,
+    pub verifier_friendly_commitment_hashes: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub page_hash: String,
+}
+
+// https://github.com/cartridge-gg/stone-prover/blob/fd78b4db8d6a037aa467b7558ac8930c10e48dc1/src/starkware/main/verifier_main_helper_impl.cc#L54-L55#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Stark {
     pub fri: Fri,
     pub log_n_cosets: u32,
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Fri {
     pub fri_step_list: Vec<u32>,
     pub last_layer_degree_bound: u32,
@@ -22,9 +42,56 @@ pub struct Fri {
     pub proof_of_work_bits: u32,
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq)]
+// Stone's `cpu_air_prover_config.json`. Missing fields take Stone's defaults.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
 pub struct ProverConfig {
+    pub cached_lde_config: CachedLdeConfig,
     pub constraint_polynomial_task_size: u32,
     pub n_out_of_memory_merkle_layers: u32,
     pub table_prover_n_tasks_per_segment: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_n_max_in_memory_fri_layer_elements: Option<u32>,
+}
+
+impl Default for ProverConfig {
+    fn default() -> Self {
+        ProverConfig {
+            cached_lde_config: CachedLdeConfig::default(),
+            constraint_polynomial_task_size: 256,
+            n_out_of_memory_merkle_layers: 1,
+            table_prover_n_tasks_per_segment: 32,
+            log_n_max_in_memory_fri_layer_elements: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct CachedLdeConfig {
+    pub store_full_lde: bool,
+    pub use_fft_for_eval: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prover_config_defaults() {
+        let config: ProverConfig = serde_json::from_str(
+            r#"{
+                "cached_lde_config": { "store_full_lde": true, "use_fft_for_eval": false },
+                "constraint_polynomial_task_size": 128,
+                "log_n_max_in_memory_fri_layer_elements": 63
+            }"#,
+        )
+        .unwrap();
+
+        assert!(config.cached_lde_config.store_full_lde);
+        assert_eq!(config.constraint_polynomial_task_size, 128);
+        assert_eq!(config.log_n_max_in_memory_fri_layer_elements, Some(63));
+        assert_eq!(config.n_out_of_memory_merkle_layers, 1);
+        assert_eq!(config.table_prover_n_tasks_per_segment, 32);
+    }
 }
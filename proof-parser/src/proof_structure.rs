@@ -1,10 +1,11 @@
 use crate::{
-    layout::Layout,
+    error::{Error, Result},
+    layout::{Layout, LayoutConstants},
     proof_params::{ProofParameters, ProverConfig},
 };
 
 #[derive(Clone, Copy)]
-struct ProofCharacteristics<'a>(&'a ProofParameters, &'a ProverConfig, Layout);
+struct ProofCharacteristics<'a>(&'a ProofParameters, &'a ProverConfig, LayoutConstants);
 
 // https://github.com/cartridge-gg/stone-prover/blob/fd78b4db8d6a037aa467b7558ac8930c10e48dc1/src/starkware/stark/stark.cc#L303-L304
 #[cfg(test)]
@@ -29,54 +30,64 @@ pub fn leaves(proof_params: &ProofParameters) -> Vec<usize> {
 }
 
 // https://github.com/cartridge-gg/stone-prover/blob/fd78b4db8d6a037aa467b7558ac8930c10e48dc1/src/starkware/commitment_scheme/packaging_commitment_scheme.cc#L245-L250
-fn authentications(prover_config: ProofCharacteristics, proof_len: Option<usize>) -> usize {
+fn authentications(prover_config: ProofCharacteristics) -> usize {
     prover_config.1.constraint_polynomial_task_size as usize
-        + authentication_additional_queries(prover_config, proof_len)
 }
 
-fn authentication_additional_queries(
-    proof_args: ProofCharacteristics,
-    proof_len: Option<usize>,
-) -> usize {
-    // 12 for fib1
-    // 8 for fib100
-    // 3 for fib2000
-    // 56 // for fib2000 on starknet layout
-
-    if proof_len.is_none() {
-        return 0;
-    }
-
-    if let Some(proof_len) = proof_len {
-        let ProofCharacteristics(proof_params, proof_config, layout) = proof_args;
-        let without_additional = ProofStructure::new(proof_params, proof_config, layout, None);
-
-        let authentication_count = 3 + without_additional.witness.len();
-        (proof_len - without_additional.expected_len()) / authentication_count
-    } else {
-        // this is assuming no additional queries are needed
-        0
-    }
-}
-
-fn witness(proof_args: ProofCharacteristics, proof_len: Option<usize>) -> Vec<usize> {
+fn witness(proof_args: ProofCharacteristics, log_eval_domain_size: u32) -> Result<Vec<usize>> {
     let fri = &proof_args.0.stark.fri;
-    let first_fri_step = 16;
-    let mut cumulative = 0;
+    // A non-zero first step folds the first layer before the first committed FRI layer.
+    let mut cumulative = fri.fri_step_list.first().copied().unwrap_or(0);
     let mut vec = Vec::new();
 
+    // Each FRI layer is a tree over the cosets of its domain, `cumulative` steps below the
+    // evaluation domain.
     // https://github.com/cartridge-gg/stone-prover/blob/fd78b4db8d6a037aa467b7558ac8930c10e48dc1/src/starkware/fri/fri_details.cc#L93-L97
     for v in fri.fri_step_list.iter().skip(1) {
         cumulative += *v;
-        vec.push(first_fri_step - cumulative);
+        let height = log_eval_domain_size
+            .checked_sub(cumulative)
+            .ok_or_else(|| {
+                Error::InvalidParameter(format!(
+                    "fri steps {:?} fold more than the evaluation domain 2^{}",
+                    fri.fri_step_list, log_eval_domain_size
+                ))
+            })?;
+        vec.push(height);
     }
 
     // https://github.com/cartridge-gg/stone-prover/blob/fd78b4db8d6a037aa467b7558ac8930c10e48dc1/src/starkware/fri/fri_details.cc#L74-L82
-    vec.into_iter()
+    Ok(vec
+        .into_iter()
         .map(|len| fri.n_queries * len)
         .map(|x| x as usize)
-        .map(|x| x + authentication_additional_queries(proof_args, proof_len))
-        .collect()
+        .collect())
+}
+
+fn first_step(proof_params: &ProofParameters) -> u32 {
+    proof_params
+        .stark
+        .fri
+        .fri_step_list
+        .first()
+        .copied()
+        .unwrap_or(0)
+}
+
+/// Number of sibling nodes needed to authenticate the leaves at the sorted `indices` of
+/// a Merkle tree of the given height.
+fn authentication_len(indices: &[u64], height: u32) -> usize {
+    let mut nodes = indices.to_vec();
+    let mut len = 0;
+    for _ in 0..height {
+        len += nodes
+            .iter()
+            .filter(|&&node| nodes.binary_search(&(node ^ 1)).is_err())
+            .count();
+        nodes = nodes.iter().map(|node| node / 2).collect();
+        nodes.dedup();
+    }
+    len
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -93,51 +104,155 @@ pub struct ProofStructure {
 }
 
 impl ProofStructure {
-    pub fn new(
+    /// The structure of a proof with the given parameters and no duplicated queries, over an
+    /// evaluation domain of size `2^log_eval_domain_size`. Proofs with duplicated queries are
+    /// sized exactly from their replayed queries, see `for_queries`; when that is not
+    /// possible, a known `proof_len` that does not match is spread over what is sent per
+    /// query, see `resized`, and is a `LengthMismatch` only if it cannot absorb it.
+    pub(crate) fn new(
         proof_params: &ProofParameters,
         proof_config: &ProverConfig,
         layout: Layout,
+        consts: LayoutConstants,
+        log_eval_domain_size: u32,
         proof_len: Option<usize>,
-    ) -> Self {
-        let n_queries = proof_params.stark.fri.n_queries;
-        let mask_len = layout.mask_len();
-        let consts = layout.get_consts();
+    ) -> Result<Self> {
+        let proof_args = ProofCharacteristics(proof_params, proof_config, consts);
+        let structure = Self::build(proof_args, log_eval_domain_size)?;
+
+        let Some(proof_len) = proof_len else {
+            return Ok(structure);
+        };
+        let expected = structure.expected_len();
+        structure
+            .resized(proof_len)
+            .ok_or_else(|| Error::LengthMismatch {
+                section: "proof".to_string(),
+                expected,
+                got: proof_len,
+                probable_cause: Some(format!(
+                    "shorter than the commitments of any proof with these parameters, check the layout ({layout}) \
+                     and the FRI parameters"
+                )),
+            })
+    }
+
+    /// The structure resized to `proof_len` felts, a guess for proofs whose exact queries are
+    /// not known. Duplicated queries shrink everything sent per query, so the leaves and the
+    /// authentication paths are scaled to take up what the commitments leave: the trace and
+    /// composition tables share their queries and keep equal paths, and the rounding
+    /// remainder goes to the last FRI layer. `None` when `proof_len` is too short for the
+    /// commitments alone.
+    pub(crate) fn resized(mut self, proof_len: usize) -> Option<Self> {
+        let decommitment_len = self.expected_len() - self.commitment_len();
+        let budget = proof_len.checked_sub(self.commitment_len())?;
+        let scale = |len: &mut usize| {
+            *len = match decommitment_len {
+                0 => 0,
+                _ => (*len as u128 * budget as u128 / decommitment_len as u128) as usize,
+            }
+        };
+
+        scale(&mut self.first_layer_queries);
+        scale(&mut self.composition_decommitment);
+        scale(&mut self.composition_leaves);
+        scale(&mut self.authentications);
+        self.layer.iter_mut().for_each(scale);
+        self.witness.iter_mut().for_each(scale);
+        let remainder = proof_len - self.expected_len();
+        match self.witness.last_mut() {
+            Some(last) => *last += remainder,
+            None if remainder.is_multiple_of(3) => self.authentications += remainder / 3,
+            None => return None,
+        }
+        Some(self)
+    }
 
-        let proof_args = ProofCharacteristics(proof_params, proof_config, layout);
+    fn build(proof_args: ProofCharacteristics, log_eval_domain_size: u32) -> Result<Self> {
+        let ProofCharacteristics(proof_params, _, consts) = proof_args;
+        // Each query decommits a whole coset of the first layer, one row unless the first
+        // FRI step is non-zero.
+        let n_queries = proof_params.stark.fri.n_queries;
+        let first_layer_rows = n_queries << first_step(proof_params);
 
-        let proof_structure = ProofStructure {
+        Ok(ProofStructure {
             // https://github.com/cartridge-gg/stone-prover/blob/fd78b4db8d6a037aa467b7558ac8930c10e48dc1/src/starkware/stark/stark.cc#L276-L277
-            first_layer_queries: (n_queries * consts.num_columns_first) as usize,
+            first_layer_queries: (first_layer_rows * consts.num_columns_first) as usize,
 
             layer_count: proof_params.stark.fri.fri_step_list.len() - 1,
-            composition_decommitment: (n_queries * consts.num_columns_second) as usize,
+            composition_decommitment: (first_layer_rows * consts.num_columns_second) as usize,
 
             // https://github.com/cartridge-gg/stone-prover/blob/fd78b4db8d6a037aa467b7558ac8930c10e48dc1/src/starkware/stark/oods.cc#L92-L93
-            oods: mask_len + proof_params.stark.log_n_cosets as usize - 1,
+            // The mask values are followed by one evaluation per composition column.
+            oods: consts.mask_len + consts.constraint_degree as usize,
             last_layer_degree_bound: proof_params.stark.fri.last_layer_degree_bound as usize,
 
             // https://github.com/cartridge-gg/stone-prover/blob/fd78b4db8d6a037aa467b7558ac8930c10e48dc1/src/starkware/stark/composition_oracle.cc#L288-L289
-            composition_leaves: 2 * n_queries as usize,
-            authentications: authentications(proof_args, proof_len),
+            composition_leaves: (first_layer_rows * consts.constraint_degree) as usize,
+            authentications: authentications(proof_args),
 
             layer: leaves(proof_params),
-            witness: witness(proof_args, proof_len),
-        };
+            witness: witness(proof_args, log_eval_domain_size)?,
+        })
+    }
+
+    /// Exact lengths for the query indices replayed from the transcript, where duplicated
+    /// queries and shared authentication nodes are only sent once.
+    pub(crate) fn for_queries(
+        proof_params: &ProofParameters,
+        consts: LayoutConstants,
+        queries: &[u64],
+        log_eval_domain_size: u32,
+    ) -> Self {
+        let first_step = first_step(proof_params);
+        let mut indices = queries.iter().map(|q| q >> first_step).collect::<Vec<_>>();
+        indices.dedup();
+        // The first layer is decommitted from the traces and the composition, whole cosets
+        // at a time.
+        let rows = indices
+            .iter()
+            .flat_map(|coset| (coset << first_step)..((coset + 1) << first_step))
+            .collect::<Vec<_>>();
+        let n_rows = rows.len() as u32;
+
+        let mut layer = vec![];
+        let mut witness = vec![];
+        let mut height = log_eval_domain_size - first_step;
+        for &step in proof_params.stark.fri.fri_step_list.iter().skip(1) {
+            let mut cosets = indices.iter().map(|i| i >> step).collect::<Vec<_>>();
+            cosets.dedup();
 
-        if let Some(proof_len) = proof_len {
-            assert_eq!(proof_structure.expected_len(), proof_len);
+            layer.push((cosets.len() << step) - indices.len());
+            height -= step;
+            witness.push(authentication_len(&cosets, height));
+            indices = cosets;
+        }
+
+        ProofStructure {
+            first_layer_queries: (n_rows * consts.num_columns_first) as usize,
+            layer_count: proof_params.stark.fri.fri_step_list.len() - 1,
+            composition_decommitment: (n_rows * consts.num_columns_second) as usize,
+            oods: consts.mask_len + consts.constraint_degree as usize,
+            last_layer_degree_bound: proof_params.stark.fri.last_layer_degree_bound as usize,
+            composition_leaves: (n_rows * consts.constraint_degree) as usize,
+            authentications: authentication_len(&rows, log_eval_domain_size),
+            layer,
+            witness,
         }
-        proof_structure
     }
 
     pub fn expected_len(&self) -> usize {
-        let commitment_len = 3 + self.oods + self.layer_count + self.last_layer_degree_bound + 1;
         let witness_len = self.first_layer_queries
             + self.composition_decommitment
             + self.composition_leaves
             + 3 * self.authentications;
         let fri_len: usize = self.layer.iter().sum::<usize>() + self.witness.iter().sum::<usize>();
-        commitment_len + witness_len + fri_len
+        self.commitment_len() + witness_len + fri_len
+    }
+
+    // The commitments, OODS values, last layer and nonce, which do not depend on the queries.
+    fn commitment_len(&self) -> usize {
+        3 + self.oods + self.layer_count + self.last_layer_degree_bound + 1
     }
 }
 
@@ -158,14 +273,24 @@ fn test_lens() {
             log_n_cosets: 3,
         },
         n_verifier_friendly_commitment_layers: 0,
+        stone6: None,
     };
     let proof_config = ProverConfig {
         constraint_polynomial_task_size: 256,
         n_out_of_memory_merkle_layers: 1,
         table_prover_n_tasks_per_segment: 1,
+        ..ProverConfig::default()
     };
 
-    let result = ProofStructure::new(&proof_params, &proof_config, layout, None);
+    let result = ProofStructure::new(
+        &proof_params,
+        &proof_config,
+        layout,
+        layout.get_consts().unwrap(),
+        16,
+        None,
+    )
+    .unwrap();
 
     let expected = ProofStructure {
         first_layer_queries: 112,
@@ -174,12 +299,219 @@ fn test_lens() {
         oods: 135,
         last_layer_degree_bound: 128,
         composition_leaves: 32,
-        authentications: 256 + 8, // 257
+        authentications: 256,
         layer: vec![240, 240, 112],
-        // witness: vec![193, 129, 81],
-        witness: vec![200, 136, 88],
+        witness: vec![192, 128, 80],
     };
 
     assert_eq!(result, expected);
     assert_eq!(fri_degree_bound(&proof_params), 262144);
 }
+
+#[test]
+fn test_constraint_degree() {
+    use crate::proof_params::Fri;
+
+    let layout = Layout::Recursive;
+    let proof_params = ProofParameters {
+        stark: crate::proof_params::Stark {
+            fri: Fri {
+                fri_step_list: vec![0, 4, 4, 3],
+                last_layer_degree_bound: 128,
+                n_queries: 16,
+                proof_of_work_bits: 30,
+            },
+            log_n_cosets: 4,
+        },
+        n_verifier_friendly_commitment_layers: 0,
+        stone6: None,
+    };
+    let proof_config = ProverConfig {
+        constraint_polynomial_task_size: 256,
+        n_out_of_memory_merkle_layers: 1,
+        table_prover_n_tasks_per_segment: 1,
+        ..ProverConfig::default()
+    };
+    let consts = LayoutConstants {
+        constraint_degree: 4,
+        ..layout.get_consts().unwrap()
+    };
+
+    let result =
+        ProofStructure::new(&proof_params, &proof_config, layout, consts, 16, None).unwrap();
+
+    assert_eq!(result.oods, 133 + 4);
+    assert_eq!(result.composition_leaves, 16 * 4);
+}
+
+#[test]
+fn test_length_mismatch() {
+    use crate::proof_params::Fri;
+
+    let layout = Layout::Recursive;
+    let proof_params = ProofParameters {
+        stark: crate::proof_params::Stark {
+            fri: Fri {
+                fri_step_list: vec![0, 4, 4, 3],
+                last_layer_degree_bound: 128,
+                n_queries: 16,
+                proof_of_work_bits: 30,
+            },
+            log_n_cosets: 3,
+        },
+        n_verifier_friendly_commitment_layers: 0,
+        stone6: None,
+    };
+    let proof_config = ProverConfig::default();
+    let consts = layout.get_consts().unwrap();
+    let structure = |proof_len| {
+        ProofStructure::new(&proof_params, &proof_config, layout, consts, 16, proof_len)
+    };
+    let len = structure(None).unwrap().expected_len();
+
+    assert_eq!(structure(Some(len)).unwrap(), structure(None).unwrap());
+
+    // Other lengths scale everything sent per query.
+    let base = structure(None).unwrap();
+    let commitment_len = base.commitment_len();
+    let decommitment_len = len - commitment_len;
+    let sections = |structure: &ProofStructure| {
+        let mut sections = vec![
+            structure.first_layer_queries,
+            structure.composition_decommitment,
+            structure.composition_leaves,
+            structure.authentications,
+        ];
+        sections.extend(&structure.layer);
+        sections.extend(&structure.witness);
+        sections
+    };
+
+    let doubled = structure(Some(len + decommitment_len)).unwrap();
+    assert_eq!(doubled.expected_len(), len + decommitment_len);
+    assert_eq!(
+        sections(&doubled),
+        sections(&base)
+            .iter()
+            .map(|len| 2 * len)
+            .collect::<Vec<_>>()
+    );
+
+    // About halved, as with many duplicated queries, the remainder going to the last layer.
+    let halved = structure(Some(commitment_len + decommitment_len / 2)).unwrap();
+    assert_eq!(halved.expected_len(), commitment_len + decommitment_len / 2);
+    let (last, halved_sections) = sections(&halved)
+        .split_last()
+        .map(|(l, s)| (*l, s.to_vec()))
+        .unwrap();
+    for (halved, base) in halved_sections.iter().zip(sections(&base)) {
+        assert!(halved.abs_diff(base / 2) <= 1, "{halved} {base}");
+    }
+    assert!(last >= base.witness[2] / 2);
+
+    let empty = structure(Some(commitment_len)).unwrap();
+    assert!(sections(&empty).iter().all(|&len| len == 0));
+
+    // Too short for the commitments alone.
+    let proof_len = commitment_len - 1;
+    match structure(Some(proof_len)).unwrap_err() {
+        Error::LengthMismatch {
+            expected,
+            got,
+            probable_cause: Some(probable_cause),
+            ..
+        } => {
+            assert_eq!((expected, got), (len, proof_len));
+            assert!(
+                probable_cause.contains("shorter than the commitments of any proof"),
+                "{probable_cause}"
+            );
+        }
+        err => panic!("unexpected error {err}"),
+    }
+}
+
+#[test]
+fn test_long_fri_step_list() {
+    use crate::proof_params::Fri;
+
+    let layout = Layout::Recursive;
+    let proof_params = ProofParameters {
+        stark: crate::proof_params::Stark {
+            fri: Fri {
+                fri_step_list: vec![0, 4, 4, 4, 4, 3],
+                last_layer_degree_bound: 64,
+                n_queries: 16,
+                proof_of_work_bits: 30,
+            },
+            log_n_cosets: 3,
+        },
+        n_verifier_friendly_commitment_layers: 0,
+        stone6: None,
+    };
+    let proof_config = ProverConfig::default();
+    let consts = layout.get_consts().unwrap();
+    let structure = |log_eval_domain_size| {
+        ProofStructure::new(
+            &proof_params,
+            &proof_config,
+            layout,
+            consts,
+            log_eval_domain_size,
+            None,
+        )
+    };
+
+    // The steps fold 2^19 rows, more than a 2^16 domain holds.
+    let err = structure(16).unwrap_err();
+    assert!(matches!(err, Error::InvalidParameter(_)), "{err}");
+
+    assert_eq!(
+        structure(25).unwrap().witness,
+        [21, 17, 13, 9, 6].map(|height| 16 * height)
+    );
+}
+
+#[test]
+fn test_non_zero_first_step() {
+    use crate::proof_params::Fri;
+
+    let layout = Layout::Recursive;
+    let proof_params = |fri_step_list| ProofParameters {
+        stark: crate::proof_params::Stark {
+            fri: Fri {
+                fri_step_list,
+                last_layer_degree_bound: 128,
+                n_queries: 2,
+                proof_of_work_bits: 30,
+            },
+            log_n_cosets: 3,
+        },
+        n_verifier_friendly_commitment_layers: 0,
+        stone6: None,
+    };
+    let consts = layout.get_consts().unwrap();
+    let queries = [3, 12];
+
+    let zero = ProofStructure::for_queries(&proof_params(vec![0, 2]), consts, &queries, 8);
+    let folded = ProofStructure::for_queries(&proof_params(vec![2, 2]), consts, &queries, 8);
+
+    // Queries 3 and 12 fall in the cosets [0, 4) and [12, 16) of the first layer.
+    assert_eq!(folded.first_layer_queries, 4 * zero.first_layer_queries);
+    assert_eq!(
+        folded.composition_leaves,
+        8 * consts.constraint_degree as usize
+    );
+    assert_eq!(folded.authentications, authentication_len(&[0, 3], 6));
+    // The first FRI layer is indexed by coset: 0 and 3, folded again into coset 0.
+    assert_eq!(folded.layer, vec![4 - 2]);
+    assert_eq!(folded.witness, vec![authentication_len(&[0], 4)]);
+}
+
+#[test]
+fn test_authentication_len() {
+    // 9 needs its sibling 8, then both paths need an uncle per level until they meet.
+    assert_eq!(authentication_len(&[2, 3, 9], 4), 1 + 2 + 2);
+    assert_eq!(authentication_len(&[0], 4), 4);
+    assert_eq!(authentication_len(&(0..16).collect::<Vec<_>>(), 4), 0);
+}
@@ -0,0 +1,104 @@
+use std::{fmt::Display, io::Read};
+
+use sha2::{Digest, Sha256};
+
+use crate::stark_proof::StarkProof;
+
+/// Ties a parsed proof back to the exact input artifact it was parsed from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SourceDigest {
+    pub sha256: [u8; 32],
+    pub parser_version: &'static str,
+}
+
+impl SourceDigest {
+    pub fn new(input: &[u8]) -> Self {
+        Self {
+            sha256: Sha256::digest(input).into(),
+            parser_version: env!("CARGO_PKG_VERSION"),
+        }
+    }
+}
+
+/// Computes the `SourceDigest` of everything read through it.
+pub(crate) struct DigestReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> DigestReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    pub fn finish(self) -> SourceDigest {
+        SourceDigest {
+            sha256: self.hasher.finalize().into(),
+            parser_version: env!("CARGO_PKG_VERSION"),
+        }
+    }
+}
+
+impl<R: Read> Read for DigestReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl Display for SourceDigest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "sha256:{}@{}/{}",
+            prefix_hex::encode(self.sha256).trim_start_matches("0x"),
+            env!("CARGO_PKG_NAME"),
+            self.parser_version
+        )
+    }
+}
+
+impl StarkProof {
+    /// Digest of the proof file this proof was parsed from, if it was parsed from one.
+    pub fn source_digest(&self) -> Option<&SourceDigest> {
+        self.source_digest.as_ref()
+    }
+
+    pub fn with_source_digest(mut self, input: &[u8]) -> Self {
+        self.source_digest = Some(SourceDigest::new(input));
+        self
+    }
+
+    pub(crate) fn with_digest(mut self, digest: SourceDigest) -> Self {
+        self.source_digest = Some(digest);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_digest() {
+        let digest = SourceDigest::new(b"abc");
+        assert_eq!(
+            digest.to_string(),
+            format!(
+                "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad@cairo-proof-parser/{}",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn test_digest_reader() {
+        let mut reader = DigestReader::new(&b"abc"[..]);
+        std::io::copy(&mut reader, &mut std::io::sink()).unwrap();
+        assert_eq!(reader.finish(), SourceDigest::new(b"abc"));
+    }
+}
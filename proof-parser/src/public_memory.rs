@@ -0,0 +1,124 @@
+use std::collections::{BTreeMap, HashSet};
+
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    error::{Error, Result},
+    stark_proof::PublicMemoryCell,
+};
+
+/// Public memory cells grouped by page: the main page, then the continuous pages that
+/// Stone numbers from 1. A continuous page is a run of consecutive addresses, and no
+/// address appears twice across pages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublicMemory {
+    main_page: Vec<PublicMemoryCell<Felt>>,
+    continuous_pages: Vec<Vec<PublicMemoryCell<Felt>>>,
+}
+
+impl PublicMemory {
+    /// Groups `(page, cell)` pairs, keeping the order of the cells within each page.
+    pub fn from_cells(
+        cells: impl IntoIterator<Item = (u32, PublicMemoryCell<Felt>)>,
+    ) -> Result<Self> {
+        let mut seen = HashSet::new();
+        let mut pages = BTreeMap::<u32, Vec<_>>::new();
+        for (page, cell) in cells {
+            if !seen.insert(cell.address) {
+                return Err(Error::InvalidValue(format!(
+                    "address {} appears twice in public memory",
+                    cell.address
+                )));
+            }
+            pages.entry(page).or_default().push(cell);
+        }
+
+        let main_page = pages.remove(&0).unwrap_or_default();
+        if pages.keys().copied().ne(1..=pages.len() as u32) {
+            return Err(Error::InvalidValue(format!(
+                "public memory pages are not numbered consecutively: {:?}",
+                pages.keys().collect::<Vec<_>>()
+            )));
+        }
+        for (page, cells) in &pages {
+            let start = cells[0].address;
+            if cells
+                .iter()
+                .zip(start..)
+                .any(|(cell, address)| cell.address != address)
+            {
+                return Err(Error::InvalidValue(format!(
+                    "public memory page {page} is not contiguous from address {start}"
+                )));
+            }
+        }
+
+        Ok(PublicMemory {
+            main_page,
+            continuous_pages: pages.into_values().collect(),
+        })
+    }
+
+    pub fn main_page(&self) -> &[PublicMemoryCell<Felt>] {
+        &self.main_page
+    }
+
+    pub fn continuous_pages(&self) -> &[Vec<PublicMemoryCell<Felt>>] {
+        &self.continuous_pages
+    }
+
+    /// Pages with their numbers, the main page first.
+    pub fn pages(&self) -> impl Iterator<Item = (u32, &[PublicMemoryCell<Felt>])> {
+        std::iter::once(self.main_page.as_slice())
+            .chain(self.continuous_pages.iter().map(Vec::as_slice))
+            .zip(0..)
+            .map(|(cells, page)| (page, cells))
+    }
+
+    pub fn into_pages(
+        self,
+    ) -> (
+        Vec<PublicMemoryCell<Felt>>,
+        Vec<Vec<PublicMemoryCell<Felt>>>,
+    ) {
+        (self.main_page, self.continuous_pages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(cells: &[(u32, u32)]) -> Vec<(u32, PublicMemoryCell<Felt>)> {
+        cells
+            .iter()
+            .map(|&(page, address)| {
+                (
+                    page,
+                    PublicMemoryCell {
+                        address,
+                        value: Felt::from(address),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_public_memory() {
+        let memory =
+            PublicMemory::from_cells(cells(&[(0, 1), (2, 20), (0, 5), (2, 21), (1, 30)])).unwrap();
+        assert_eq!(
+            memory
+                .pages()
+                .map(|(page, cells)| (page, cells.iter().map(|c| c.address).collect()))
+                .collect::<Vec<(u32, Vec<_>)>>(),
+            [(0, vec![1, 5]), (1, vec![30]), (2, vec![20, 21])]
+        );
+
+        let err = |c: &[(u32, u32)]| PublicMemory::from_cells(cells(c)).unwrap_err().to_string();
+        assert!(err(&[(0, 1), (1, 1)]).contains("appears twice"));
+        assert!(err(&[(0, 1), (1, 20), (1, 22)]).contains("not contiguous"));
+        assert!(err(&[(0, 1), (2, 20)]).contains("numbered consecutively"));
+    }
+}
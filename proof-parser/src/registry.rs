@@ -0,0 +1,537 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::info;
+use starknet::{
+    accounts::{Call, ConnectedAccount},
+    core::types::{
+        ExecuteInvocation, FeeEstimate, Felt, SimulatedTransaction, TransactionExecutionStatus,
+        TransactionReceipt, TransactionStatus, TransactionTrace,
+    },
+    macros::{felt, selector},
+    providers::Provider,
+};
+use starknet_crypto::{poseidon_hash, poseidon_hash_many};
+use tokio::time::sleep;
+
+use crate::{
+    integrity::{IntegrityCalls, VerifierSettings},
+    StarkProof,
+};
+
+/// How the registration transaction pays its fee. Values left `None` are estimated by
+/// simulating the transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fee {
+    /// A v1 transaction paying in ETH.
+    Eth { max_fee: Option<Felt> },
+    /// A v3 transaction paying in STRK, within the given L1 gas bounds.
+    Strk {
+        gas: Option<u64>,
+        gas_price: Option<u128>,
+    },
+}
+
+impl Default for Fee {
+    fn default() -> Self {
+        Fee::Eth {
+            max_fee: Some(felt!("1000000000000000")),
+        }
+    }
+}
+
+/// The status `wait_for_transaction` waits for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirmation {
+    /// Return once the transaction is sent.
+    None,
+    AcceptedOnL2,
+    AcceptedOnL1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitPolicy {
+    pub confirmation: Confirmation,
+    pub timeout: Duration,
+    pub poll_interval: Duration,
+}
+
+impl Default for WaitPolicy {
+    fn default() -> Self {
+        WaitPolicy {
+            confirmation: Confirmation::AcceptedOnL2,
+            timeout: Duration::from_secs(60),
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistrationOptions {
+    pub fee: Fee,
+    pub wait: WaitPolicy,
+    /// Proofs with more calldata felts are verified in steps, see `register_proof`.
+    pub max_calldata: usize,
+    /// The verifier configuration of split verification.
+    pub settings: VerifierSettings,
+}
+
+impl Default for RegistrationOptions {
+    fn default() -> Self {
+        RegistrationOptions {
+            fee: Fee::default(),
+            wait: WaitPolicy::default(),
+            max_calldata: 4000,
+            settings: VerifierSettings::default(),
+        }
+    }
+}
+
+/// The calldata `register_proof` sends, decided by the size of the proof.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegistrationPlan {
+    /// The serialized proof, in a single call.
+    Single(Vec<Felt>),
+    /// Integrity's split verification, for proofs over `max_calldata` felts. The calls
+    /// after `verify_proof_initial` need the state emitted by the previous one.
+    Split(IntegrityCalls),
+}
+
+impl RegistrationPlan {
+    pub fn new(proof: &StarkProof, options: &RegistrationOptions) -> anyhow::Result<Self> {
+        let serialized_proof = proof.to_felts()?;
+        if serialized_proof.len() <= options.max_calldata {
+            return Ok(RegistrationPlan::Single(serialized_proof));
+        }
+
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+        let job_id = poseidon_hash(poseidon_hash_many(&serialized_proof), Felt::from(nanos));
+        Ok(RegistrationPlan::Split(
+            proof.to_integrity_calls(job_id, &options.settings)?,
+        ))
+    }
+
+    /// The number of calls, hence transactions, `register_proof` sends.
+    pub fn n_calls(&self) -> usize {
+        match self {
+            RegistrationPlan::Single(_) => 1,
+            // `verify_proof_initial`, the steps, and `verify_proof_final_and_register_fact`.
+            RegistrationPlan::Split(calls) => calls.steps.len() + 2,
+        }
+    }
+
+    /// The selector and calldata of the first call, `selector` being the one of a single
+    /// call registration.
+    pub fn first_call(&self, selector: Felt) -> (Felt, Vec<Felt>) {
+        match self {
+            RegistrationPlan::Single(calldata) => (selector, calldata.clone()),
+            RegistrationPlan::Split(calls) => {
+                (selector!("verify_proof_initial"), calls.initial.clone())
+            }
+        }
+    }
+}
+
+/// The outcome of simulating a registration, see `simulate_registration`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Simulation {
+    pub overall_fee: Felt,
+    /// Why the verifier rejected the proof, `None` if the call succeeded.
+    pub revert_reason: Option<String>,
+    /// Calls of a split verification left out of the simulation, see `simulate_proof_registration`.
+    pub unsimulated_calls: usize,
+}
+
+/// Simulates the registration `register_fact` would send, without sending it. Fees are
+/// not charged in the simulation, so an underfunded account can still dry-run.
+pub async fn simulate_registration<A>(
+    account: &A,
+    serialized_proof: Vec<Felt>,
+    to: Felt,
+    selector: Felt,
+    fee: Fee,
+) -> anyhow::Result<Simulation>
+where
+    A: ConnectedAccount + Sync,
+    A::SignError: Send + Sync + 'static,
+{
+    let calls = vec![Call {
+        to,
+        selector,
+        calldata: serialized_proof,
+    }];
+
+    let SimulatedTransaction {
+        transaction_trace,
+        fee_estimation,
+    } = match fee {
+        Fee::Eth { max_fee } => {
+            let execution = account.execute_v1(calls);
+            match max_fee {
+                Some(max_fee) => execution.max_fee(max_fee),
+                None => execution,
+            }
+            .simulate(false, true)
+            .await?
+        }
+        Fee::Strk { .. } => account.execute_v3(calls).simulate(false, true).await?,
+    };
+
+    let revert_reason = match transaction_trace {
+        TransactionTrace::Invoke(trace) => match trace.execute_invocation {
+            ExecuteInvocation::Success(_) => None,
+            ExecuteInvocation::Reverted(reverted) => Some(reverted.revert_reason),
+        },
+        _ => anyhow::bail!("Simulated registration is not an invoke transaction"),
+    };
+
+    Ok(Simulation {
+        overall_fee: fee_estimation.overall_fee,
+        revert_reason,
+        unsimulated_calls: 0,
+    })
+}
+
+/// Simulates the first transaction `register_proof` would send for `proof`: the single
+/// registration call, or the `verify_proof_initial` of a split verification. The later
+/// split verification calls read the state the earlier ones leave on chain, so they can't
+/// be simulated beforehand and are only counted.
+pub async fn simulate_proof_registration<A>(
+    account: &A,
+    proof: &StarkProof,
+    to: Felt,
+    selector: Felt,
+    options: RegistrationOptions,
+) -> anyhow::Result<Simulation>
+where
+    A: ConnectedAccount + Sync,
+    A::SignError: Send + Sync + 'static,
+{
+    let plan = RegistrationPlan::new(proof, &options)?;
+    let (selector, calldata) = plan.first_call(selector);
+    let simulation = simulate_registration(account, calldata, to, selector, options.fee).await?;
+
+    Ok(Simulation {
+        unsimulated_calls: plan.n_calls() - 1,
+        ..simulation
+    })
+}
+
+/// Submits the serialized proof to the fact registry at `to` and waits for it as
+/// `options.wait` says.
+pub async fn register_fact<A>(
+    account: &A,
+    serialized_proof: Vec<Felt>,
+    to: Felt,
+    selector: Felt,
+    options: RegistrationOptions,
+) -> anyhow::Result<Felt>
+where
+    A: ConnectedAccount + Sync,
+    A::SignError: Send + Sync + 'static,
+{
+    send_call(
+        account,
+        to,
+        selector,
+        serialized_proof,
+        options.fee,
+        options.wait,
+    )
+    .await
+}
+
+/// Registers `proof` in a single `selector` call when its calldata fits in
+/// `options.max_calldata` felts, and through Integrity's split verification otherwise.
+/// Returns the hashes of the transactions sent.
+pub async fn register_proof<A>(
+    account: &A,
+    proof: &StarkProof,
+    to: Felt,
+    selector: Felt,
+    options: RegistrationOptions,
+) -> anyhow::Result<Vec<Felt>>
+where
+    A: ConnectedAccount + Sync,
+    A::SignError: Send + Sync + 'static,
+{
+    let calls = match RegistrationPlan::new(proof, &options)? {
+        RegistrationPlan::Single(serialized_proof) => {
+            let tx = register_fact(account, serialized_proof, to, selector, options).await?;
+            return Ok(vec![tx]);
+        }
+        RegistrationPlan::Split(calls) => calls,
+    };
+    info!(
+        "Proof exceeds the calldata limit of {} felts, verifying in steps.",
+        options.max_calldata
+    );
+    info!("job id: {:#x}", calls.job_id);
+
+    // The receipt of each call carries the FRI state the next call needs.
+    let wait = match options.wait.confirmation {
+        Confirmation::None => WaitPolicy {
+            confirmation: Confirmation::AcceptedOnL2,
+            ..options.wait
+        },
+        _ => options.wait,
+    };
+    let provider = account.provider();
+    let fee = options.fee;
+
+    let initial = calls.initial.clone();
+    let mut txs = vec![
+        send_call(
+            account,
+            to,
+            selector!("verify_proof_initial"),
+            initial,
+            fee,
+            wait,
+        )
+        .await?,
+    ];
+    for i in 0..calls.steps.len() {
+        let state = fri_state(provider, *txs.last().unwrap(), to).await?;
+        let calldata = calls.step_calldata(i, &state).expect("step index in range");
+        txs.push(
+            send_call(
+                account,
+                to,
+                selector!("verify_proof_step"),
+                calldata,
+                fee,
+                wait,
+            )
+            .await?,
+        );
+    }
+    let state = fri_state(provider, *txs.last().unwrap(), to).await?;
+    let calldata = calls.final_calldata(&state);
+    let selector = selector!("verify_proof_final_and_register_fact");
+    txs.push(send_call(account, to, selector, calldata, fee, wait).await?);
+
+    Ok(txs)
+}
+
+// The FRI verification state a split verification call leaves for the next one: the
+// data of the last event the verifier emitted.
+async fn fri_state<P>(
+    provider: &P,
+    transaction_hash: Felt,
+    verifier: Felt,
+) -> anyhow::Result<Vec<Felt>>
+where
+    P: Provider + Sync,
+{
+    let receipt = provider.get_transaction_receipt(transaction_hash).await?;
+    let TransactionReceipt::Invoke(receipt) = receipt.receipt else {
+        anyhow::bail!("Transaction {transaction_hash:#x} is not an invoke transaction");
+    };
+    receipt
+        .events
+        .into_iter()
+        .rev()
+        .find(|event| event.from_address == verifier)
+        .map(|event| event.data)
+        .ok_or(anyhow::anyhow!(
+            "Transaction {transaction_hash:#x} emitted no FRI verification state"
+        ))
+}
+
+async fn send_call<A>(
+    account: &A,
+    to: Felt,
+    selector: Felt,
+    calldata: Vec<Felt>,
+    fee: Fee,
+    wait: WaitPolicy,
+) -> anyhow::Result<Felt>
+where
+    A: ConnectedAccount + Sync,
+    A::SignError: Send + Sync + 'static,
+{
+    let calls = vec![Call {
+        to,
+        selector,
+        calldata,
+    }];
+
+    let tx = match fee {
+        Fee::Eth { max_fee } => {
+            let execution = account.execute_v1(calls);
+            let max_fee = match max_fee {
+                Some(max_fee) => max_fee,
+                None => {
+                    let estimate = execution.estimate_fee().await?;
+                    info!("estimated fee: {} wei", estimate.overall_fee);
+                    // Leave a 50% margin over the estimate.
+                    let fee = u128::try_from(estimate.overall_fee)
+                        .map_err(|_| anyhow::anyhow!("Fee estimate out of range"))?;
+                    Felt::from(fee + fee / 2)
+                }
+            };
+            execution.max_fee(max_fee).send().await?
+        }
+        Fee::Strk { gas, gas_price } => {
+            let execution = account.execute_v3(calls);
+            let (gas, gas_price) = match (gas, gas_price) {
+                (Some(gas), Some(gas_price)) => (gas, gas_price),
+                _ => {
+                    let estimate = execution.estimate_fee().await?;
+                    info!("estimated fee: {} fri", estimate.overall_fee);
+                    let (estimated_gas, estimated_gas_price) = strk_bounds(&estimate)?;
+                    (
+                        gas.unwrap_or(estimated_gas),
+                        gas_price.unwrap_or(estimated_gas_price),
+                    )
+                }
+            };
+            execution.gas(gas).gas_price(gas_price).send().await?
+        }
+    };
+
+    info!("tx hash: {:#x}", tx.transaction_hash);
+
+    wait_for_transaction(account.provider(), tx.transaction_hash, wait).await?;
+
+    Ok(tx.transaction_hash)
+}
+
+// L1 gas bounds covering `estimate` with the same 50% margin as v1 max fees: the gas
+// that pays the overall fee at the estimated price, and that price.
+fn strk_bounds(estimate: &FeeEstimate) -> anyhow::Result<(u64, u128)> {
+    let out_of_range = || anyhow::anyhow!("Fee estimate out of range");
+    let overall_fee = u128::try_from(estimate.overall_fee).map_err(|_| out_of_range())?;
+    let gas_price = u128::try_from(estimate.gas_price).map_err(|_| out_of_range())?;
+    if gas_price == 0 {
+        anyhow::bail!("Fee estimate has a zero gas price");
+    }
+
+    let gas = overall_fee.div_ceil(gas_price);
+    let gas = u64::try_from(gas + gas / 2).map_err(|_| out_of_range())?;
+    Ok((gas, gas_price + gas_price / 2))
+}
+
+/// Polls the transaction status until it reaches `policy.confirmation`, failing with the
+/// last known status if it is rejected, reverted or not confirmed within `policy.timeout`.
+pub async fn wait_for_transaction<P>(
+    provider: &P,
+    transaction_hash: Felt,
+    policy: WaitPolicy,
+) -> anyhow::Result<()>
+where
+    P: Provider + Sync,
+{
+    if policy.confirmation == Confirmation::None {
+        return Ok(());
+    }
+
+    let start_fetching = std::time::Instant::now();
+    let mut last_status = None;
+    loop {
+        if start_fetching.elapsed() > policy.timeout {
+            anyhow::bail!(
+                "Transaction {transaction_hash:#x} not confirmed in {} seconds, last status: {}.",
+                policy.timeout.as_secs(),
+                last_status.map_or("unknown".to_string(), |s| format!("{s:?}"))
+            );
+        }
+
+        let status = match provider.get_transaction_status(transaction_hash).await {
+            Ok(status) => status,
+            Err(_e) => {
+                sleep(policy.poll_interval).await;
+                continue;
+            }
+        };
+
+        let execution_status = match &status {
+            TransactionStatus::Received => None,
+            TransactionStatus::Rejected => {
+                anyhow::bail!("Transaction {:#x} rejected.", transaction_hash);
+            }
+            TransactionStatus::AcceptedOnL2(execution_status)
+            | TransactionStatus::AcceptedOnL1(execution_status) => Some(*execution_status),
+        };
+        if execution_status == Some(TransactionExecutionStatus::Reverted) {
+            anyhow::bail!("Transaction {:#x} reverted.", transaction_hash);
+        }
+
+        let confirmed = match &status {
+            TransactionStatus::AcceptedOnL1(_) => true,
+            TransactionStatus::AcceptedOnL2(_) => policy.confirmation == Confirmation::AcceptedOnL2,
+            _ => false,
+        };
+        if confirmed {
+            info!("Transaction {status:?}.");
+            return Ok(());
+        }
+        if last_status.as_ref() != Some(&status) {
+            info!("Transaction {status:?}.");
+        }
+        last_status = Some(status);
+        sleep(policy.poll_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strk_bounds() {
+        let estimate = FeeEstimate {
+            gas_consumed: felt!("90"),
+            gas_price: felt!("100"),
+            data_gas_consumed: felt!("1"),
+            data_gas_price: felt!("1001"),
+            overall_fee: felt!("10001"),
+            unit: starknet::core::types::PriceUnit::Fri,
+        };
+        // 101 gas pay the overall fee at the estimated price, plus the margin.
+        assert_eq!(strk_bounds(&estimate).unwrap(), (151, 150));
+
+        let free = FeeEstimate {
+            gas_price: Felt::ZERO,
+            ..estimate
+        };
+        assert!(strk_bounds(&free).is_err());
+    }
+
+    #[test]
+    fn test_registration_plan_round_trip() {
+        let proof = StarkProof::sample();
+        let (to, selector) = (felt!("0x10"), selector!("register"));
+        // `__execute__` calldata of a single call.
+        let execute = |(selector, calldata): (Felt, Vec<Felt>)| {
+            [Felt::ONE, to, selector, Felt::from(calldata.len())]
+                .into_iter()
+                .chain(calldata)
+                .collect::<Vec<_>>()
+        };
+        let decode = |calldata: Vec<Felt>| {
+            let (selector, calldata) = crate::onchain::execute_calls(&calldata).unwrap().remove(0);
+            StarkProof::from_call(selector, &calldata).unwrap()
+        };
+
+        let single = RegistrationPlan::new(&proof, &RegistrationOptions::default()).unwrap();
+        assert!(matches!(single, RegistrationPlan::Single(_)));
+        assert_eq!(decode(execute(single.first_call(selector))), proof);
+
+        let options = RegistrationOptions {
+            max_calldata: 1,
+            ..RegistrationOptions::default()
+        };
+        let split = RegistrationPlan::new(&proof, &options).unwrap();
+        assert!(matches!(split, RegistrationPlan::Split(_)));
+        assert_eq!(split.n_calls(), proof.witness.fri_witness.layers.len() + 2);
+        let mut without_layers = proof.clone();
+        without_layers.witness.fri_witness.layers.clear();
+        assert_eq!(decode(execute(split.first_call(selector))), without_layers);
+
+        let mut full = split.first_call(selector).1;
+        full.remove(0);
+        let full = (selector!("verify_proof_full_and_register_fact"), full);
+        assert_eq!(decode(execute(full)), without_layers);
+    }
+}
@@ -0,0 +1,203 @@
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    json_parser::ProofJSON,
+    proof_structure::ProofStructure,
+    sections::Section,
+    stark_proof::{CairoPublicInput, StarkConfig},
+};
+
+/// Everything that could be recovered from a damaged proof, and where recovery stopped.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SalvageReport {
+    pub config: Option<StarkConfig>,
+    pub public_input: Option<CairoPublicInput<Felt>>,
+    /// Sections recovered from the proof hex, as raw felts in their hex layout
+    /// (leaves are still Montgomery-encoded).
+    pub sections: Vec<(Section, Vec<Felt>)>,
+    pub failures: Vec<SalvageFailure>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SalvageFailure {
+    pub stage: String,
+    /// Felt offset into the proof hex, for failures while splitting it into sections.
+    pub offset: Option<usize>,
+    pub reason: String,
+}
+
+impl SalvageReport {
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    fn fail(&mut self, stage: impl ToString, offset: Option<usize>, reason: impl ToString) {
+        self.failures.push(SalvageFailure {
+            stage: stage.to_string(),
+            offset,
+            reason: reason.to_string(),
+        });
+    }
+}
+
+/// Parses as much of a proof as possible instead of stopping at the first error.
+pub fn salvage(input: &str) -> SalvageReport {
+    let mut report = SalvageReport::default();
+
+    let proof_json = match input.parse::<ProofJSON>() {
+        Ok(proof_json) => proof_json,
+        Err(e) => {
+            report.fail("json", None, e);
+            return report;
+        }
+    };
+
+    match proof_json.stark_config() {
+        Ok(config) => report.config = Some(config),
+        Err(e) => report.fail("config", None, e),
+    }
+
+    match ProofJSON::public_input(proof_json.public_input.clone()) {
+        Ok(public_input) => report.public_input = Some(public_input),
+        Err(e) => report.fail("public input", None, e),
+    }
+
+    let hex = match proof_json.proof_felts() {
+        Ok(hex) => hex,
+        Err(e) => {
+            report.fail("proof hex", None, e);
+            return report;
+        }
+    };
+
+    let consts = match proof_json.layout_consts() {
+        Ok(consts) => consts,
+        Err(e) => {
+            report.fail("proof structure", None, e);
+            return report;
+        }
+    };
+
+    // A damaged proof may not be consistent with its replayed queries, in which case the
+    // sections are laid out as if there were no duplicated queries.
+    let structure = proof_json.sized_proof_structure(&hex.0).or_else(|_| {
+        ProofStructure::new(
+            &proof_json.proof_parameters,
+            &proof_json.prover_config,
+            proof_json.public_input.layout,
+            consts,
+            proof_json.log_eval_damain_size()?,
+            None,
+        )
+    });
+    let structure = match structure {
+        Ok(structure) => structure,
+        Err(e) => {
+            report.fail("proof structure", None, e);
+            return report;
+        }
+    };
+
+    let mut offset = 0;
+    for (section, len) in hex_layout(&structure) {
+        match hex.0.get(offset..offset + len) {
+            Some(felts) => report.sections.push((section, felts.to_vec())),
+            None => {
+                report.fail(
+                    section,
+                    Some(offset),
+                    format!(
+                        "expected {len} felts, only {} left",
+                        hex.0.len().saturating_sub(offset)
+                    ),
+                );
+                return report;
+            }
+        }
+        offset += len;
+    }
+
+    if offset < hex.0.len() {
+        report.fail(
+            "trailing data",
+            Some(offset),
+            format!("{} unexpected felts", hex.0.len() - offset),
+        );
+    }
+
+    report
+}
+
+// Section lengths in the order they appear in the proof hex.
+fn hex_layout(structure: &ProofStructure) -> Vec<(Section, usize)> {
+    let mut layout = vec![
+        (Section::TracesCommitment, 2),
+        (Section::CompositionCommitment, 1),
+        (Section::OodsValues, structure.oods),
+        (Section::FriLayersCommitments, structure.layer_count),
+        (
+            Section::FriLastLayerCoefficients,
+            structure.last_layer_degree_bound,
+        ),
+        (Section::ProofOfWorkNonce, 1),
+        (Section::OriginalLeaves, structure.first_layer_queries),
+        (Section::OriginalAuthentications, structure.authentications),
+        (
+            Section::InteractionLeaves,
+            structure.composition_decommitment,
+        ),
+        (
+            Section::InteractionAuthentications,
+            structure.authentications,
+        ),
+        (Section::CompositionLeaves, structure.composition_leaves),
+        (
+            Section::CompositionAuthentications,
+            structure.authentications,
+        ),
+    ];
+
+    for (i, (leaves, witness)) in structure
+        .layer
+        .iter()
+        .zip(structure.witness.iter())
+        .enumerate()
+    {
+        layout.push((Section::FriLayer(i + 1), leaves + witness));
+    }
+
+    layout
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::tests::proof_json_str;
+
+    #[test]
+    fn test_salvage_truncated_proof() {
+        let hex = (1..=10).map(|i| format!("{i:064x}")).collect::<String>();
+        let report = salvage(&proof_json_str(&format!(r#""proof_hex": "0x{hex}""#)));
+
+        assert!(report.config.is_some());
+        assert!(report.public_input.is_some());
+        assert_eq!(
+            report.sections,
+            vec![
+                (Section::TracesCommitment, vec![Felt::ONE, Felt::TWO]),
+                (Section::CompositionCommitment, vec![Felt::THREE]),
+            ]
+        );
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].stage, "oods values");
+        assert_eq!(report.failures[0].offset, Some(3));
+    }
+
+    #[test]
+    fn test_salvage_invalid_json() {
+        let report = salvage("{");
+        assert!(!report.is_complete());
+        assert!(report.config.is_none());
+        assert_eq!(report.failures[0].stage, "json");
+    }
+}
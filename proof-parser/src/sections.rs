@@ -0,0 +1,226 @@
+use std::{fmt::Display, io::Write};
+
+use serde::Serialize;
+use serde_felt::to_felts;
+use starknet_types_core::felt::Felt;
+
+use crate::{error::Result, stark_proof::StarkProof};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    Config,
+    PublicInput,
+    TracesCommitment,
+    CompositionCommitment,
+    OodsValues,
+    FriLayersCommitments,
+    FriLastLayerCoefficients,
+    ProofOfWorkNonce,
+    OriginalLeaves,
+    InteractionLeaves,
+    OriginalAuthentications,
+    InteractionAuthentications,
+    CompositionLeaves,
+    CompositionAuthentications,
+    FriWitnessLength,
+    FriLayer(usize),
+}
+
+impl Display for Section {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Section::Config => write!(f, "config"),
+            Section::PublicInput => write!(f, "public input"),
+            Section::TracesCommitment => write!(f, "traces commitment"),
+            Section::CompositionCommitment => write!(f, "composition commitment"),
+            Section::OodsValues => write!(f, "oods values"),
+            Section::FriLayersCommitments => write!(f, "fri layers commitments"),
+            Section::FriLastLayerCoefficients => write!(f, "fri last layer coefficients"),
+            Section::ProofOfWorkNonce => write!(f, "proof of work nonce"),
+            Section::OriginalLeaves => write!(f, "original leaves"),
+            Section::InteractionLeaves => write!(f, "interaction leaves"),
+            Section::OriginalAuthentications => write!(f, "original authentications"),
+            Section::InteractionAuthentications => write!(f, "interaction authentications"),
+            Section::CompositionLeaves => write!(f, "composition leaves"),
+            Section::CompositionAuthentications => write!(f, "composition authentications"),
+            Section::FriWitnessLength => write!(f, "fri witness length"),
+            Section::FriLayer(i) => write!(f, "fri layer {i}"),
+        }
+    }
+}
+
+fn section<T: Serialize>(section: Section, value: &T) -> Result<(Section, Vec<Felt>)> {
+    Ok((section, to_felts(value)?))
+}
+
+/// Serializes the proof section by section. Concatenating the returned felts
+/// yields exactly `to_felts(proof)`.
+// The order here must follow the field order of `StarkProof`.
+pub fn to_felts_sections(proof: &StarkProof) -> Result<Vec<(Section, Vec<Felt>)>> {
+    let commitment = &proof.unsent_commitment;
+    let witness = &proof.witness;
+
+    let mut result = vec![
+        section(Section::Config, &proof.config)?,
+        section(Section::PublicInput, &proof.public_input)?,
+        section(Section::TracesCommitment, &commitment.traces)?,
+        section(Section::CompositionCommitment, &commitment.composition)?,
+        section(Section::OodsValues, &commitment.oods_values)?,
+        section(Section::FriLayersCommitments, &commitment.fri.inner_layers)?,
+        section(
+            Section::FriLastLayerCoefficients,
+            &commitment.fri.last_layer_coefficients,
+        )?,
+        section(Section::ProofOfWorkNonce, &commitment.proof_of_work_nonce)?,
+    ];
+
+    // The witness vectors are serialized with a doubled length prefix, see `double_len_serialize`.
+    for (s, v) in [
+        (Section::OriginalLeaves, &witness.original_leaves),
+        (Section::InteractionLeaves, &witness.interaction_leaves),
+        (
+            Section::OriginalAuthentications,
+            &witness.original_authentications,
+        ),
+        (
+            Section::InteractionAuthentications,
+            &witness.interaction_authentications,
+        ),
+        (Section::CompositionLeaves, &witness.composition_leaves),
+        (
+            Section::CompositionAuthentications,
+            &witness.composition_authentications,
+        ),
+    ] {
+        result.push(section(s, &(v.len(), v))?);
+    }
+
+    // The sequence length prefix is written by the serializer, so take it from its output.
+    let fri_witness = to_felts(&witness.fri_witness)?;
+    result.push((Section::FriWitnessLength, fri_witness[..1].to_vec()));
+    for (i, layer) in witness.fri_witness.layers.iter().enumerate() {
+        result.push(section(Section::FriLayer(i + 1), layer)?);
+    }
+
+    Ok(result)
+}
+
+/// Felt counts of each proof section, see `StarkProof::stats`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofStats {
+    /// Felts of each section, in calldata order.
+    pub sections: Vec<(Section, usize)>,
+    pub commitment: usize,
+    pub witness: usize,
+    pub total: usize,
+}
+
+impl ProofStats {
+    /// Felts of `section`, or 0 if the proof does not have it.
+    pub fn len(&self, section: Section) -> usize {
+        self.sections
+            .iter()
+            .find(|(s, _)| *s == section)
+            .map_or(0, |(_, len)| *len)
+    }
+}
+
+impl Display for ProofStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (section, len) in &self.sections {
+            writeln!(f, "{section}: {len}")?;
+        }
+        writeln!(f, "commitment total: {}", self.commitment)?;
+        writeln!(f, "witness total: {}", self.witness)?;
+        write!(f, "total: {}", self.total)
+    }
+}
+
+impl StarkProof {
+    /// Felt counts of each section of the serialized proof.
+    pub fn stats(&self) -> Result<ProofStats> {
+        let sections = to_felts_sections(self)?
+            .into_iter()
+            .map(|(section, felts)| (section, felts.len()))
+            .collect::<Vec<_>>();
+        let sum = |from: Section, to: Option<Section>| {
+            sections
+                .iter()
+                .skip_while(|(s, _)| *s != from)
+                .take_while(|(s, _)| Some(*s) != to)
+                .map(|(_, len)| len)
+                .sum()
+        };
+
+        Ok(ProofStats {
+            commitment: sum(Section::TracesCommitment, Some(Section::OriginalLeaves)),
+            witness: sum(Section::OriginalLeaves, None),
+            total: sections.iter().map(|(_, len)| len).sum(),
+            sections,
+        })
+    }
+
+    /// Writes the serialized proof one felt per line, grouped and labeled by
+    /// section, with the offset of every felt in the flat serialization.
+    pub fn dump_annotated<W: Write>(&self, mut writer: W) -> Result<()> {
+        let mut offset = 0;
+        for (section, felts) in to_felts_sections(self)? {
+            writeln!(
+                writer,
+                "# {section} [{offset}..{}) ({} felts)",
+                offset + felts.len(),
+                felts.len()
+            )?;
+            for felt in felts {
+                writeln!(writer, "{offset}: {felt:#x}")?;
+                offset += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sections_concatenate_to_serialization() {
+        let proof = StarkProof::sample();
+        let concatenated = to_felts_sections(&proof)
+            .unwrap()
+            .into_iter()
+            .flat_map(|(_, felts)| felts)
+            .collect::<Vec<_>>();
+
+        assert_eq!(concatenated, to_felts(&proof).unwrap());
+    }
+
+    #[test]
+    fn test_stats() {
+        let proof = StarkProof::sample();
+        let stats = proof.stats().unwrap();
+
+        assert_eq!(stats.total, to_felts(&proof).unwrap().len());
+        assert_eq!(
+            stats.len(Section::Config) + stats.len(Section::PublicInput) + stats.commitment,
+            stats.total - stats.witness
+        );
+        assert_eq!(stats.len(Section::OodsValues), 5);
+        assert_eq!(stats.len(Section::FriLayer(1)), 2 + 30 + 2);
+        assert_eq!(stats.len(Section::FriLayer(2)), 0);
+    }
+
+    #[test]
+    fn test_dump_annotated() {
+        let proof = StarkProof::sample();
+        let mut out = Vec::new();
+        proof.dump_annotated(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let total = to_felts(&proof).unwrap().len();
+        assert_eq!(out.lines().filter(|l| !l.starts_with('#')).count(), total);
+        assert!(out.starts_with("# config [0.."));
+        assert!(out.contains("# fri layer 1 ["));
+    }
+}
@@ -1,19 +1,47 @@
-use std::collections::BTreeMap;
+use std::{
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+};
 
 use serde::{Deserialize, Serialize};
 use starknet_types_core::felt::Felt;
 
-use serde_felt::deserialize_montgomery_vec;
+use serde_felt::{DeserializeFelt, SerializeFelt};
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+use crate::{hashes::HashSettings, proof_structure::ProofStructure, provenance::SourceDigest};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StarkProof {
     pub config: StarkConfig,
     pub public_input: CairoPublicInput<Felt>,
     pub unsent_commitment: StarkUnsentCommitment,
     pub witness: StarkWitnessReordered,
+    #[serde(skip)]
+    pub(crate) source_digest: Option<SourceDigest>,
+}
+
+// Equality and hashing follow the proof's contents, not the file it was parsed from.
+impl PartialEq for StarkProof {
+    fn eq(&self, other: &Self) -> bool {
+        self.config == other.config
+            && self.public_input == other.public_input
+            && self.unsent_commitment == other.unsent_commitment
+            && self.witness == other.witness
+    }
+}
+
+impl Eq for StarkProof {}
+
+impl Hash for StarkProof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&self.config, state);
+        Hash::hash(&self.public_input, state);
+        Hash::hash(&self.unsent_commitment, state);
+        Hash::hash(&self.witness, state);
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct StarkConfig {
     pub traces: TracesConfig,
     pub composition: TableCommitmentConfig,
@@ -23,27 +51,31 @@ pub struct StarkConfig {
     pub n_queries: u32,
     pub log_n_cosets: u32,
     pub n_verifier_friendly_commitment_layers: u32,
+    /// Not part of the verifier's config: only used by local verification, so it is left
+    /// out of the felt serialization.
+    #[serde(default, with = "serde_felt::skip")]
+    pub hashes: HashSettings,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct TracesConfig {
     pub original: TableCommitmentConfig,
     pub interaction: TableCommitmentConfig,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct TableCommitmentConfig {
     pub n_columns: u32,
     pub vector: VectorCommitmentConfig,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct VectorCommitmentConfig {
     pub height: u32,
     pub n_verifier_friendly_commitment_layers: u32,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct FriConfig {
     pub log_input_size: u32,
     pub n_layers: u32,
@@ -52,59 +84,93 @@ pub struct FriConfig {
     pub log_last_layer_degree_bound: u32,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ProofOfWorkConfig {
     pub n_bits: u32,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+// The felt derives follow the proof hex, so fields must stay in the order Stone writes them.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, SerializeFelt, DeserializeFelt,
+)]
+#[felt(context = "ProofStructure")]
 pub struct StarkUnsentCommitment {
     pub traces: TracesUnsentCommitment,
     pub composition: Felt,
+    #[felt(len_from = "oods")]
     pub oods_values: Vec<Felt>,
     pub fri: FriUnsentCommitment,
     pub proof_of_work_nonce: Felt,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, SerializeFelt, DeserializeFelt,
+)]
 pub struct TracesUnsentCommitment {
     pub original: Felt,
     pub interaction: Felt,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, SerializeFelt, DeserializeFelt,
+)]
+#[felt(context = "ProofStructure")]
 pub struct FriUnsentCommitment {
+    #[felt(len_from = "layer_count")]
     pub inner_layers: Vec<Felt>,
+    #[felt(len_from = "last_layer_degree_bound")]
     pub last_layer_coefficients: Vec<Felt>,
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SerializeFelt, DeserializeFelt)]
+#[felt(context = "ProofStructure")]
 pub struct StarkWitness {
-    #[serde(deserialize_with = "deserialize_montgomery_vec")]
+    #[felt(len_from = "first_layer_queries", montgomery)]
     pub original_leaves: Vec<Felt>,
+    #[felt(len_from = "authentications")]
     pub original_authentications: Vec<Felt>,
-    #[serde(deserialize_with = "deserialize_montgomery_vec")]
+    #[felt(len_from = "composition_decommitment", montgomery)]
     pub interaction_leaves: Vec<Felt>,
+    #[felt(len_from = "authentications")]
     pub interaction_authentications: Vec<Felt>,
-    #[serde(deserialize_with = "deserialize_montgomery_vec")]
+    #[felt(len_from = "composition_leaves", montgomery)]
     pub composition_leaves: Vec<Felt>,
+    #[felt(len_from = "authentications")]
     pub composition_authentications: Vec<Felt>,
-    pub fri_witness: FriWitness,
+    pub fri_witness: StoneFriWitness,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct StarkWitnessReordered {
-    #[serde(serialize_with = "double_len_serialize")]
+    #[serde(
+        serialize_with = "double_len_serialize",
+        deserialize_with = "double_len_deserialize"
+    )]
     pub original_leaves: Vec<Felt>,
-    #[serde(serialize_with = "double_len_serialize")]
+    #[serde(
+        serialize_with = "double_len_serialize",
+        deserialize_with = "double_len_deserialize"
+    )]
     pub interaction_leaves: Vec<Felt>,
-    #[serde(serialize_with = "double_len_serialize")]
+    #[serde(
+        serialize_with = "double_len_serialize",
+        deserialize_with = "double_len_deserialize"
+    )]
     pub original_authentications: Vec<Felt>,
-    #[serde(serialize_with = "double_len_serialize")]
+    #[serde(
+        serialize_with = "double_len_serialize",
+        deserialize_with = "double_len_deserialize"
+    )]
     pub interaction_authentications: Vec<Felt>,
-    #[serde(serialize_with = "double_len_serialize")]
+    #[serde(
+        serialize_with = "double_len_serialize",
+        deserialize_with = "double_len_deserialize"
+    )]
     pub composition_leaves: Vec<Felt>,
-    #[serde(serialize_with = "double_len_serialize")]
+    #[serde(
+        serialize_with = "double_len_serialize",
+        deserialize_with = "double_len_deserialize"
+    )]
     pub composition_authentications: Vec<Felt>,
     pub fri_witness: FriWitness,
 }
@@ -118,7 +184,21 @@ impl From<StarkWitness> for StarkWitnessReordered {
             interaction_authentications: witness.interaction_authentications,
             composition_leaves: witness.composition_leaves,
             composition_authentications: witness.composition_authentications,
-            fri_witness: witness.fri_witness,
+            fri_witness: witness.fri_witness.into(),
+        }
+    }
+}
+
+impl From<StarkWitnessReordered> for StarkWitness {
+    fn from(witness: StarkWitnessReordered) -> Self {
+        Self {
+            original_leaves: witness.original_leaves,
+            original_authentications: witness.original_authentications,
+            interaction_leaves: witness.interaction_leaves,
+            interaction_authentications: witness.interaction_authentications,
+            composition_leaves: witness.composition_leaves,
+            composition_authentications: witness.composition_authentications,
+            fri_witness: witness.fri_witness.into(),
         }
     }
 }
@@ -134,25 +214,74 @@ where
     value.serialize(serializer)
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub fn double_len_deserialize<'de, D>(deserializer: D) -> Result<Vec<Felt>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = VecWithLen::<Felt>::deserialize(deserializer)?;
+    Ok(value.vec)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct VecWithLen<T> {
     len: usize,
     vec: Vec<T>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct FriWitness {
     pub layers: Vec<FriLayerWitness>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct FriLayerWitness {
-    #[serde(deserialize_with = "deserialize_montgomery_vec")]
     pub leaves: Vec<Felt>,
     pub table_witness: Vec<Felt>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+// The FRI witness as laid out in Stone's proof hex, where leaves are Montgomery-encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StoneFriWitness {
+    pub layers: Vec<StoneFriLayerWitness>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StoneFriLayerWitness {
+    pub leaves: Vec<Felt>,
+    pub table_witness: Vec<Felt>,
+}
+
+impl From<FriWitness> for StoneFriWitness {
+    fn from(witness: FriWitness) -> Self {
+        Self {
+            layers: witness
+                .layers
+                .into_iter()
+                .map(|layer| StoneFriLayerWitness {
+                    leaves: layer.leaves,
+                    table_witness: layer.table_witness,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<StoneFriWitness> for FriWitness {
+    fn from(witness: StoneFriWitness) -> Self {
+        Self {
+            layers: witness
+                .layers
+                .into_iter()
+                .map(|layer| FriLayerWitness {
+                    leaves: layer.leaves,
+                    table_witness: layer.table_witness,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CairoPublicInput<B> {
     pub log_n_steps: u32,
     pub range_check_min: u32,
@@ -167,16 +296,124 @@ pub struct CairoPublicInput<B> {
     pub main_page: Vec<PublicMemoryCell<B>>,
     pub n_continuous_pages: usize,
     pub continuous_page_headers: Vec<B>,
+    /// The cells of each continuous page, when known. Verifiers only get the headers, so
+    /// the cells are left out of the felt serialization.
+    #[serde(default, with = "serde_felt::skip")]
+    pub continuous_pages: Vec<Vec<PublicMemoryCell<B>>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PublicMemoryCell<B> {
     pub address: u32,
     pub value: B,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct SegmentInfo {
     pub begin_addr: u32,
     pub stop_ptr: u32,
 }
+
+#[cfg(test)]
+impl StarkProof {
+    // A small, structurally valid proof used by unit tests. The values are not a real proof.
+    pub(crate) fn sample() -> Self {
+        let felts = |start: u64, n: u64| (start..start + n).map(Felt::from).collect::<Vec<_>>();
+        let table = |n_columns, height| TableCommitmentConfig {
+            n_columns,
+            vector: VectorCommitmentConfig {
+                height,
+                n_verifier_friendly_commitment_layers: 0,
+            },
+        };
+
+        StarkProof {
+            config: StarkConfig {
+                traces: TracesConfig {
+                    original: table(7, 21),
+                    interaction: table(3, 21),
+                },
+                composition: table(2, 21),
+                fri: FriConfig {
+                    log_input_size: 21,
+                    n_layers: 2,
+                    inner_layers: vec![table(16, 17)],
+                    fri_step_sizes: vec![0, 4],
+                    log_last_layer_degree_bound: 2,
+                },
+                proof_of_work: ProofOfWorkConfig { n_bits: 30 },
+                log_trace_domain_size: 18,
+                n_queries: 2,
+                log_n_cosets: 3,
+                n_verifier_friendly_commitment_layers: 0,
+                hashes: HashSettings::default(),
+            },
+            public_input: CairoPublicInput {
+                log_n_steps: 14,
+                range_check_min: 0,
+                range_check_max: 100,
+                layout: Felt::from_hex("0x726563757273697665").unwrap(),
+                dynamic_params: BTreeMap::new(),
+                n_segments: 3,
+                segments: vec![
+                    SegmentInfo {
+                        begin_addr: 1,
+                        stop_ptr: 5,
+                    },
+                    SegmentInfo {
+                        begin_addr: 10,
+                        stop_ptr: 20,
+                    },
+                    SegmentInfo {
+                        begin_addr: 20,
+                        stop_ptr: 22,
+                    },
+                ],
+                padding_addr: 1,
+                padding_value: Felt::from(42u64),
+                main_page_len: 2,
+                main_page: vec![
+                    PublicMemoryCell {
+                        address: 1,
+                        value: Felt::from(42u64),
+                    },
+                    PublicMemoryCell {
+                        address: 2,
+                        value: Felt::from(43u64),
+                    },
+                ],
+                n_continuous_pages: 0,
+                continuous_page_headers: vec![],
+                continuous_pages: vec![],
+            },
+            unsent_commitment: StarkUnsentCommitment {
+                traces: TracesUnsentCommitment {
+                    original: Felt::from(1u64),
+                    interaction: Felt::from(2u64),
+                },
+                composition: Felt::from(3u64),
+                oods_values: felts(100, 4),
+                fri: FriUnsentCommitment {
+                    inner_layers: felts(200, 1),
+                    last_layer_coefficients: felts(300, 4),
+                },
+                proof_of_work_nonce: Felt::from(4u64),
+            },
+            witness: StarkWitnessReordered {
+                original_leaves: felts(400, 14),
+                interaction_leaves: felts(500, 6),
+                original_authentications: felts(600, 3),
+                interaction_authentications: felts(700, 3),
+                composition_leaves: felts(800, 4),
+                composition_authentications: felts(900, 3),
+                fri_witness: FriWitness {
+                    layers: vec![FriLayerWitness {
+                        leaves: felts(1000, 30),
+                        table_witness: felts(1100, 2),
+                    }],
+                },
+            },
+            source_digest: None,
+        }
+    }
+}
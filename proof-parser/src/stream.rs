@@ -0,0 +1,287 @@
+use std::io::{self, Read};
+
+use crate::error::{Error, Result};
+
+const CHUNK_LEN: usize = 8192;
+
+/// Passes a proof JSON through, decoding the top-level `proof_hex` string into bytes as it
+/// is read and handing an empty string to the JSON parser in its place, so the payload is
+/// never held as text. Payloads that turn out not to be hex, such as base64 ones, are
+/// rebuilt and go through untouched.
+pub(crate) struct ProofHexReader<R> {
+    inner: R,
+    chunk: Vec<u8>,
+    out: Vec<u8>,
+    out_pos: usize,
+    lexer: Lexer,
+    payload: Option<HexPayload>,
+    proof_bytes: Option<Vec<u8>>,
+    error: Option<Error>,
+}
+
+#[derive(Default)]
+struct Lexer {
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    // The top-level string being read, as long as it may still be `proof_hex`.
+    string: Vec<u8>,
+    after_key: bool,
+    before_value: bool,
+}
+
+// A `proof_hex` payload being decoded. Unprefixed payloads may still turn out to be base64,
+// so the case of their digits is kept to give the text back.
+#[derive(Default)]
+struct HexPayload {
+    // Leading whitespace and the `0x` prefix.
+    prefix: Vec<u8>,
+    prefixed: bool,
+    bytes: Vec<u8>,
+    high: Option<u8>,
+    n_digits: usize,
+    upper_case: Vec<u8>,
+    trailing: Vec<u8>,
+}
+
+impl HexPayload {
+    // Takes the next byte of the payload, `Err` with the byte if it is not hex.
+    fn push(&mut self, b: u8) -> std::result::Result<(), u8> {
+        if b.is_ascii_whitespace() {
+            match self.n_digits {
+                0 => self.prefix.push(b),
+                _ => self.trailing.push(b),
+            }
+            return Ok(());
+        }
+        if !self.trailing.is_empty() {
+            return Err(b);
+        }
+        if b == b'x' && !self.prefixed && self.n_digits == 1 && self.high == Some(0) {
+            self.prefix.extend(b"0x");
+            self.prefixed = true;
+            self.high = None;
+            self.n_digits = 0;
+            return Ok(());
+        }
+
+        let digit = match b {
+            b'0'..=b'9' => b - b'0',
+            b'a'..=b'f' => b - b'a' + 10,
+            b'A'..=b'F' => b - b'A' + 10,
+            _ => return Err(b),
+        };
+        if self.n_digits.is_multiple_of(8) {
+            self.upper_case.push(0);
+        }
+        if b.is_ascii_uppercase() {
+            self.upper_case[self.n_digits / 8] |= 1 << (self.n_digits % 8);
+        }
+        self.n_digits += 1;
+        match self.high.take() {
+            Some(high) => self.bytes.push((high << 4) | digit),
+            None => self.high = Some(digit),
+        }
+        Ok(())
+    }
+
+    // The payload text read so far.
+    fn text(&self) -> Vec<u8> {
+        let mut text = self.prefix.clone();
+        text.extend((0..self.n_digits).map(|i| {
+            let nibble = match (self.bytes.get(i / 2), i % 2) {
+                (Some(byte), 0) => byte >> 4,
+                (Some(byte), _) => byte & 0xf,
+                (None, _) => self.high.unwrap_or_default(),
+            };
+            let digit = char::from_digit(nibble.into(), 16).unwrap_or('0') as u8;
+            match self.upper_case[i / 8] >> (i % 8) & 1 {
+                1 => digit.to_ascii_uppercase(),
+                _ => digit,
+            }
+        }));
+        text.extend(&self.trailing);
+        text
+    }
+}
+
+impl<R: Read> ProofHexReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            chunk: vec![0; CHUNK_LEN],
+            out: vec![],
+            out_pos: 0,
+            lexer: Lexer::default(),
+            payload: None,
+            proof_bytes: None,
+            error: None,
+        }
+    }
+
+    /// The inner reader and the decoded `proof_hex`, if it was hex.
+    pub fn finish(self) -> Result<(R, Option<Vec<u8>>)> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok((self.inner, self.proof_bytes)),
+        }
+    }
+
+    fn push(&mut self, b: u8) -> Result<()> {
+        if let Some(payload) = &mut self.payload {
+            if b != b'"' {
+                match payload.push(b) {
+                    Ok(()) => return Ok(()),
+                    Err(_) if payload.prefixed => {
+                        return Err(Error::HexDecode("invalid hex".to_string()))
+                    }
+                    Err(_) => self.pass_through(),
+                }
+            } else if payload.n_digits == 0 || (payload.high.is_some() && !payload.prefixed) {
+                self.pass_through();
+            } else if payload.high.is_some() {
+                return Err(Error::HexDecode("odd number of hex digits".to_string()));
+            } else {
+                self.proof_bytes = self.payload.take().map(|payload| payload.bytes);
+            }
+        }
+
+        self.out.push(b);
+        if self.lexer.step(b) {
+            self.payload = Some(HexPayload::default());
+        }
+        Ok(())
+    }
+
+    // Hands the payload read so far to the JSON parser, as it is not hex.
+    fn pass_through(&mut self) {
+        if let Some(payload) = self.payload.take() {
+            for b in payload.text() {
+                self.out.push(b);
+                self.lexer.step(b);
+            }
+        }
+    }
+}
+
+impl Lexer {
+    // Returns whether `b` opens the value of the top-level `proof_hex`.
+    fn step(&mut self, b: u8) -> bool {
+        if self.in_string {
+            if self.escaped {
+                self.escaped = false;
+            } else if b == b'\\' {
+                self.escaped = true;
+            } else if b == b'"' {
+                self.in_string = false;
+                self.after_key = self.depth == 1 && self.string == b"proof_hex";
+                return false;
+            }
+            if self.depth == 1 && self.string.len() <= "proof_hex".len() {
+                self.string.push(b);
+            }
+            return false;
+        }
+
+        let opens_payload = b == b'"' && self.before_value;
+        match b {
+            b'"' => {
+                self.in_string = true;
+                self.string.clear();
+            }
+            b':' if self.after_key => self.before_value = true,
+            b'{' | b'[' => self.depth += 1,
+            b'}' | b']' => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        }
+        if !b.is_ascii_whitespace() {
+            self.after_key = false;
+            self.before_value &= b == b':';
+        }
+        opens_payload
+    }
+}
+
+impl<R: Read> Read for ProofHexReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.out_pos == self.out.len() {
+            self.out.clear();
+            self.out_pos = 0;
+            let n = self.inner.read(&mut self.chunk)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            for i in 0..n {
+                if let Err(e) = self.push(self.chunk[i]) {
+                    let message = e.to_string();
+                    self.error = Some(e);
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+                }
+            }
+        }
+
+        let n = buf.len().min(self.out.len() - self.out_pos);
+        buf[..n].copy_from_slice(&self.out[self.out_pos..self.out_pos + n]);
+        self.out_pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(input: &str) -> Result<(String, Option<Vec<u8>>)> {
+        let mut reader = ProofHexReader::new(input.as_bytes());
+        let mut out = String::new();
+        let read = reader.read_to_string(&mut out);
+        let (_, bytes) = reader.finish()?;
+        read?;
+        Ok((out, bytes))
+    }
+
+    #[test]
+    fn test_decodes_top_level_proof_hex() {
+        let hex = "ab".repeat(5000);
+        let input = format!(
+            r#"{{ "a": {{ "proof_hex": "0x{hex}" }}, "b": ["proof_hex"],
+                "proof_hex" : "0x{hex}", "c": "proof_hex" }}"#
+        );
+
+        let (out, bytes) = read(&input).unwrap();
+        assert_eq!(bytes, Some(vec![0xab; 5000]));
+        assert_eq!(
+            out,
+            input.replacen(
+                &format!(r#""proof_hex" : "0x{hex}""#),
+                r#""proof_hex" : """#,
+                1
+            )
+        );
+        assert_eq!(out.matches(&hex).count(), 1);
+    }
+
+    #[test]
+    fn test_passes_other_payloads_through() {
+        let base64 = format!("{}aB+/=", "AAaA0f".repeat(20));
+        for payload in ["", " ", "0x", &base64, "0123456789abcdefg", "aBc", "ab cd"] {
+            let input = format!(r#"{{ "proof_hex": "{payload}" }}"#);
+            assert_eq!(read(&input).unwrap(), (input, None), "{payload}");
+        }
+
+        let (out, bytes) = read(r#"{ "proof_hex": " 2aFF " }"#).unwrap();
+        assert_eq!(
+            (out.as_str(), bytes),
+            (r#"{ "proof_hex": "" }"#, Some(vec![0x2a, 0xff]))
+        );
+    }
+
+    #[test]
+    fn test_invalid_hex() {
+        for payload in ["0x00g", "0x000", "0x00 0"] {
+            let input = format!(r#"{{ "proof_hex": "{payload}" }}"#);
+            let err = read(&input).unwrap_err();
+            assert!(matches!(err, Error::HexDecode(_)), "{payload}: {err}");
+        }
+    }
+}
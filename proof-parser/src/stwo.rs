@@ -0,0 +1,166 @@
+//! Experimental parsing of stwo's Circle-STARK proofs.
+//!
+//! Covers the commitment scheme proof stwo writes with serde_json, for the Blake2s Merkle
+//! channel. The felt serialization is the crate's usual one, with sequences prefixed by
+//! their length in felts: field elements are felts, secure field elements are their four
+//! M31 coordinates, and hashes are eight little-endian u32 words. The format may change
+//! with stwo releases.
+
+use serde::{Deserialize, Serialize};
+use serde_felt::to_felts;
+use starknet_types_core::felt::Felt;
+
+use crate::error::Result;
+
+/// An element of the Mersenne-31 field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct M31(pub u32);
+
+/// An element of the degree 4 extension of M31, stwo's secure field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "((M31, M31), (M31, M31))", into = "[M31; 4]")]
+pub struct QM31(pub [M31; 4]);
+
+impl From<((M31, M31), (M31, M31))> for QM31 {
+    fn from(((a, b), (c, d)): ((M31, M31), (M31, M31))) -> Self {
+        QM31([a, b, c, d])
+    }
+}
+
+impl From<QM31> for [M31; 4] {
+    fn from(value: QM31) -> Self {
+        value.0
+    }
+}
+
+/// A Blake2s digest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "[u8; 32]", into = "[u32; 8]")]
+pub struct Blake2sHash(pub [u8; 32]);
+
+impl From<[u8; 32]> for Blake2sHash {
+    fn from(bytes: [u8; 32]) -> Self {
+        Blake2sHash(bytes)
+    }
+}
+
+impl From<Blake2sHash> for [u32; 8] {
+    fn from(hash: Blake2sHash) -> Self {
+        std::array::from_fn(|i| u32::from_le_bytes(hash.0[4 * i..4 * i + 4].try_into().unwrap()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FriConfig {
+    pub log_blowup_factor: u32,
+    pub log_last_layer_degree_bound: u32,
+    pub n_queries: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PcsConfig {
+    pub pow_bits: u32,
+    pub fri_config: FriConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleDecommitment {
+    pub hash_witness: Vec<Blake2sHash>,
+    /// Dropped from the proof by later stwo versions.
+    #[serde(default)]
+    pub column_witness: Vec<M31>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FriLayerProof {
+    pub fri_witness: Vec<QM31>,
+    pub decommitment: MerkleDecommitment,
+    pub commitment: Blake2sHash,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FriProof {
+    pub first_layer: FriLayerProof,
+    pub inner_layers: Vec<FriLayerProof>,
+    /// Coefficients of the last layer polynomial.
+    pub last_layer_poly: Vec<QM31>,
+}
+
+/// stwo's `CommitmentSchemeProof`. Each outer vector has one entry per committed tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StwoProof {
+    pub config: PcsConfig,
+    pub commitments: Vec<Blake2sHash>,
+    /// Out of domain samples, per tree, column and mask offset.
+    pub sampled_values: Vec<Vec<Vec<QM31>>>,
+    pub decommitments: Vec<MerkleDecommitment>,
+    pub queried_values: Vec<Vec<M31>>,
+    pub proof_of_work: u64,
+    pub fri_proof: FriProof,
+}
+
+impl StwoProof {
+    /// Parses the JSON of a stwo proof, or of a Cairo proof wrapping one in `stark_proof`.
+    pub fn parse(input: &str) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(input)?;
+        let proof = match value.get("stark_proof") {
+            Some(stark_proof) => stark_proof.clone(),
+            None => value,
+        };
+        Ok(serde_json::from_value(proof)?)
+    }
+
+    pub fn to_felts(&self) -> Result<Vec<Felt>> {
+        Ok(to_felts(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stwo_proof() {
+        let hash = |b: u8| format!("{:?}", [b; 32]);
+        let layer = |b: u8| {
+            format!(
+                r#"{{"fri_witness": [[[1, 2], [3, 4]]], "decommitment": {{"hash_witness": [{}]}}, "commitment": {}}}"#,
+                hash(b),
+                hash(b + 1)
+            )
+        };
+        let input = format!(
+            r#"{{"claim": {{}}, "stark_proof": {{
+                "config": {{"pow_bits": 10, "fri_config": {{"log_blowup_factor": 1, "log_last_layer_degree_bound": 0, "n_queries": 3}}}},
+                "commitments": [{}, {}],
+                "sampled_values": [[[[[5, 6], [7, 8]]]], []],
+                "decommitments": [{{"hash_witness": [], "column_witness": [9]}}, {{"hash_witness": []}}],
+                "queried_values": [[10, 11], []],
+                "proof_of_work": 12,
+                "fri_proof": {{"first_layer": {}, "inner_layers": [], "last_layer_poly": [[[13, 14], [15, 16]]]}}
+            }}}}"#,
+            hash(0),
+            hash(1),
+            layer(2),
+        );
+
+        let proof = StwoProof::parse(&input).unwrap();
+        assert_eq!(proof.config.fri_config.n_queries, 3);
+        assert_eq!(
+            proof.sampled_values[0][0][0],
+            QM31([M31(5), M31(6), M31(7), M31(8)])
+        );
+        assert_eq!(proof.decommitments[0].column_witness, [M31(9)]);
+
+        let felts = proof.to_felts().unwrap();
+        // Config, then the two commitments prefixed by their length in felts.
+        assert_eq!(felts[..4], [10, 1, 0, 3].map(Felt::from));
+        assert_eq!(felts[4], Felt::from(16u64));
+        assert_eq!(felts[5], Felt::from(u32::from_le_bytes([0; 4])));
+        assert_eq!(felts[13], Felt::from(u32::from_le_bytes([1; 4])));
+        assert_eq!(felts.len(), 70);
+        // The last layer polynomial ends the serialization.
+        assert_eq!(felts[felts.len() - 4..], [13, 14, 15, 16].map(Felt::from));
+    }
+}
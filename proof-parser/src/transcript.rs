@@ -0,0 +1,80 @@
+use serde::Serialize;
+use starknet_types_core::felt::Felt;
+
+use crate::error::Result;
+
+/// A single interaction with the Fiat-Shamir channel.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TranscriptEvent {
+    /// Values sent by the prover, with the channel digest after absorbing them.
+    Absorb { values: Vec<Felt>, digest: Felt },
+    /// A random value drawn by the verifier.
+    Squeeze { value: Felt },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TranscriptLog {
+    pub events: Vec<TranscriptEvent>,
+}
+
+impl TranscriptLog {
+    pub fn absorb(&mut self, values: &[Felt], digest: Felt) {
+        self.events.push(TranscriptEvent::Absorb {
+            values: values.to_vec(),
+            digest,
+        });
+    }
+
+    pub fn squeeze(&mut self, value: Felt) {
+        self.events.push(TranscriptEvent::Squeeze { value });
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Index of the first event where the two transcripts disagree, if any.
+    pub fn first_divergence(&self, other: &TranscriptLog) -> Option<usize> {
+        let common = self.events.len().min(other.events.len());
+        (0..common)
+            .find(|&i| self.events[i] != other.events[i])
+            .or((self.events.len() != other.events.len()).then_some(common))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transcript_json() {
+        let mut log = TranscriptLog::default();
+        log.absorb(&[Felt::from(1u64)], Felt::from(2u64));
+        log.squeeze(Felt::from(3u64));
+
+        let json: serde_json::Value = serde_json::from_str(&log.to_json().unwrap()).unwrap();
+        assert_eq!(json["events"][0]["kind"], "absorb");
+        assert_eq!(json["events"][0]["digest"], "0x2");
+        assert_eq!(json["events"][1]["kind"], "squeeze");
+        assert_eq!(json["events"][1]["value"], "0x3");
+    }
+
+    #[test]
+    fn test_first_divergence() {
+        let mut a = TranscriptLog::default();
+        a.squeeze(Felt::from(1u64));
+        a.squeeze(Felt::from(2u64));
+        let mut b = a.clone();
+
+        assert_eq!(a.first_divergence(&b), None);
+        b.squeeze(Felt::from(3u64));
+        assert_eq!(a.first_divergence(&b), Some(2));
+        a.squeeze(Felt::from(4u64));
+        assert_eq!(a.first_divergence(&b), Some(2));
+        b.events[0] = TranscriptEvent::Squeeze {
+            value: Felt::from(0u64),
+        };
+        assert_eq!(a.first_divergence(&b), Some(0));
+    }
+}
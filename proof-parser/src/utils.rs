@@ -1,3 +1,9 @@
+use std::{fs, io::Read, path::Path};
+
+use flate2::read::GzDecoder;
+
+use crate::error::{Error, Result};
+
 #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
 pub fn log2_if_power_of_2(x: u32) -> Option<u32> {
     if x != 0 && (x & (x - 1)) == 0 {
@@ -7,6 +13,23 @@ pub fn log2_if_power_of_2(x: u32) -> Option<u32> {
     }
 }
 
+/// Reads a file, decompressing it when its extension is `.gz`.
+pub(crate) fn read_file(path: &Path) -> Result<Vec<u8>> {
+    let bytes = fs::read(path)?;
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("gz") => {
+            let mut decompressed = vec![];
+            GzDecoder::new(bytes.as_slice()).read_to_end(&mut decompressed)?;
+            Ok(decompressed)
+        }
+        Some("zst") => Err(Error::InvalidParameter(format!(
+            "{}: zstd compressed proofs are not supported, decompress them with `zstd -d`",
+            path.display()
+        ))),
+        _ => Ok(bytes),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -38,4 +61,21 @@ mod tests {
         assert_eq!(log2_if_power_of_2(1024), Some(10));
         assert_eq!(log2_if_power_of_2(1 << 15), Some(15));
     }
+
+    #[test]
+    fn test_read_gzip_file() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join(format!("read-file-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(b"{}").unwrap();
+        fs::write(dir.join("proof.json.gz"), encoder.finish().unwrap()).unwrap();
+        fs::write(dir.join("proof.json.zst"), b"").unwrap();
+
+        assert_eq!(read_file(&dir.join("proof.json.gz")).unwrap(), b"{}");
+        assert!(read_file(&dir.join("proof.json.zst")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,258 @@
+use std::fmt::Display;
+
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    error::Result,
+    json_parser::{ProofJSON, PublicInput},
+    pagination::HEADER_LEN,
+    stark_proof::{CairoPublicInput, PublicMemoryCell, StarkProof},
+};
+
+/// Everything wrong with a public input, see [`CairoPublicInput::validate`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    RangeCheckBounds {
+        min: u32,
+        max: u32,
+    },
+    /// `n_steps` does not fit the `u32` Stone reads it into.
+    StepsOutOfRange {
+        log_n_steps: u32,
+    },
+    SegmentReversed {
+        index: usize,
+        begin_addr: u32,
+        stop_ptr: u32,
+    },
+    /// Segment `index` starts before the previous one stops.
+    SegmentsOverlap {
+        index: usize,
+    },
+    PaddingMissing {
+        address: u32,
+    },
+    LengthMismatch {
+        field: &'static str,
+        expected: usize,
+        got: usize,
+    },
+    /// The proof disagrees with the AIR public input of the run it claims to prove.
+    AirPublicInputMismatch {
+        field: &'static str,
+    },
+}
+
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationIssue::RangeCheckBounds { min, max } => {
+                write!(f, "range check min {min} above max {max}")
+            }
+            ValidationIssue::StepsOutOfRange { log_n_steps } => {
+                write!(f, "2^{log_n_steps} steps do not fit in a u32")
+            }
+            ValidationIssue::SegmentReversed {
+                index,
+                begin_addr,
+                stop_ptr,
+            } => write!(
+                f,
+                "segment {index} stops at {stop_ptr} before its start {begin_addr}"
+            ),
+            ValidationIssue::SegmentsOverlap { index } => {
+                write!(f, "segment {index} overlaps the previous segment")
+            }
+            ValidationIssue::PaddingMissing { address } => {
+                write!(f, "padding cell at address {address} not in the main page")
+            }
+            ValidationIssue::LengthMismatch {
+                field,
+                expected,
+                got,
+            } => write!(f, "{field} is {got}, expected {expected}"),
+            ValidationIssue::AirPublicInputMismatch { field } => {
+                write!(f, "{field} differs from the AIR public input")
+            }
+        }
+    }
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl CairoPublicInput<Felt> {
+    /// Checks the public input as a whole instead of stopping at the first problem.
+    pub fn validate(&self) -> ValidationReport {
+        let mut issues = vec![];
+
+        if self.range_check_min > self.range_check_max {
+            issues.push(ValidationIssue::RangeCheckBounds {
+                min: self.range_check_min,
+                max: self.range_check_max,
+            });
+        }
+        if self.log_n_steps >= u32::BITS {
+            issues.push(ValidationIssue::StepsOutOfRange {
+                log_n_steps: self.log_n_steps,
+            });
+        }
+
+        for (index, segment) in self.segments.iter().enumerate() {
+            if segment.stop_ptr < segment.begin_addr {
+                issues.push(ValidationIssue::SegmentReversed {
+                    index,
+                    begin_addr: segment.begin_addr,
+                    stop_ptr: segment.stop_ptr,
+                });
+            }
+        }
+        for (index, pair) in self.segments.windows(2).enumerate() {
+            if pair[1].begin_addr < pair[0].stop_ptr {
+                issues.push(ValidationIssue::SegmentsOverlap { index: index + 1 });
+            }
+        }
+
+        if !self
+            .main_page
+            .iter()
+            .any(|cell| cell.address == self.padding_addr && cell.value == self.padding_value)
+        {
+            issues.push(ValidationIssue::PaddingMissing {
+                address: self.padding_addr,
+            });
+        }
+
+        let mut expect_len = |field, expected, got| {
+            if expected != got {
+                issues.push(ValidationIssue::LengthMismatch {
+                    field,
+                    expected,
+                    got,
+                });
+            }
+        };
+        expect_len("n_segments", self.segments.len(), self.n_segments);
+        expect_len("main_page_len", self.main_page.len(), self.main_page_len);
+        expect_len(
+            "continuous page headers",
+            self.n_continuous_pages * HEADER_LEN,
+            self.continuous_page_headers.len(),
+        );
+
+        ValidationReport { issues }
+    }
+}
+
+/// Checks that `proof` proves the run cairo-vm described in `air_public_input`, the
+/// contents of its `air_public_input.json`, to catch mixed up trace and proof files.
+pub fn validate_against_air_public_input(
+    proof: &StarkProof,
+    air_public_input: &str,
+) -> Result<ValidationReport> {
+    let air_public_input: PublicInput = serde_json::from_str(air_public_input)?;
+    let expected = ProofJSON::public_input(air_public_input)?;
+    let public_input = &proof.public_input;
+
+    // Pagination only moves cells between pages, so compare the memory as a whole.
+    let memory = |public_input: &CairoPublicInput<Felt>| {
+        let mut cells = public_input
+            .main_page
+            .iter()
+            .chain(public_input.continuous_pages.iter().flatten())
+            .map(|PublicMemoryCell { address, value }| (*address, *value))
+            .collect::<Vec<_>>();
+        cells.sort();
+        cells
+    };
+
+    let mut issues = vec![];
+    for (field, matches) in [
+        ("layout", public_input.layout == expected.layout),
+        ("n_steps", public_input.log_n_steps == expected.log_n_steps),
+        ("segments", public_input.segments == expected.segments),
+        (
+            "range check bounds",
+            (public_input.range_check_min, public_input.range_check_max)
+                == (expected.range_check_min, expected.range_check_max),
+        ),
+        ("public memory", memory(public_input) == memory(&expected)),
+    ] {
+        if !matches {
+            issues.push(ValidationIssue::AirPublicInputMismatch { field });
+        }
+    }
+
+    Ok(ValidationReport { issues })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let mut public_input = StarkProof::sample().public_input;
+        assert!(public_input.validate().is_valid());
+
+        public_input.range_check_min = 200;
+        public_input.segments[1].begin_addr = 4;
+        public_input.segments[2].stop_ptr = 19;
+        public_input.padding_value = Felt::ZERO;
+        public_input.main_page_len = 3;
+
+        assert_eq!(
+            public_input.validate().issues,
+            [
+                ValidationIssue::RangeCheckBounds { min: 200, max: 100 },
+                ValidationIssue::SegmentReversed {
+                    index: 2,
+                    begin_addr: 20,
+                    stop_ptr: 19
+                },
+                ValidationIssue::SegmentsOverlap { index: 1 },
+                ValidationIssue::PaddingMissing { address: 1 },
+                ValidationIssue::LengthMismatch {
+                    field: "main_page_len",
+                    expected: 2,
+                    got: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_against_air_public_input() {
+        let mut proof = StarkProof::sample();
+        let air_public_input =
+            serde_json::to_string(&ProofJSON::try_from(&proof).unwrap().public_input).unwrap();
+        let report = validate_against_air_public_input(&proof, &air_public_input).unwrap();
+        assert!(report.is_valid(), "{:?}", report.issues);
+
+        proof.public_input.range_check_max += 1;
+        proof.public_input.main_page[1].value = Felt::ZERO;
+        assert_eq!(
+            validate_against_air_public_input(&proof, &air_public_input)
+                .unwrap()
+                .issues,
+            [
+                ValidationIssue::AirPublicInputMismatch {
+                    field: "range check bounds"
+                },
+                ValidationIssue::AirPublicInputMismatch {
+                    field: "public memory"
+                },
+            ]
+        );
+
+        assert!(validate_against_air_public_input(&proof, "{}").is_err());
+    }
+}
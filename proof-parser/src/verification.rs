@@ -0,0 +1,318 @@
+use std::{collections::VecDeque, fmt::Display};
+
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    error::{Error, Result},
+    hashes::HashFunction,
+    stark_proof::{StarkProof, TableCommitmentConfig},
+};
+
+/// A Merkle committed table of the proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Table {
+    Original,
+    Interaction,
+    Composition,
+}
+
+impl Display for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Table::Original => write!(f, "original trace"),
+            Table::Interaction => write!(f, "interaction trace"),
+            Table::Composition => write!(f, "composition"),
+        }
+    }
+}
+
+// The verifier friendly layers are hashed with Poseidon, the ones below with `commitment`.
+fn layer_hash(commitment: HashFunction, n_verifier_friendly: u32, depth: u32) -> HashFunction {
+    if n_verifier_friendly >= depth {
+        HashFunction::Poseidon3
+    } else {
+        commitment
+    }
+}
+
+fn leaf_hash(row: &[Felt], hash: HashFunction) -> Result<Felt> {
+    match row {
+        [value] => Ok(*value),
+        _ => hash.hash_row(row),
+    }
+}
+
+/// What [`StarkProof::verify_decommitments`] checked against the commitments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decommitments {
+    /// Distinct queries opened in each of the trace and composition tables.
+    pub n_queries: usize,
+    /// FRI layers whose table witnesses were not checked: their rows include the values the
+    /// FRI folding computes from the OODS evaluations, so their roots cannot be recomputed
+    /// from the proof alone.
+    pub unchecked_fri_layers: usize,
+}
+
+/// Recomputes the root of a table from the rows at `queries` and the authentication path,
+/// the same way Integrity's `table_decommit` does.
+fn table_root(
+    config: &TableCommitmentConfig,
+    commitment: HashFunction,
+    queries: &[u64],
+    leaves: &[Felt],
+    authentications: &[Felt],
+) -> Result<Felt> {
+    let height = config.vector.height;
+    let n_verifier_friendly = config.vector.n_verifier_friendly_commitment_layers;
+    let n_columns = config.n_columns as usize;
+    if leaves.len() != queries.len() * n_columns {
+        return Err(Error::LengthMismatch {
+            section: "leaves".to_string(),
+            expected: queries.len() * n_columns,
+            got: leaves.len(),
+            probable_cause: None,
+        });
+    }
+
+    let hash = |depth| layer_hash(commitment, n_verifier_friendly, depth);
+    let mut queue = queries
+        .iter()
+        .zip(leaves.chunks_exact(n_columns))
+        .map(|(query, row)| {
+            let node = (1u64 << height) + query;
+            Ok((node, height, leaf_hash(row, hash(height))?))
+        })
+        .collect::<Result<VecDeque<_>>>()?;
+    let mut authentications = authentications.iter();
+    let mut next_authentication = || {
+        authentications
+            .next()
+            .copied()
+            .ok_or_else(|| Error::Verification("authentication path too short".to_string()))
+    };
+
+    while let Some((node, depth, value)) = queue.pop_front() {
+        if node == 1 {
+            return Ok(value);
+        }
+
+        let hash = hash(depth);
+        let parent = if node % 2 == 1 {
+            hash.hash_nodes(next_authentication()?, value)?
+        } else if queue.front().is_some_and(|(next, _, _)| *next == node + 1) {
+            let (_, _, sibling) = queue.pop_front().unwrap();
+            hash.hash_nodes(value, sibling)?
+        } else {
+            hash.hash_nodes(value, next_authentication()?)?
+        };
+        queue.push_back((node / 2, depth - 1, parent));
+    }
+
+    Err(Error::Verification("no queries to decommit".to_string()))
+}
+
+impl StarkProof {
+    /// Checks that the proof is internally consistent, catching corrupted proofs before they
+    /// are sent on-chain. This is not a verifier: it only runs the checks of Integrity's
+    /// verifier that do not depend on the layout's constraints, namely the config, the proof
+    /// of work and the trace and composition decommitments. The OODS consistency check, the
+    /// FRI folding and the FRI layer decommitments need the layout's composition polynomial
+    /// and are not run, so a consistent proof may still be invalid.
+    pub fn check_consistency(&self) -> Result<Decommitments> {
+        self.check_config()?;
+        self.verify_pow()?;
+        self.verify_decommitments()
+    }
+
+    fn check_config(&self) -> Result<()> {
+        let config = &self.config;
+        let fri = &config.fri;
+        let commitment = &self.unsent_commitment.fri;
+        let fail = |msg: String| Err(Error::Verification(msg));
+
+        if fri.log_input_size != config.log_trace_domain_size + config.log_n_cosets {
+            return fail(format!(
+                "fri input size 2^{} is not the evaluation domain size 2^{}",
+                fri.log_input_size,
+                config.log_trace_domain_size + config.log_n_cosets
+            ));
+        }
+        if fri.fri_step_sizes.first() != Some(&0) {
+            return fail("the first fri step must be 0".to_string());
+        }
+        if fri.n_layers as usize != fri.fri_step_sizes.len()
+            || commitment.inner_layers.len() != fri.inner_layers.len()
+            || fri.inner_layers.len() + 1 != fri.fri_step_sizes.len()
+        {
+            return fail(format!(
+                "{} fri layers with {} steps and {} commitments",
+                fri.n_layers,
+                fri.fri_step_sizes.len(),
+                commitment.inner_layers.len()
+            ));
+        }
+        let degree_bound = 1usize << fri.log_last_layer_degree_bound;
+        if commitment.last_layer_coefficients.len() != degree_bound {
+            return fail(format!(
+                "{} last layer coefficients for degree bound {degree_bound}",
+                commitment.last_layer_coefficients.len()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks the trace and composition witnesses against their commitments, reporting
+    /// the first table whose Merkle root does not match. Layers below the verifier friendly
+    /// ones are hashed with the proof's commitment hash. The FRI layer witnesses are not
+    /// checked, see [`Decommitments::unchecked_fri_layers`].
+    pub fn verify_decommitments(&self) -> Result<Decommitments> {
+        let queries = self.query_indices()?;
+        let commitment_hash = self.config.hashes.commitment;
+        let witness = &self.witness;
+        let commitment = &self.unsent_commitment;
+
+        let tables = [
+            (
+                Table::Original,
+                &self.config.traces.original,
+                &witness.original_leaves,
+                &witness.original_authentications,
+                commitment.traces.original,
+            ),
+            (
+                Table::Interaction,
+                &self.config.traces.interaction,
+                &witness.interaction_leaves,
+                &witness.interaction_authentications,
+                commitment.traces.interaction,
+            ),
+            (
+                Table::Composition,
+                &self.config.composition,
+                &witness.composition_leaves,
+                &witness.composition_authentications,
+                commitment.composition,
+            ),
+        ];
+
+        for (table, config, leaves, authentications, expected) in tables {
+            let root = table_root(config, commitment_hash, &queries, leaves, authentications)
+                .map_err(|e| Error::Verification(format!("{table} table: {e}")))?;
+            if root != expected {
+                return Err(Error::Verification(format!(
+                    "{table} table: root {root:#x} does not match commitment {expected:#x}"
+                )));
+            }
+        }
+
+        Ok(Decommitments {
+            n_queries: queries.len(),
+            unchecked_fri_layers: self.witness.fri_witness.layers.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    // Builds the full tree over `rows` and returns its root and the proof witness for `queries`.
+    fn commit(
+        config: &TableCommitmentConfig,
+        commitment: HashFunction,
+        rows: &[Vec<Felt>],
+        queries: &[u64],
+    ) -> (Felt, Vec<Felt>, Vec<Felt>) {
+        let height = config.vector.height;
+        let n_verifier_friendly = config.vector.n_verifier_friendly_commitment_layers;
+        let hash = |depth| layer_hash(commitment, n_verifier_friendly, depth);
+        let mut layer = rows
+            .iter()
+            .map(|row| leaf_hash(row, hash(height)).unwrap())
+            .collect::<Vec<_>>();
+        let mut known = queries.iter().map(|q| *q as usize).collect::<Vec<_>>();
+        let mut authentications = vec![];
+
+        for depth in (1..=height).rev() {
+            for &i in &known {
+                if !known.contains(&(i ^ 1)) {
+                    authentications.push(layer[i ^ 1]);
+                }
+            }
+            known = known.iter().map(|i| i / 2).collect();
+            known.dedup();
+            layer = layer
+                .chunks_exact(2)
+                .map(|pair| hash(depth).hash_nodes(pair[0], pair[1]).unwrap())
+                .collect();
+        }
+
+        let leaves = queries
+            .iter()
+            .flat_map(|q| rows[*q as usize].clone())
+            .collect();
+        (layer[0], leaves, authentications)
+    }
+
+    #[test]
+    fn test_table_root() {
+        let commitments = [
+            HashFunction::Keccak256Masked160Lsb,
+            HashFunction::Blake256Masked248Lsb,
+        ];
+        for (n_verifier_friendly, commitment) in
+            [0, 2, 4].into_iter().cartesian_product(commitments)
+        {
+            let config = TableCommitmentConfig {
+                n_columns: 2,
+                vector: crate::stark_proof::VectorCommitmentConfig {
+                    height: 4,
+                    n_verifier_friendly_commitment_layers: n_verifier_friendly,
+                },
+            };
+            let rows = (0..16u64)
+                .map(|i| vec![Felt::from(i), Felt::from(i * i)])
+                .collect::<Vec<_>>();
+            let queries = [2, 3, 9];
+
+            let (root, leaves, authentications) = commit(&config, commitment, &rows, &queries);
+            assert_eq!(
+                table_root(&config, commitment, &queries, &leaves, &authentications).unwrap(),
+                root
+            );
+
+            let mut tampered = authentications.clone();
+            tampered[0] += Felt::ONE;
+            assert_ne!(
+                table_root(&config, commitment, &queries, &leaves, &tampered).unwrap(),
+                root
+            );
+        }
+
+        let config = TableCommitmentConfig {
+            n_columns: 2,
+            vector: crate::stark_proof::VectorCommitmentConfig {
+                height: 4,
+                n_verifier_friendly_commitment_layers: 0,
+            },
+        };
+        let leaves = vec![Felt::ONE; 2];
+        assert!(table_root(&config, HashFunction::Pedersen, &[0], &leaves, &[]).is_err());
+    }
+
+    #[test]
+    fn test_check_consistency_checks_config() {
+        let mut proof = StarkProof::sample();
+        proof.unsent_commitment.fri.last_layer_coefficients.pop();
+
+        let err = proof.check_consistency().unwrap_err();
+        assert!(err.to_string().contains("last layer coefficients"), "{err}");
+    }
+
+    #[test]
+    fn test_verify_decommitments_reports_table() {
+        let err = StarkProof::sample().verify_decommitments().unwrap_err();
+        assert!(err.to_string().contains("original trace table"), "{err}");
+    }
+}
@@ -0,0 +1,90 @@
+//! `wasm-bindgen` bindings, felts cross the boundary as hex strings.
+
+use starknet_types_core::felt::Felt;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    output,
+    program::{self, CairoVersion},
+};
+
+fn to_hex(felts: &[Felt]) -> Vec<String> {
+    felts.iter().map(|felt| format!("{felt:#x}")).collect()
+}
+
+/// The proof JSON parsed into the verifier's `StarkProof`, as JSON.
+#[wasm_bindgen]
+pub fn parse(input: &str) -> Result<String, JsError> {
+    Ok(serde_json::to_string(&crate::parse(input)?)?)
+}
+
+/// The verifier calldata of a proof JSON.
+#[wasm_bindgen(js_name = toFelts)]
+pub fn to_felts(input: &str) -> Result<Vec<String>, JsError> {
+    let proof = crate::parse(input)?;
+    Ok(to_hex(
+        &proof.to_felts().map_err(|e| JsError::new(&e.to_string()))?,
+    ))
+}
+
+#[wasm_bindgen]
+pub struct ExtractedOutput {
+    output: Vec<String>,
+    output_hash: String,
+}
+
+#[wasm_bindgen]
+impl ExtractedOutput {
+    #[wasm_bindgen(getter)]
+    pub fn output(&self) -> Vec<String> {
+        self.output.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = outputHash)]
+    pub fn output_hash(&self) -> String {
+        self.output_hash.clone()
+    }
+}
+
+#[wasm_bindgen(js_name = extractOutput)]
+pub fn extract_output(input: &str) -> Result<ExtractedOutput, JsError> {
+    let result = output::extract_output(input)?;
+    Ok(ExtractedOutput {
+        output: to_hex(&result.program_output),
+        output_hash: format!("{:#x}", result.program_output_hash),
+    })
+}
+
+#[wasm_bindgen]
+pub struct ExtractedProgram {
+    program: Vec<String>,
+    program_hash: String,
+}
+
+#[wasm_bindgen]
+impl ExtractedProgram {
+    #[wasm_bindgen(getter)]
+    pub fn program(&self) -> Vec<String> {
+        self.program.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = programHash)]
+    pub fn program_hash(&self) -> String {
+        self.program_hash.clone()
+    }
+}
+
+/// The proven program, `cairo1` selecting Cairo 1 over Cairo 0 entry code checks.
+#[wasm_bindgen(js_name = extractProgram)]
+pub fn extract_program(input: &str, cairo1: bool) -> Result<ExtractedProgram, JsError> {
+    let version = if cairo1 {
+        CairoVersion::Cairo1
+    } else {
+        CairoVersion::Cairo0
+    };
+    let result = program::extract_program(&crate::parse(input)?, version)?;
+    Ok(ExtractedProgram {
+        program: to_hex(&result.program),
+        program_hash: format!("{:#x}", result.program_hash),
+    })
+}
@@ -0,0 +1,198 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Expr, Fields, Lifetime, LitStr, Type,
+};
+
+/// How a field is laid out in the felts.
+struct FieldAttrs {
+    // Expression on the context giving the number of felts of a `Vec<Felt>` field.
+    len_from: Option<Expr>,
+    montgomery: bool,
+}
+
+fn field_attrs(field: &syn::Field) -> syn::Result<FieldAttrs> {
+    let mut attrs = FieldAttrs {
+        len_from: None,
+        montgomery: false,
+    };
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("felt")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("len_from") {
+                let path: LitStr = meta.value()?.parse()?;
+                attrs.len_from = Some(syn::parse_str(&format!("ctx.{}", path.value()))?);
+                Ok(())
+            } else if meta.path.is_ident("montgomery") {
+                attrs.montgomery = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported felt attribute"))
+            }
+        })?;
+    }
+    if attrs.montgomery && attrs.len_from.is_none() {
+        return Err(syn::Error::new_spanned(
+            field,
+            "`montgomery` requires `len_from`",
+        ));
+    }
+    Ok(attrs)
+}
+
+fn context(input: &DeriveInput) -> syn::Result<Option<Type>> {
+    let mut context = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("felt")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("context") {
+                let ty: LitStr = meta.value()?.parse()?;
+                context = Some(ty.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported felt attribute"))
+            }
+        })?;
+    }
+    Ok(context)
+}
+
+fn named_fields(input: &DeriveInput) -> syn::Result<Vec<(syn::Field, FieldAttrs)>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(input, "only structs are supported"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            input,
+            "only structs with named fields are supported",
+        ));
+    };
+    fields
+        .named
+        .iter()
+        .map(|f| Ok((f.clone(), field_attrs(f)?)))
+        .collect()
+}
+
+/// Derives `serde_felt::DeserializeFelt`, reading fields in declaration order.
+///
+/// `#[felt(context = "Type")]` on the struct names the context the lengths are read from,
+/// otherwise the impl is generic over it. On fields, `#[felt(len_from = "path")]` reads
+/// `ctx.path` felts into a `Vec<Felt>`, or borrows them into a `&[Felt]` or `Cow<[Felt]>`
+/// tied to the struct's first lifetime, and `#[felt(montgomery)]` converts them out of
+/// Montgomery form. Other fields are decoded with their own `DeserializeFelt` impl.
+#[proc_macro_derive(DeserializeFelt, attributes(felt))]
+pub fn derive_deserialize_felt(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_deserialize(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_deserialize(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let context = context(input)?;
+    let fields = named_fields(input)?;
+
+    // The first lifetime of the struct, if any, is the one borrowed from the reader.
+    let mut generics = input.generics.clone();
+    let de: Lifetime = match input.generics.lifetimes().next() {
+        Some(param) => param.lifetime.clone(),
+        None => {
+            let de: Lifetime = parse_quote!('__de);
+            generics.params.insert(0, parse_quote!(#de));
+            de
+        }
+    };
+    let ctx_ty = match context {
+        Some(context) => quote! { #context },
+        None => {
+            generics.params.push(parse_quote!(__C: ?Sized));
+            quote! { __C }
+        }
+    };
+
+    let mut reads = vec![];
+    for (field, attrs) in &fields {
+        let ident = field.ident.as_ref().unwrap();
+        let label = ident.to_string();
+        reads.push(match (&attrs.len_from, attrs.montgomery) {
+            (Some(len), false) => quote! {
+                #ident: ::serde_felt::FromFeltSlice::from_felt_slice(reader.take(#len, #label)?)
+            },
+            (Some(len), true) => quote! {
+                #ident: reader
+                    .take(#len, #label)?
+                    .iter()
+                    .copied()
+                    .map(::serde_felt::montgomery_to_felt)
+                    .collect()
+            },
+            (None, _) => {
+                let ty = &field.ty;
+                generics
+                    .make_where_clause()
+                    .predicates
+                    .push(parse_quote!(#ty: ::serde_felt::DeserializeFelt<#de, #ctx_ty>));
+                quote! {
+                    #ident: ::serde_felt::DeserializeFelt::deserialize_felt(reader, ctx)?
+                }
+            }
+        });
+    }
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::serde_felt::DeserializeFelt<#de, #ctx_ty> for #name #ty_generics
+        #where_clause
+        {
+            fn deserialize_felt(
+                reader: &mut ::serde_felt::FeltReader<#de>,
+                ctx: &#ctx_ty,
+            ) -> ::serde_felt::Result<Self> {
+                Ok(Self { #(#reads,)* })
+            }
+        }
+    })
+}
+
+/// Derives `serde_felt::SerializeFelt`, the inverse of `DeserializeFelt`: fields are
+/// written in declaration order without length prefixes.
+#[proc_macro_derive(SerializeFelt, attributes(felt))]
+pub fn derive_serialize_felt(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_serialize(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_serialize(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = named_fields(input)?;
+
+    let writes = fields.iter().map(|(field, attrs)| {
+        let ident = field.ident.as_ref().unwrap();
+        match (&attrs.len_from, attrs.montgomery) {
+            (Some(_), false) => quote! {
+                out.extend_from_slice(&self.#ident)
+            },
+            (Some(_), true) => quote! {
+                out.extend(self.#ident.iter().copied().map(::serde_felt::felt_to_montgomery))
+            },
+            (None, _) => quote! {
+                ::serde_felt::SerializeFelt::serialize_felt(&self.#ident, out)
+            },
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::serde_felt::SerializeFelt for #name #ty_generics #where_clause {
+            fn serialize_felt(&self, out: &mut ::std::vec::Vec<::serde_felt::__private::Felt>) {
+                #(#writes;)*
+            }
+        }
+    })
+}
@@ -0,0 +1,56 @@
+//! `#[serde(with = "serde_felt::byte_array")]` for `String` and `Vec<u8>` fields, encoded as a
+//! Cairo `ByteArray` instead of a felt or a sequence of felts.
+
+use serde::{de, Deserializer, Serializer};
+
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: AsRef<[u8]>,
+    S: Serializer,
+{
+    serializer.serialize_bytes(value.as_ref())
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromBytes,
+    D: Deserializer<'de>,
+{
+    struct BytesVisitor;
+
+    impl<'de> de::Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a byte array")
+        }
+
+        fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+            Ok(v)
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+            Ok(v.to_vec())
+        }
+    }
+
+    let bytes = deserializer.deserialize_byte_buf(BytesVisitor)?;
+    T::from_bytes(bytes).map_err(de::Error::custom)
+}
+
+/// Types that can be rebuilt from the bytes of a `ByteArray`.
+pub trait FromBytes: Sized {
+    fn from_bytes(bytes: Vec<u8>) -> Result<Self, String>;
+}
+
+impl FromBytes for Vec<u8> {
+    fn from_bytes(bytes: Vec<u8>) -> Result<Self, String> {
+        Ok(bytes)
+    }
+}
+
+impl FromBytes for String {
+    fn from_bytes(bytes: Vec<u8>) -> Result<Self, String> {
+        String::from_utf8(bytes).map_err(|e| e.to_string())
+    }
+}
@@ -4,14 +4,44 @@ use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, V
 use serde::Deserialize;
 use starknet_types_core::felt::Felt;
 
-use super::error::{Error, Result};
+use super::{
+    error::{Error, Result},
+    montgomery::montgomery_to_felt,
+    ser::{SerializerOptions, BYTES_PER_WORD},
+    skip,
+};
 
 pub type Lengths = HashMap<String, Vec<usize>>;
 
+/// What to do with felts left after the decoded value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrailingFelts {
+    /// Fail with `Error::DataLeft`.
+    Error,
+    /// Drop them.
+    #[default]
+    Ignore,
+    /// Hand them back with the value.
+    Return,
+}
+
 pub struct Deserializer<'de> {
     input: &'de [Felt],
     lengths: Option<Lengths>, // Workaround around serde limit to 32 element tuples.
     next_length: Option<usize>,
+    montgomery_fields: &'static [&'static str],
+    // Inside one of `montgomery_fields`.
+    montgomery: bool,
+    // Where we are, for error messages: the index of the last felt taken and the fields
+    // and sequence elements being decoded.
+    len: usize,
+    position: usize,
+    path: Vec<PathSegment>,
+}
+
+enum PathSegment {
+    Field(&'static str),
+    Index(usize),
 }
 
 impl<'de> Deserializer<'de> {
@@ -20,25 +50,84 @@ impl<'de> Deserializer<'de> {
     }
 
     pub fn take(&mut self) -> Result<Felt> {
+        self.position = self.len - self.input.len();
         let el = self.peek()?;
         self.input = &self.input[1..];
 
         Ok(el)
     }
 
+    // Takes a felt that must fit in `T`. The value is read from the big-endian bytes, which
+    // is much cheaper than going through its decimal string.
+    fn take_uint<T: TryFrom<u128>>(&mut self) -> Result<Option<T>> {
+        let bytes = self.take()?.to_bytes_be();
+        let (high, low) = bytes.split_at(16);
+        if high.iter().any(|b| *b != 0) {
+            return Ok(None);
+        }
+        let value = u128::from_be_bytes(low.try_into().unwrap());
+        Ok(T::try_from(value).ok())
+    }
+
+    // Takes a felt that must fit in the signed `T`. Negative values are encoded as their
+    // additive inverse in the field, like Cairo's signed integers.
+    fn take_int<T: TryFrom<i128>>(&mut self) -> Result<T> {
+        let felt = self.take()?;
+        let fits = |felt: Felt| {
+            let bytes = felt.to_bytes_be();
+            let (high, low) = bytes.split_at(16);
+            let low = u128::from_be_bytes(low.try_into().unwrap());
+            (high.iter().all(|b| *b == 0))
+                .then_some(low)
+                .and_then(|v| i128::try_from(v).ok())
+        };
+        let value = match fits(felt) {
+            Some(value) => Some(value),
+            None => fits(-felt).map(|v| -v),
+        };
+        value
+            .and_then(|v| T::try_from(v).ok())
+            .ok_or(Error::ValueExceededRange)
+    }
+
     pub fn from_felts(input: &'de Vec<Felt>) -> Self {
         Deserializer {
             input,
             lengths: None,
             next_length: None,
+            montgomery_fields: &[],
+            montgomery: false,
+            len: input.len(),
+            position: 0,
+            path: vec![],
         }
     }
 
     pub fn from_felts_with_lengths(input: &'de Vec<Felt>, lengths: Lengths) -> Self {
         Deserializer {
-            input,
             lengths: Some(lengths),
-            next_length: None,
+            ..Self::from_felts(input)
+        }
+    }
+
+    // Attaches the current position to an error.
+    fn locate(&self, error: Error) -> Error {
+        let mut path = String::new();
+        for segment in &self.path {
+            match segment {
+                PathSegment::Field(field) if path.is_empty() => path.push_str(field),
+                PathSegment::Field(field) => {
+                    path.push('.');
+                    path.push_str(field);
+                }
+                PathSegment::Index(i) => path.push_str(&format!("[{i}]")),
+            }
+        }
+
+        Error::At {
+            position: self.position,
+            path,
+            error: Box::new(error),
         }
     }
 
@@ -71,17 +160,65 @@ pub fn from_felts<'a, T>(s: &'a Vec<Felt>) -> Result<T>
 where
     T: Deserialize<'a>,
 {
-    from_felts_inner(s, None)
+    from_felts_inner(s, None, TrailingFelts::Ignore, SerializerOptions::default()).map(|(t, _)| t)
+}
+
+/// Like `from_felts`, but also returns the felts left after `T`, for inputs holding several
+/// values back to back.
+pub fn from_felts_with_remainder<'a, T>(s: &'a Vec<Felt>) -> Result<(T, &'a [Felt])>
+where
+    T: Deserialize<'a>,
+{
+    from_felts_with_trailing(s, TrailingFelts::Return)
+}
+
+/// Decodes `T`, handling the felts after it as `trailing` says. The returned slice is empty
+/// unless `trailing` is `TrailingFelts::Return`.
+pub fn from_felts_with_trailing<'a, T>(
+    s: &'a Vec<Felt>,
+    trailing: TrailingFelts,
+) -> Result<(T, &'a [Felt])>
+where
+    T: Deserialize<'a>,
+{
+    from_felts_inner(s, None, trailing, SerializerOptions::default())
+}
+
+/// Decodes felts written by `to_felts_with_options` with the same `options`.
+pub fn from_felts_with_options<'a, T>(s: &'a Vec<Felt>, options: SerializerOptions) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    from_felts_inner(s, None, TrailingFelts::Ignore, options).map(|(t, _)| t)
+}
+
+/// Like `from_felts`, but fails with `Error::DataLeft` if any felt is left unconsumed.
+pub fn from_felts_strict<'a, T>(s: &'a Vec<Felt>) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    from_felts_inner(s, None, TrailingFelts::Error, SerializerOptions::default()).map(|(t, _)| t)
 }
 
 pub fn from_felts_with_lengths<'a, T>(s: &'a Vec<Felt>, lengths: Lengths) -> Result<T>
 where
     T: Deserialize<'a>,
 {
-    from_felts_inner(s, Some(lengths))
+    from_felts_inner(
+        s,
+        Some(lengths),
+        TrailingFelts::Ignore,
+        SerializerOptions::default(),
+    )
+    .map(|(t, _)| t)
 }
 
-fn from_felts_inner<'a, T>(s: &'a Vec<Felt>, lengths: Option<Lengths>) -> Result<T>
+fn from_felts_inner<'a, T>(
+    s: &'a Vec<Felt>,
+    lengths: Option<Lengths>,
+    trailing: TrailingFelts,
+    options: SerializerOptions,
+) -> Result<(T, &'a [Felt])>
 where
     T: Deserialize<'a>,
 {
@@ -90,8 +227,9 @@ where
     } else {
         Deserializer::from_felts(s)
     };
+    deserializer.montgomery_fields = options.montgomery_fields;
 
-    let t = T::deserialize(&mut deserializer)?;
+    let t = T::deserialize(&mut deserializer).map_err(|e| deserializer.locate(e))?;
 
     if let Some(lengths) = deserializer.lengths {
         let non_empty = lengths
@@ -104,15 +242,26 @@ where
         }
     }
 
-    if deserializer.input.is_empty() {
-        Ok(t)
-    } else {
-        // Err(Error::DataLeft) // TODO: This should be hard fall.
-        Ok(t)
+    match trailing {
+        TrailingFelts::Error if !deserializer.input.is_empty() => {
+            Err(Error::DataLeft(deserializer.input.len()))
+        }
+        TrailingFelts::Return => Ok((t, deserializer.input)),
+        _ => Ok((t, &[])),
+    }
+}
+
+// The low `len` bytes of a `ByteArray` word, failing if the word has more.
+fn word_bytes(word: Felt, len: usize) -> Result<Vec<u8>> {
+    let bytes = word.to_bytes_be();
+    let (high, low) = bytes.split_at(bytes.len() - len);
+    if high.iter().any(|b| *b != 0) {
+        return Err(Error::ValueExceededRange);
     }
+    Ok(low.to_vec())
 }
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     type Error = Error;
 
     fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
@@ -122,64 +271,76 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         unimplemented!()
     }
 
-    fn deserialize_bool<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        match self.take_uint::<u8>()? {
+            Some(0) => visitor.visit_bool(false),
+            Some(1) => visitor.visit_bool(true),
+            _ => Err(Error::ValueExceededRange),
+        }
     }
 
-    fn deserialize_i8<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        let value = self.take_int::<i8>()?;
+
+        visitor.visit_i8(value)
     }
 
-    fn deserialize_i16<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        let value = self.take_int::<i16>()?;
+
+        visitor.visit_i16(value)
     }
 
-    fn deserialize_i32<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        let value = self.take_int::<i32>()?;
+
+        visitor.visit_i32(value)
     }
 
-    fn deserialize_i64<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        let value = self.take_int::<i64>()?;
+
+        visitor.visit_i64(value)
     }
 
-    fn deserialize_u8<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        let value = self.take_uint::<u8>()?.ok_or(Error::ValueExceededRange)?;
+
+        visitor.visit_u8(value)
     }
 
-    fn deserialize_u16<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        let value = self.take_uint::<u16>()?.ok_or(Error::ValueExceededRange)?;
+
+        visitor.visit_u16(value)
     }
 
     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let value = self
-            .take()?
-            .to_string()
-            .parse::<u32>()
-            .map_err(|_| Error::ValueExceededRange)?;
+        let value = self.take_uint::<u32>()?.ok_or(Error::ValueExceededRange)?;
 
         visitor.visit_u32(value)
     }
@@ -188,15 +349,20 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        let value = self
-            .take()?
-            .to_string()
-            .parse::<u64>()
-            .map_err(|_| Error::ValueExceededRange)?;
+        let value = self.take_uint::<u64>()?.ok_or(Error::ValueExceededRange)?;
 
         visitor.visit_u64(value)
     }
 
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let value = self.take_uint::<u128>()?.ok_or(Error::ValueExceededRange)?;
+
+        visitor.visit_u128(value)
+    }
+
     fn deserialize_f32<V>(self, _visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
@@ -224,7 +390,11 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        let hex = format!("{:#x}", self.take()?);
+        let mut felt = self.take()?;
+        if self.montgomery {
+            felt = montgomery_to_felt(felt);
+        }
+        let hex = format!("{felt:#x}");
         visitor.visit_string(hex)
     }
 
@@ -235,20 +405,32 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         self.deserialize_str(visitor)
     }
 
-    // The `Serializer` implementation on the previous page serialized byte
-    // arrays as JSON arrays of bytes. Handle that representation here.
-    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value>
+    // Bytes use Cairo's `ByteArray` layout, see `Serializer::serialize_bytes`.
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        self.deserialize_byte_buf(visitor)
     }
 
-    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        let n_words = self.take_uint::<usize>()?.ok_or(Error::InvalidArrayLen)?;
+
+        let mut bytes = Vec::with_capacity((n_words + 1) * BYTES_PER_WORD);
+        for _ in 0..n_words {
+            bytes.extend_from_slice(&word_bytes(self.take()?, BYTES_PER_WORD)?);
+        }
+        let pending = self.take()?;
+        let pending_len = self
+            .take_uint::<usize>()?
+            .filter(|len| *len < BYTES_PER_WORD)
+            .ok_or(Error::InvalidArrayLen)?;
+        bytes.extend_from_slice(&word_bytes(pending, pending_len)?);
+
+        visitor.visit_byte_buf(bytes)
     }
 
     fn deserialize_option<V>(self, _visitor: V) -> Result<V::Value>
@@ -273,11 +455,14 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         unimplemented!()
     }
 
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, _visitor: V) -> Result<V::Value>
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        if name == skip::NAME {
+            return visitor.visit_unit();
+        }
+        visitor.visit_newtype_struct(self)
     }
 
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
@@ -309,11 +494,16 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     // Much like `deserialize_seq` but calls the visitors `visit_map` method
     // with a `MapAccess` implementation, rather than the visitor's `visit_seq`
     // method with a `SeqAccess` implementation.
-    fn deserialize_map<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        // Unlike sequences, maps are prefixed with their number of entries.
+        let len = match self.get_length() {
+            Some(len) => len,
+            None => self.take_uint::<usize>()?.ok_or(Error::InvalidArrayLen)?,
+        };
+        visitor.visit_map(DeserSeq::new_with_len(self, len))
     }
 
     // Structs look just like maps in JSON.
@@ -397,7 +587,14 @@ impl<'a, 'de> MapAccess<'de> for DeserStruct<'a, 'de> {
         V: serde::de::DeserializeSeed<'de>,
     {
         // Deserialize the value for the current field
+        let field = self.fields[self.index];
+        let montgomery = self.de.montgomery;
+        self.de.montgomery |= self.de.montgomery_fields.contains(&field);
+        self.de.path.push(PathSegment::Field(field));
         let value = seed.deserialize(&mut *self.de)?;
+        // Left in place on errors, to be reported.
+        self.de.path.pop();
+        self.de.montgomery = montgomery;
         self.index += 1;
         Ok(value)
     }
@@ -406,20 +603,58 @@ impl<'a, 'de> MapAccess<'de> for DeserStruct<'a, 'de> {
 struct DeserSeq<'a, 'de: 'a> {
     de: &'a mut Deserializer<'de>,
     left: Option<usize>,
+    // The serializer prefixes sequences with the number of felts they span, not the number of
+    // elements, so a prefix read from the input is a budget of felts rather than an element count.
+    until: Option<usize>,
+    index: usize,
 }
 
 impl<'a, 'de> DeserSeq<'a, 'de> {
     fn new(de: &'a mut Deserializer<'de>) -> Result<Self> {
         let len = de.get_length();
 
-        Ok(DeserSeq { de, left: len })
+        Ok(DeserSeq {
+            de,
+            left: len,
+            until: None,
+            index: 0,
+        })
     }
 
     fn new_with_len(de: &'a mut Deserializer<'de>, len: usize) -> Self {
         DeserSeq {
             de,
             left: Some(len),
+            until: None,
+            index: 0,
+        }
+    }
+
+    fn has_next(&mut self) -> Result<bool> {
+        if let Some(left) = self.left {
+            if left == 0 {
+                return Ok(false);
+            }
+            self.left = Some(left - 1);
+            return Ok(true);
+        }
+
+        if self.until.is_none() {
+            let len = self
+                .de
+                .take_uint::<usize>()?
+                .ok_or(Error::InvalidArrayLen)?;
+
+            let until = self
+                .de
+                .input
+                .len()
+                .checked_sub(len)
+                .ok_or(Error::NoDataLeft)?;
+            self.until = Some(until);
         }
+
+        Ok(self.until.is_some_and(|until| self.de.input.len() > until))
     }
 }
 
@@ -430,23 +665,36 @@ impl<'de, 'a> SeqAccess<'de> for DeserSeq<'a, 'de> {
     where
         T: DeserializeSeed<'de>,
     {
-        if let Some(left) = self.left {
-            Ok(if left > 0 {
-                self.left = Some(left - 1);
-                Some(seed.deserialize(&mut *self.de)?)
-            } else {
-                None
-            })
-        } else {
-            let len = self
-                .de
-                .take()?
-                .to_string()
-                .parse::<usize>()
-                .map_err(|_| Error::InvalidArrayLen)?;
+        if !self.has_next()? {
+            return Ok(None);
+        }
 
-            self.left = Some(len);
-            self.next_element_seed(seed)
+        self.de.path.push(PathSegment::Index(self.index));
+        let value = seed.deserialize(&mut *self.de)?;
+        self.de.path.pop();
+        self.index += 1;
+        Ok(Some(value))
+    }
+}
+
+impl<'de, 'a> MapAccess<'de> for DeserSeq<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.has_next()? {
+            Ok(Some(seed.deserialize(&mut *self.de)?))
+        } else {
+            Ok(None)
         }
     }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        seed.deserialize(&mut *self.de)
+    }
 }
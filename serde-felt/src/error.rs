@@ -6,7 +6,7 @@ use serde::{de, ser};
 pub enum Error {
     Message(String),
     Error,
-    DataLeft,
+    DataLeft(usize),
     NoDataLeft,
     InvalidArrayLen,
     ValueExceededRange,
@@ -15,6 +15,18 @@ pub enum Error {
     LengthSetButNotConsumed,
     LengthNotKnownAtSerialization,
     UnparsableString,
+    LengthMismatch {
+        field: String,
+        expected: usize,
+        got: usize,
+    },
+    /// An error of `from_felts` with the index of the felt being decoded and the path of
+    /// the field it belongs to, like `witness.leaves[3]`.
+    At {
+        position: usize,
+        path: String,
+        error: Box<Error>,
+    },
 }
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -34,7 +46,7 @@ impl Display for Error {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Error::Message(msg) => formatter.write_str(msg),
-            Error::DataLeft => formatter.write_str("unexpected end of input"),
+            Error::DataLeft(left) => write!(formatter, "{left} felts left unconsumed"),
             Error::Error => formatter.write_str("Invalid proof hex"),
             Error::NoDataLeft => formatter.write_str("unexpected end of input"),
             Error::InvalidArrayLen => formatter.write_str("invalid array length"),
@@ -48,8 +60,30 @@ impl Display for Error {
                 formatter.write_str("length not known at serialization")
             }
             Error::UnparsableString => formatter.write_str("non-parsable strings not supported"),
+            Error::LengthMismatch {
+                field,
+                expected,
+                got,
+            } => write!(formatter, "{field}: expected {expected} felts, got {got}"),
+            Error::At {
+                position,
+                path,
+                error,
+            } if path.is_empty() => write!(formatter, "{error} at felt {position}"),
+            Error::At {
+                position,
+                path,
+                error,
+            } => write!(formatter, "{error} at felt {position} ({path})"),
         }
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::At { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -1,12 +1,34 @@
+pub mod byte_array;
 mod deser;
 mod error;
 mod montgomery;
+mod reader;
 mod ser;
+pub mod short_string;
+pub mod skip;
+mod uint;
 
-pub use deser::{from_felts, from_felts_with_lengths};
-pub use error::Error;
+pub use deser::{
+    from_felts, from_felts_strict, from_felts_with_lengths, from_felts_with_options,
+    from_felts_with_remainder, from_felts_with_trailing, TrailingFelts,
+};
+pub use error::{Error, Result};
 pub use montgomery::*;
-pub use ser::to_felts;
+pub use reader::{DeserializeFelt, FeltReader, FromFeltSlice, SerializeFelt};
+pub use ser::{
+    short_string_to_felt, to_felts, to_felts_with_options, to_sink, to_writer, FeltSink,
+    SerializerOptions, WriteSink,
+};
+pub use serde_felt_derive::{DeserializeFelt, SerializeFelt};
+pub use uint::U256;
+
+// Lets the derives name `serde_felt` from inside this crate too.
+extern crate self as serde_felt;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use starknet_types_core::felt::Felt;
+}
 
 #[cfg(test)]
 mod tests;
@@ -21,6 +21,30 @@ pub fn montgomery_to_felt(montgomery_felt: Felt) -> Felt {
     Felt::from_raw(bytes)
 }
 
+/// Inverse of `montgomery_to_felt`.
+pub fn felt_to_montgomery(felt: Felt) -> Felt {
+    let mut bytes = [0u8; 32];
+    for (chunk, limb) in bytes.chunks_mut(8).zip(felt.to_raw()) {
+        chunk.copy_from_slice(&limb.to_be_bytes());
+    }
+
+    Felt::from_bytes_be(&bytes)
+}
+
+/// `montgomery_to_felt` over a slice, e.g. the leaves of a proof hex section.
+pub fn montgomery_to_felts(montgomery_felts: &[Felt]) -> Vec<Felt> {
+    montgomery_felts
+        .iter()
+        .copied()
+        .map(montgomery_to_felt)
+        .collect()
+}
+
+/// Inverse of `montgomery_to_felts`, for re-emitting a proof hex.
+pub fn felts_to_montgomery(felts: &[Felt]) -> Vec<Felt> {
+    felts.iter().copied().map(felt_to_montgomery).collect()
+}
+
 pub fn deserialize_montgomery<'de, D>(de: D) -> Result<Felt, D::Error>
 where
     D: Deserializer<'de>,
@@ -36,10 +60,7 @@ where
     let incorrectly_deserialized_felts =
         Vec::<Felt>::deserialize(de).map_err(serde::de::Error::custom)?;
 
-    Ok(incorrectly_deserialized_felts
-        .into_iter()
-        .map(montgomery_to_felt)
-        .collect())
+    Ok(montgomery_to_felts(&incorrectly_deserialized_felts))
 }
 
 #[test]
@@ -49,4 +70,8 @@ fn test() {
 
     let felt = montgomery_to_felt(Felt::from_hex(got).unwrap());
     assert_eq!(felt, Felt::from_hex(expected).unwrap());
+    assert_eq!(felt_to_montgomery(felt), Felt::from_hex(got).unwrap());
+
+    let felts = [felt, Felt::ZERO, Felt::MAX];
+    assert_eq!(montgomery_to_felts(&felts_to_montgomery(&felts)), felts);
 }
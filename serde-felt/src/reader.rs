@@ -0,0 +1,88 @@
+use std::borrow::Cow;
+
+use starknet_types_core::felt::Felt;
+
+use super::error::{Error, Result};
+
+/// Cursor over felts laid out without length prefixes, as read by `DeserializeFelt`.
+pub struct FeltReader<'a> {
+    felts: &'a [Felt],
+    offset: usize,
+}
+
+impl<'a> FeltReader<'a> {
+    pub fn new(felts: &'a [Felt]) -> Self {
+        Self { felts, offset: 0 }
+    }
+
+    pub fn take(&mut self, len: usize, field: &str) -> Result<&'a [Felt]> {
+        let Some(felts) = self.felts.get(self.offset..self.offset + len) else {
+            return Err(Error::LengthMismatch {
+                field: field.to_string(),
+                expected: len,
+                got: self.felts.len().saturating_sub(self.offset),
+            });
+        };
+        self.offset += len;
+        Ok(felts)
+    }
+
+    /// Fails unless every felt has been read.
+    pub fn finish(self) -> Result<()> {
+        if self.offset != self.felts.len() {
+            return Err(Error::LengthMismatch {
+                field: "input".to_string(),
+                expected: self.offset,
+                got: self.felts.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Decoding from felts whose vector lengths come from a context `C` instead of the input,
+/// usually derived with `#[derive(DeserializeFelt)]`. The result may borrow from the input
+/// for `'de`.
+pub trait DeserializeFelt<'de, C: ?Sized>: Sized {
+    fn deserialize_felt(reader: &mut FeltReader<'de>, ctx: &C) -> Result<Self>;
+}
+
+/// Vectors read with `#[felt(len_from)]`; the borrowed forms avoid copying the input.
+pub trait FromFeltSlice<'de> {
+    fn from_felt_slice(felts: &'de [Felt]) -> Self;
+}
+
+impl<'de> FromFeltSlice<'de> for Vec<Felt> {
+    fn from_felt_slice(felts: &'de [Felt]) -> Self {
+        felts.to_vec()
+    }
+}
+
+impl<'de> FromFeltSlice<'de> for &'de [Felt] {
+    fn from_felt_slice(felts: &'de [Felt]) -> Self {
+        felts
+    }
+}
+
+impl<'de> FromFeltSlice<'de> for Cow<'de, [Felt]> {
+    fn from_felt_slice(felts: &'de [Felt]) -> Self {
+        Cow::Borrowed(felts)
+    }
+}
+
+/// Encoding into felts without length prefixes, the inverse of `DeserializeFelt`.
+pub trait SerializeFelt {
+    fn serialize_felt(&self, out: &mut Vec<Felt>);
+}
+
+impl<'de, C: ?Sized> DeserializeFelt<'de, C> for Felt {
+    fn deserialize_felt(reader: &mut FeltReader<'de>, _ctx: &C) -> Result<Self> {
+        Ok(reader.take(1, "felt")?[0])
+    }
+}
+
+impl SerializeFelt for Felt {
+    fn serialize_felt(&self, out: &mut Vec<Felt>) {
+        out.push(*self);
+    }
+}
@@ -1,58 +1,252 @@
+use std::io;
+
 use serde::{ser, Serialize};
 use starknet_types_core::felt::Felt;
 
-use super::error::{Error, Result};
+use super::{
+    error::{Error, Result},
+    montgomery::felt_to_montgomery,
+    skip,
+};
+
+// Bytes in a full word of a Cairo `ByteArray`.
+pub(crate) const BYTES_PER_WORD: usize = 31;
+
+/// Encoding choices of the `Serializer`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SerializerOptions {
+    /// Encode strings that are not hex felts as Cairo short strings, the ASCII bytes read
+    /// as a big-endian felt, instead of failing.
+    pub short_strings: bool,
+    /// Struct fields whose felts are Montgomery-encoded, as Stone writes its leaves. The
+    /// deserializer converts them back when given the same options.
+    pub montgomery_fields: &'static [&'static str],
+    /// Write sequences without their length prefix, for verifiers reading fixed-size
+    /// arrays. Decode with `from_felts_with_lengths`, giving every sequence's length.
+    pub omit_seq_lengths: bool,
+    /// Struct fields whose sequence is written without its length prefix, as
+    /// `omit_seq_lengths` does for all of them. Sequences nested inside keep theirs.
+    pub unprefixed_fields: &'static [&'static str],
+}
+
+/// Destination of the felts written by `to_sink`.
+pub trait FeltSink {
+    fn write_felt(&mut self, felt: Felt) -> Result<()>;
+
+    // Overwrites an already written felt, for sinks that can.
+    #[doc(hidden)]
+    fn patch_felt(&mut self, _index: usize, _felt: Felt) -> bool {
+        false
+    }
+}
+
+impl FeltSink for Vec<Felt> {
+    fn write_felt(&mut self, felt: Felt) -> Result<()> {
+        self.push(felt);
+        Ok(())
+    }
+
+    fn patch_felt(&mut self, index: usize, felt: Felt) -> bool {
+        self[index] = felt;
+        true
+    }
+}
+
+/// Writes felts as space-separated decimals, the format of `StarkProof`'s `Display`.
+pub struct WriteSink<W> {
+    writer: W,
+    first: bool,
+}
+
+impl<W: io::Write> WriteSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            first: true,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: io::Write> FeltSink for WriteSink<W> {
+    fn write_felt(&mut self, felt: Felt) -> Result<()> {
+        let separator = if self.first { "" } else { " " };
+        self.first = false;
+        write!(self.writer, "{separator}{felt}").map_err(|e| Error::Message(e.to_string()))
+    }
+}
+
+impl<S: FeltSink + ?Sized> FeltSink for &mut S {
+    fn write_felt(&mut self, felt: Felt) -> Result<()> {
+        (**self).write_felt(felt)
+    }
+
+    fn patch_felt(&mut self, index: usize, felt: Felt) -> bool {
+        (**self).patch_felt(index, felt)
+    }
+}
+
+// Drops the felts of the counting pass of `to_sink`.
+struct Discard;
+
+impl FeltSink for Discard {
+    fn write_felt(&mut self, _felt: Felt) -> Result<()> {
+        Ok(())
+    }
+}
+
+// Where the felt length prefixing each sequence comes from.
+enum SeqLengths {
+    // Patched in once the sequence ends.
+    Patch,
+    // Counted by a first pass, in the order the sequences start.
+    Record(Vec<usize>),
+    Replay(std::vec::IntoIter<usize>),
+}
+
+pub struct Serializer<S = Vec<Felt>> {
+    output: S,
+    written: usize,
+    lengths: SeqLengths,
+    options: SerializerOptions,
+    // Inside one of `options.montgomery_fields`.
+    montgomery: bool,
+    // The value of one of `options.unprefixed_fields`, until its sequence starts.
+    unprefixed: bool,
+}
+
+impl<S: FeltSink> Serializer<S> {
+    fn new(output: S, lengths: SeqLengths, options: SerializerOptions) -> Self {
+        Self {
+            output,
+            written: 0,
+            lengths,
+            options,
+            montgomery: false,
+            unprefixed: false,
+        }
+    }
+
+    fn write(&mut self, felt: Felt) -> Result<()> {
+        self.written += 1;
+        self.output.write_felt(felt)
+    }
+
+    // Serializes a value on its own, as part of the current field.
+    fn nested<T>(&self, value: &T) -> Result<Vec<Felt>>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut serializer = Serializer::new(Vec::new(), SeqLengths::Patch, self.options);
+        serializer.montgomery = self.montgomery;
+        value.serialize(&mut serializer)?;
+        Ok(serializer.output)
+    }
+}
 
-pub struct Serializer {
-    output: Vec<Felt>,
+pub struct SeqSerializer<'a, S> {
+    se: &'a mut Serializer<S>,
+    // Felts written before the sequence, and the slot of its length when recording.
+    start: usize,
+    slot: usize,
+    prefixed: bool,
 }
 
-pub struct SeqSerializer<'a> {
-    se: &'a mut Serializer,
-    len_index: usize,
+// Buffers the serialized entries so they can be written sorted by key, whatever the
+// iteration order of the map.
+pub struct MapSerializer<'a, S> {
+    se: &'a mut Serializer<S>,
+    entries: Vec<(Vec<Felt>, Vec<Felt>)>,
 }
 
 pub fn to_felts<T>(value: &T) -> Result<Vec<Felt>>
 where
-    T: Serialize,
+    T: ?Sized + Serialize,
 {
-    let mut serializer = Serializer { output: Vec::new() };
+    to_felts_with_options(value, SerializerOptions::default())
+}
+
+pub fn to_felts_with_options<T>(value: &T, options: SerializerOptions) -> Result<Vec<Felt>>
+where
+    T: ?Sized + Serialize,
+{
+    let mut serializer = Serializer::new(Vec::new(), SeqLengths::Patch, options);
     value.serialize(&mut serializer)?;
     Ok(serializer.output)
 }
 
-impl<'a> ser::Serializer for &'a mut Serializer {
+/// Streams the serialization of `value` into `sink` without materializing it. Sequence
+/// lengths are counted by a first pass, so `value` is serialized twice.
+pub fn to_sink<T, S>(value: &T, options: SerializerOptions, sink: &mut S) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    S: FeltSink,
+{
+    let mut counter = Serializer::new(Discard, SeqLengths::Record(vec![]), options);
+    value.serialize(&mut counter)?;
+    let SeqLengths::Record(lengths) = counter.lengths else {
+        unreachable!()
+    };
+
+    let mut serializer = Serializer::new(sink, SeqLengths::Replay(lengths.into_iter()), options);
+    value.serialize(&mut serializer)
+}
+
+/// `to_sink` into a `WriteSink`.
+pub fn to_writer<T, W>(value: &T, writer: W) -> Result<()>
+where
+    T: ?Sized + Serialize,
+    W: io::Write,
+{
+    to_sink(
+        value,
+        SerializerOptions::default(),
+        &mut WriteSink::new(writer),
+    )
+}
+
+/// Encodes an ASCII string of at most 31 bytes as a Cairo short string.
+pub fn short_string_to_felt(s: &str) -> Result<Felt> {
+    if !s.is_ascii() || s.len() > BYTES_PER_WORD {
+        return Err(Error::UnparsableString);
+    }
+    Ok(Felt::from_bytes_be_slice(s.as_bytes()))
+}
+
+impl<'a, S: FeltSink> ser::Serializer for &'a mut Serializer<S> {
     type Ok = ();
     type Error = Error;
 
-    type SerializeSeq = SeqSerializer<'a>;
+    type SerializeSeq = SeqSerializer<'a, S>;
     type SerializeTuple = Self;
-    type SerializeTupleStruct = SeqSerializer<'a>;
+    type SerializeTupleStruct = SeqSerializer<'a, S>;
     type SerializeTupleVariant = Self;
-    type SerializeMap = SeqSerializer<'a>;
+    type SerializeMap = MapSerializer<'a, S>;
     type SerializeStruct = Self;
     type SerializeStructVariant = Self;
 
-    fn serialize_bool(self, _v: bool) -> Result<()> {
-        unimplemented!()
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.serialize_u64(v.into())
     }
 
-    fn serialize_i8(self, _v: i8) -> Result<()> {
-        unimplemented!()
+    // Negative values are written as their additive inverse in the field, as Cairo does.
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.serialize_i64(v.into())
     }
 
-    fn serialize_i16(self, _v: i16) -> Result<()> {
-        unimplemented!()
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.serialize_i64(v.into())
     }
 
-    fn serialize_i32(self, _v: i32) -> Result<()> {
-        unimplemented!()
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.serialize_i64(v.into())
     }
 
-    // Not particularly efficient but this is example code anyway. A more
-    // performant approach would be to use the `itoa` crate.
-    fn serialize_i64(self, _v: i64) -> Result<()> {
-        unimplemented!()
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.write(Felt::from(v))
     }
 
     fn serialize_u8(self, v: u8) -> Result<()> {
@@ -68,8 +262,11 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_u64(self, v: u64) -> Result<()> {
-        self.output.push(Felt::from(v));
-        Ok(())
+        self.write(Felt::from(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        self.write(Felt::from(v))
     }
 
     fn serialize_f32(self, _v: f32) -> Result<()> {
@@ -85,18 +282,29 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_str(self, v: &str) -> Result<()> {
-        let felt = Felt::from_hex(v).map_err(|_| Error::UnparsableString)?;
-        self.output.push(felt);
-        Ok(())
+        let felt = match Felt::from_hex(v) {
+            Ok(felt) if self.montgomery => felt_to_montgomery(felt),
+            Ok(felt) => felt,
+            Err(_) if self.options.short_strings => short_string_to_felt(v)?,
+            Err(_) => return Err(Error::UnparsableString),
+        };
+        self.write(felt)
     }
 
+    // Bytes use Cairo's `ByteArray` layout: the full 31-byte words as an array, then the
+    // pending word and its length in bytes.
     fn serialize_bytes(self, v: &[u8]) -> Result<()> {
-        use serde::ser::SerializeSeq;
-        let mut seq = self.serialize_seq(Some(v.len()))?;
-        for byte in v {
-            seq.serialize_element(byte)?;
+        let words = v.chunks(BYTES_PER_WORD);
+        let (full, pending): (Vec<_>, Vec<_>) =
+            words.partition(|word| word.len() == BYTES_PER_WORD);
+        let pending = pending.first().copied().unwrap_or_default();
+
+        self.write(Felt::from(full.len()))?;
+        for word in full {
+            self.write(Felt::from_bytes_be_slice(word))?;
         }
-        seq.end()
+        self.write(Felt::from_bytes_be_slice(pending))?;
+        self.write(Felt::from(pending.len()))
     }
 
     fn serialize_none(self) -> Result<()> {
@@ -129,10 +337,13 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self.serialize_str(variant)
     }
 
-    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
+        if name == skip::NAME {
+            return Ok(());
+        }
         value.serialize(self)
     }
 
@@ -151,12 +362,33 @@ impl<'a> ser::Serializer for &'a mut Serializer {
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
         let len = len.ok_or(Error::LengthNotKnownAtSerialization)?;
-        let len_index = self.output.len();
-        self.output.push(Felt::from(len)); // This is later overwritten with the actual length
+        if std::mem::take(&mut self.unprefixed) || self.options.omit_seq_lengths {
+            return Ok(SeqSerializer {
+                start: self.written,
+                se: self,
+                slot: 0,
+                prefixed: false,
+            });
+        }
+
+        let mut slot = 0;
+        let prefix = match &mut self.lengths {
+            // This is later overwritten with the actual length
+            SeqLengths::Patch => len,
+            SeqLengths::Record(lengths) => {
+                slot = lengths.len();
+                lengths.push(0);
+                len
+            }
+            SeqLengths::Replay(lengths) => lengths.next().ok_or(Error::Error)?,
+        };
+        self.write(Felt::from(prefix))?;
 
         Ok(SeqSerializer {
+            start: self.written,
             se: self,
-            len_index,
+            slot,
+            prefixed: true,
         })
     }
 
@@ -185,7 +417,10 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
-        self.serialize_seq(len)
+        Ok(MapSerializer {
+            se: self,
+            entries: Vec::with_capacity(len.unwrap_or_default()),
+        })
     }
 
     fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
@@ -203,7 +438,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeSeq for SeqSerializer<'a> {
+impl<'a, S: FeltSink> ser::SerializeSeq for SeqSerializer<'a, S> {
     type Ok = ();
     type Error = Error;
 
@@ -215,12 +450,24 @@ impl<'a> ser::SerializeSeq for SeqSerializer<'a> {
     }
 
     fn end(self) -> Result<()> {
-        self.se.output[self.len_index] = Felt::from(self.se.output.len() - self.len_index - 1);
+        if !self.prefixed {
+            return Ok(());
+        }
+        let len = self.se.written - self.start;
+        match &mut self.se.lengths {
+            SeqLengths::Patch => {
+                if !self.se.output.patch_felt(self.start - 1, Felt::from(len)) {
+                    return Err(Error::LengthNotKnownAtSerialization);
+                }
+            }
+            SeqLengths::Record(lengths) => lengths[self.slot] = len,
+            SeqLengths::Replay(_) => {}
+        }
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
+impl<S: FeltSink> ser::SerializeTuple for &mut Serializer<S> {
     type Ok = ();
     type Error = Error;
 
@@ -236,7 +483,7 @@ impl<'a> ser::SerializeTuple for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeTupleStruct for SeqSerializer<'a> {
+impl<'a, S: FeltSink> ser::SerializeTupleStruct for SeqSerializer<'a, S> {
     type Ok = ();
     type Error = Error;
 
@@ -252,7 +499,7 @@ impl<'a> ser::SerializeTupleStruct for SeqSerializer<'a> {
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+impl<S: FeltSink> ser::SerializeTupleVariant for &mut Serializer<S> {
     type Ok = ();
     type Error = Error;
 
@@ -268,7 +515,8 @@ impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeMap for SeqSerializer<'a> {
+// Maps are written as `len, key, value, ...`, where `len` is the number of entries.
+impl<'a, S: FeltSink> ser::SerializeMap for MapSerializer<'a, S> {
     type Ok = ();
     type Error = Error;
 
@@ -276,30 +524,52 @@ impl<'a> ser::SerializeMap for SeqSerializer<'a> {
     where
         T: ?Sized + Serialize,
     {
-        key.serialize(&mut *self.se)
+        self.entries.push((self.se.nested(key)?, vec![]));
+        Ok(())
     }
 
     fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut *self.se)
+        let (_, entry) = self
+            .entries
+            .last_mut()
+            .ok_or(Error::Message("map value without a key".to_string()))?;
+        *entry = self.se.nested(value)?;
+        Ok(())
     }
 
-    fn end(self) -> Result<()> {
+    fn end(mut self) -> Result<()> {
+        self.entries.sort();
+
+        self.se.write(Felt::from(self.entries.len()))?;
+        for felt in self
+            .entries
+            .into_iter()
+            .flat_map(|(key, value)| [key, value].concat())
+        {
+            self.se.write(felt)?;
+        }
         Ok(())
     }
 }
 
-impl<'a> ser::SerializeStruct for &'a mut Serializer {
+impl<S: FeltSink> ser::SerializeStruct for &mut Serializer<S> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        let montgomery = self.montgomery;
+        self.montgomery |= self.options.montgomery_fields.contains(&key);
+        self.unprefixed = self.options.unprefixed_fields.contains(&key);
+        let result = value.serialize(&mut **self);
+        self.montgomery = montgomery;
+        self.unprefixed = false;
+        result
     }
 
     fn end(self) -> Result<()> {
@@ -307,7 +577,7 @@ impl<'a> ser::SerializeStruct for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
+impl<S: FeltSink> ser::SerializeStructVariant for &mut Serializer<S> {
     type Ok = ();
     type Error = Error;
 
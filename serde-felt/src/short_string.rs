@@ -0,0 +1,25 @@
+//! `#[serde(with = "serde_felt::short_string")]` for `String` fields encoded as a single
+//! Cairo short string felt, whatever the `SerializerOptions`.
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use starknet_types_core::felt::Felt;
+
+use crate::short_string_to_felt;
+
+pub fn serialize<S>(value: &str, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    short_string_to_felt(value)
+        .map_err(serde::ser::Error::custom)?
+        .serialize(serializer)
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let bytes = Felt::deserialize(deserializer)?.to_bytes_be();
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    String::from_utf8(bytes[start..].to_vec()).map_err(de::Error::custom)
+}
@@ -1,9 +1,19 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+};
+
 use serde::{Deserialize, Serialize};
 use starknet_types_core::felt::Felt;
 
-use crate::{from_felts, from_felts_with_lengths, to_felts};
+use crate::{
+    felt_to_montgomery, from_felts, from_felts_strict, from_felts_with_lengths,
+    from_felts_with_options, from_felts_with_remainder, from_felts_with_trailing, to_felts,
+    to_felts_with_options, to_sink, to_writer, DeserializeFelt, FeltReader, SerializeFelt,
+    SerializerOptions, TrailingFelts, U256,
+};
 
-use super::error::Result;
+use super::error::{Error, Result};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 struct Basic {
@@ -30,6 +40,18 @@ struct WithArray {
     b: Felt,
 }
 
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct WithStructSequence {
+    a: Vec<Basic>,
+    b: Felt,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct WithMap {
+    a: BTreeMap<u32, Felt>,
+    b: Felt,
+}
+
 #[test]
 fn test_deser_basic() -> Result<()> {
     let value = Basic {
@@ -40,6 +62,7 @@ fn test_deser_basic() -> Result<()> {
 
     assert_eq!(to_felts(&value).unwrap(), expected);
     assert_eq!(from_felts::<Basic>(&expected).unwrap(), value);
+    assert_eq!(from_felts_strict::<Basic>(&expected).unwrap(), value);
     Ok(())
 }
 
@@ -101,3 +124,468 @@ fn test_deser_seq_with_len() -> Result<()> {
     assert_eq!(de, expected);
     Ok(())
 }
+
+#[test]
+fn test_deser_struct_seq() -> Result<()> {
+    let value = WithStructSequence {
+        a: vec![
+            Basic {
+                a: 11u64.into(),
+                b: 12u64.into(),
+            },
+            Basic {
+                a: 13u64.into(),
+                b: 14u64.into(),
+            },
+        ],
+        b: 2u64.into(),
+    };
+    // The prefix counts felts, not elements.
+    let expected = vec![
+        4u64.into(),
+        11u64.into(),
+        12u64.into(),
+        13u64.into(),
+        14u64.into(),
+        2u64.into(),
+    ];
+
+    assert_eq!(to_felts(&value).unwrap(), expected);
+    assert_eq!(from_felts::<WithStructSequence>(&expected).unwrap(), value);
+    Ok(())
+}
+
+#[test]
+fn test_deser_map() -> Result<()> {
+    let value = WithMap {
+        a: [(1, 11u64.into()), (2, 12u64.into())].into_iter().collect(),
+        b: 2u64.into(),
+    };
+    let expected = vec![
+        2u64.into(),
+        1u64.into(),
+        11u64.into(),
+        2u64.into(),
+        12u64.into(),
+        2u64.into(),
+    ];
+
+    assert_eq!(to_felts(&value).unwrap(), expected);
+    assert_eq!(from_felts::<WithMap>(&expected).unwrap(), value);
+    Ok(())
+}
+
+#[test]
+fn test_map_key_order() -> Result<()> {
+    let entries = (0..32u32).rev().map(|k| (k, Felt::from(k * 10)));
+    let hash_map = entries.clone().collect::<HashMap<_, _>>();
+    let btree_map = entries.collect::<BTreeMap<_, _>>();
+
+    let felts = to_felts(&hash_map)?;
+    assert_eq!(felts, to_felts(&btree_map)?);
+    assert_eq!(felts[..3], [32u64.into(), 0u64.into(), 0u64.into()]);
+    assert_eq!(from_felts::<HashMap<u32, Felt>>(&felts)?, hash_map);
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct WithUints {
+    a: u128,
+    b: U256,
+}
+
+#[test]
+fn test_deser_uints() -> Result<()> {
+    let value = WithUints {
+        a: u128::MAX,
+        b: U256 {
+            low: 1,
+            high: u128::MAX,
+        },
+    };
+    let expected = vec![Felt::from(u128::MAX), 1u64.into(), Felt::from(u128::MAX)];
+
+    assert_eq!(to_felts(&value)?, expected);
+    assert_eq!(from_felts::<WithUints>(&expected)?, value);
+
+    let too_big = vec![Felt::from(u128::MAX) + Felt::ONE, 1u64.into(), 0u64.into()];
+    assert!(from_felts::<WithUints>(&too_big).is_err());
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct WithSmallInts {
+    a: u8,
+    b: u16,
+    c: i8,
+    d: i32,
+    e: i64,
+    f: bool,
+}
+
+#[test]
+fn test_deser_small_ints() -> Result<()> {
+    let value = WithSmallInts {
+        a: u8::MAX,
+        b: 7,
+        c: -1,
+        d: i32::MIN,
+        e: i64::MAX,
+        f: true,
+    };
+    let felts = to_felts(&value)?;
+    assert_eq!(felts[2], -Felt::ONE);
+    assert_eq!(from_felts::<WithSmallInts>(&felts)?, value);
+
+    let mut out_of_range = felts.clone();
+    out_of_range[0] = Felt::from(256u64);
+    assert!(from_felts::<WithSmallInts>(&out_of_range).is_err());
+    let mut out_of_range = felts.clone();
+    out_of_range[2] = Felt::from(-129i64);
+    assert!(from_felts::<WithSmallInts>(&out_of_range).is_err());
+    let mut not_bool = felts;
+    not_bool[5] = Felt::TWO;
+    assert!(from_felts::<WithSmallInts>(&not_bool).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_u256_bytes() {
+    let value = U256 { low: 2, high: 1 };
+    let bytes = value.to_be_bytes();
+    assert_eq!((bytes[15], bytes[31]), (1, 2));
+    assert_eq!(U256::from_be_bytes(bytes), value);
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct WithByteArrays {
+    #[serde(with = "crate::byte_array")]
+    a: String,
+    #[serde(with = "crate::byte_array")]
+    b: Vec<u8>,
+}
+
+#[test]
+fn test_deser_byte_array() -> Result<()> {
+    let value = WithByteArrays {
+        a: "This is a string longer than thirty-one bytes".to_string(),
+        b: vec![],
+    };
+    let felts = to_felts(&value)?;
+
+    assert_eq!(felts[0], 1u64.into());
+    assert_eq!(
+        felts[1],
+        Felt::from_bytes_be_slice(&value.a.as_bytes()[..31])
+    );
+    assert_eq!(
+        felts[2],
+        Felt::from_bytes_be_slice(&value.a.as_bytes()[31..])
+    );
+    assert_eq!(felts[3], 14u64.into());
+    assert_eq!(felts[4..], [0u64.into(), 0u64.into(), 0u64.into()]);
+    assert_eq!(from_felts::<WithByteArrays>(&felts)?, value);
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct WithShortString {
+    #[serde(with = "crate::short_string")]
+    a: String,
+    b: String,
+}
+
+#[test]
+fn test_short_strings() -> Result<()> {
+    let value = WithShortString {
+        a: "recursive".to_string(),
+        b: "0x2a".to_string(),
+    };
+    let expected = vec![Felt::from_bytes_be_slice(b"recursive"), 42u64.into()];
+
+    assert_eq!(to_felts(&value)?, expected);
+    assert_eq!(from_felts::<WithShortString>(&expected)?, value);
+
+    let options = SerializerOptions {
+        short_strings: true,
+        ..Default::default()
+    };
+    assert!(to_felts(&"keccak_160_lsb").is_err());
+    assert_eq!(
+        to_felts_with_options(&"keccak_160_lsb", options)?,
+        vec![Felt::from_bytes_be_slice(b"keccak_160_lsb")]
+    );
+    assert!(to_felts_with_options(&"z".repeat(32), options).is_err());
+    Ok(())
+}
+
+struct Sizes {
+    values: usize,
+}
+
+#[derive(SerializeFelt, DeserializeFelt, PartialEq, Debug)]
+struct Pair {
+    a: Felt,
+    b: Felt,
+}
+
+#[derive(SerializeFelt, DeserializeFelt, PartialEq, Debug)]
+#[felt(context = "Sizes")]
+struct WithLengths {
+    a: Felt,
+    #[felt(len_from = "values")]
+    b: Vec<Felt>,
+    #[felt(len_from = "values", montgomery)]
+    c: Vec<Felt>,
+    d: Pair,
+}
+
+#[test]
+fn test_derive_lengths() -> Result<()> {
+    let value = WithLengths {
+        a: 1u64.into(),
+        b: vec![2u64.into(), 3u64.into()],
+        c: vec![4u64.into(), 5u64.into()],
+        d: Pair {
+            a: 6u64.into(),
+            b: 7u64.into(),
+        },
+    };
+    let mut felts = vec![];
+    value.serialize_felt(&mut felts);
+
+    assert_eq!(felts.len(), 7);
+    assert_eq!(felts[3], felt_to_montgomery(4u64.into()));
+
+    let sizes = Sizes { values: 2 };
+    let mut reader = FeltReader::new(&felts);
+    assert_eq!(WithLengths::deserialize_felt(&mut reader, &sizes)?, value);
+    reader.finish()?;
+
+    let mut reader = FeltReader::new(&felts[..4]);
+    let err = WithLengths::deserialize_felt(&mut reader, &sizes).unwrap_err();
+    assert_eq!(err.to_string(), "c: expected 2 felts, got 1");
+    Ok(())
+}
+
+#[test]
+fn test_strict_data_left() {
+    let felts = vec![Felt::ONE, Felt::TWO, Felt::THREE];
+
+    assert!(from_felts::<Basic>(&felts).is_ok());
+    let err = from_felts_strict::<Basic>(&felts).unwrap_err();
+    assert!(matches!(err, Error::DataLeft(1)));
+    assert_eq!(err.to_string(), "1 felts left unconsumed");
+}
+
+#[test]
+fn test_remainder() -> Result<()> {
+    let felts = vec![Felt::ONE, Felt::TWO, Felt::THREE];
+
+    let (value, rest) = from_felts_with_remainder::<Basic>(&felts)?;
+    assert_eq!(
+        value,
+        Basic {
+            a: Felt::ONE,
+            b: Felt::TWO
+        }
+    );
+    assert_eq!(rest, [Felt::THREE]);
+
+    let (_, rest) = from_felts_with_remainder::<(Felt, Felt, Felt)>(&felts)?;
+    assert!(rest.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_trailing_felts() -> Result<()> {
+    let felts = vec![Felt::ONE, Felt::TWO, Felt::THREE];
+
+    let (_, rest) = from_felts_with_trailing::<Basic>(&felts, TrailingFelts::Ignore)?;
+    assert!(rest.is_empty());
+    let (_, rest) = from_felts_with_trailing::<Basic>(&felts, TrailingFelts::Return)?;
+    assert_eq!(rest, [Felt::THREE]);
+    let err = from_felts_with_trailing::<Basic>(&felts, TrailingFelts::Error).unwrap_err();
+    assert!(matches!(err, Error::DataLeft(1)));
+
+    from_felts_with_trailing::<Basic>(&felts[..2].to_vec(), TrailingFelts::Error)?;
+    Ok(())
+}
+
+#[derive(SerializeFelt, DeserializeFelt, PartialEq, Debug)]
+#[felt(context = "Sizes")]
+struct Borrowed<'a> {
+    #[felt(len_from = "values")]
+    a: &'a [Felt],
+    #[felt(len_from = "values")]
+    b: Cow<'a, [Felt]>,
+    c: Pair,
+}
+
+#[test]
+fn test_derive_borrowed() -> Result<()> {
+    let felts = (1..=6u64).map(Felt::from).collect::<Vec<_>>();
+    let mut reader = FeltReader::new(&felts);
+    let value = Borrowed::deserialize_felt(&mut reader, &Sizes { values: 2 })?;
+    reader.finish()?;
+
+    assert_eq!(value.a.as_ptr(), felts.as_ptr());
+    assert!(matches!(value.b, Cow::Borrowed(b) if b == &felts[2..4]));
+
+    let mut out = vec![];
+    value.serialize_felt(&mut out);
+    assert_eq!(out, felts);
+    Ok(())
+}
+
+#[test]
+fn test_montgomery_fields() -> Result<()> {
+    let value = Nested {
+        a: 1u64.into(),
+        b: Basic {
+            a: 2u64.into(),
+            b: 3u64.into(),
+        },
+        c: 4u64.into(),
+    };
+    let options = SerializerOptions {
+        montgomery_fields: &["b"],
+        ..Default::default()
+    };
+
+    let felts = to_felts_with_options(&value, options)?;
+    assert_eq!(
+        felts,
+        vec![
+            1u64.into(),
+            felt_to_montgomery(2u64.into()),
+            felt_to_montgomery(3u64.into()),
+            4u64.into(),
+        ]
+    );
+    assert_eq!(from_felts_with_options::<Nested>(&felts, options)?, value);
+    assert_ne!(from_felts::<Nested>(&felts)?, value);
+    Ok(())
+}
+
+#[test]
+fn test_omit_seq_lengths() -> Result<()> {
+    let value = WithSequence {
+        a: vec![1u64.into(), 2u64.into()],
+        b: 3u64.into(),
+    };
+    let unprefixed = vec![1u64.into(), 2u64.into(), 3u64.into()];
+
+    let options = SerializerOptions {
+        unprefixed_fields: &["a"],
+        ..Default::default()
+    };
+    assert_eq!(to_felts_with_options(&value, options)?, unprefixed);
+    let options = SerializerOptions {
+        omit_seq_lengths: true,
+        ..Default::default()
+    };
+    assert_eq!(to_felts_with_options(&value, options)?, unprefixed);
+    let mut streamed = vec![];
+    to_sink(&value, options, &mut streamed)?;
+    assert_eq!(streamed, unprefixed);
+
+    let lengths = HashMap::from([("a".to_string(), vec![2])]);
+    assert_eq!(
+        from_felts_with_lengths::<WithSequence>(&unprefixed, lengths)?,
+        value
+    );
+
+    // Only the field's own sequence loses its prefix.
+    let nested = vec![vec![Felt::ONE]];
+    let options = SerializerOptions {
+        unprefixed_fields: &["a"],
+        ..Default::default()
+    };
+    let felts = to_felts_with_options(&(WithNested { a: nested }), options)?;
+    assert_eq!(felts, vec![Felt::ONE, Felt::ONE]);
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct WithNested {
+    a: Vec<Vec<Felt>>,
+}
+
+#[test]
+fn test_streaming() -> Result<()> {
+    let value = WithStructSequence {
+        a: vec![
+            Basic {
+                a: 1u64.into(),
+                b: 2u64.into(),
+            },
+            Basic {
+                a: 3u64.into(),
+                b: 4u64.into(),
+            },
+        ],
+        b: 5u64.into(),
+    };
+    let map = BTreeMap::from([(2u32, vec![Felt::ONE]), (1u32, vec![])]);
+    let options = SerializerOptions {
+        montgomery_fields: &["b"],
+        ..Default::default()
+    };
+
+    let mut sink = vec![];
+    to_sink(&value, options, &mut sink)?;
+    assert_eq!(sink, to_felts_with_options(&value, options)?);
+
+    let mut sink = vec![];
+    to_sink(&map, SerializerOptions::default(), &mut sink)?;
+    assert_eq!(sink, to_felts(&map)?);
+
+    let mut written = vec![];
+    to_writer(&(1u64, vec![2u64, 3]), &mut written)?;
+    assert_eq!(String::from_utf8(written).unwrap(), "1 2 2 3");
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct WithUintSequence {
+    a: Felt,
+    b: Vec<u64>,
+    c: Felt,
+}
+
+#[test]
+fn test_error_position() {
+    let felts = vec![Felt::ONE, Felt::TWO, Felt::ONE, Felt::MAX, Felt::THREE];
+
+    let err = from_felts::<WithUintSequence>(&felts).unwrap_err();
+    assert!(
+        matches!(&err, Error::At { position: 3, error, .. } if matches!(**error, Error::ValueExceededRange))
+    );
+    assert_eq!(err.to_string(), "value exceeded range at felt 3 (b[1])");
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct WithSkipped {
+    a: Felt,
+    #[serde(default, with = "crate::skip")]
+    b: Vec<u64>,
+    c: Felt,
+}
+
+#[test]
+fn test_skip() -> Result<()> {
+    let value = WithSkipped {
+        a: Felt::ONE,
+        b: vec![1, 2],
+        c: Felt::TWO,
+    };
+
+    let felts = to_felts(&value)?;
+    assert_eq!(felts, vec![Felt::ONE, Felt::TWO]);
+    assert_eq!(
+        from_felts_strict::<WithSkipped>(&felts)?,
+        WithSkipped { b: vec![], ..value }
+    );
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// A 256-bit unsigned integer laid out like Cairo's `u256`: the low 128 bits, then the high.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct U256 {
+    pub low: u128,
+    pub high: u128,
+}
+
+impl U256 {
+    pub fn from_be_bytes(bytes: [u8; 32]) -> Self {
+        let (high, low) = bytes.split_at(16);
+        U256 {
+            low: u128::from_be_bytes(low.try_into().unwrap()),
+            high: u128::from_be_bytes(high.try_into().unwrap()),
+        }
+    }
+
+    pub fn to_be_bytes(self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        bytes[..16].copy_from_slice(&self.high.to_be_bytes());
+        bytes[16..].copy_from_slice(&self.low.to_be_bytes());
+        bytes
+    }
+}
+
+impl From<u128> for U256 {
+    fn from(low: u128) -> Self {
+        U256 { low, high: 0 }
+    }
+}